@@ -0,0 +1,65 @@
+use wolfram_library_link::{
+    self as wll, sys::mint, AsyncTaskObject, BackpressurePolicy, BoundedEventSender,
+    DataStore, EventQueueStats,
+};
+
+/// Start an asynchronous task that raises an `"item"` event for each of the numbers 1
+/// through `count`, allowing at most `capacity` unacknowledged events to be outstanding.
+///
+/// `policy` is one of `"Block"`, `"DropNewest"`, or `"CoalesceLatest"`.
+///
+/// When finished, the task raises a `"done"` event containing the statistics of the
+/// bounded event sender.
+///
+/// See `RustLink/Examples/AsyncExamples.wlt` for example usage of this function.
+#[wll::export]
+fn start_producer(count: mint, capacity: mint, policy: String) -> mint {
+    let policy = match policy.as_str() {
+        "Block" => BackpressurePolicy::Block,
+        "DropNewest" => BackpressurePolicy::DropNewest,
+        "CoalesceLatest" => BackpressurePolicy::CoalesceLatest,
+        _ => panic!("unknown backpressure policy: {}", policy),
+    };
+
+    let task = AsyncTaskObject::spawn_with_thread(move |task: AsyncTaskObject| {
+        let events = task.bounded_event_sender(capacity as usize, policy);
+
+        for i in 1..=count {
+            let mut data = DataStore::new();
+            data.add_i64(i);
+
+            events.raise_async_event("item", data);
+        }
+
+        task.raise_async_event("done", stats_data_store(events.stats()));
+    });
+
+    task.id()
+}
+
+/// Acknowledge an `"item"` event raised by the task with ID `task_id`.
+#[wll::export]
+fn acknowledge_event(task_id: mint) -> bool {
+    BoundedEventSender::acknowledge(task_id)
+}
+
+fn stats_data_store(stats: EventQueueStats) -> DataStore {
+    let EventQueueStats {
+        capacity: _,
+        outstanding,
+        max_outstanding,
+        raised,
+        acknowledged,
+        dropped,
+        coalesced,
+    } = stats;
+
+    let mut data = DataStore::new();
+    data.add_named_i64("outstanding", outstanding as i64);
+    data.add_named_i64("max_outstanding", max_outstanding as i64);
+    data.add_named_i64("raised", raised as i64);
+    data.add_named_i64("acknowledged", acknowledged as i64);
+    data.add_named_i64("dropped", dropped as i64);
+    data.add_named_i64("coalesced", coalesced as i64);
+    data
+}
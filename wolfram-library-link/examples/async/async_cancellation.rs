@@ -0,0 +1,74 @@
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use once_cell::sync::Lazy;
+
+use wolfram_library_link::{
+    self as wll, sys::mint, AsyncTaskJoinHandle, AsyncTaskObject, CancellationSignal,
+    DataStore,
+};
+
+/// Running ticker tasks, keyed by task ID.
+static TICKERS: Lazy<Mutex<HashMap<mint, (AsyncTaskObject, AsyncTaskJoinHandle)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Start an asynchronous task that raises a `"tick"` event every `interval_ms`
+/// milliseconds, until the task is stopped.
+///
+/// The task can be stopped either from the Wolfram Language, using
+/// `StopAsynchronousTask` or `RemoveAsynchronousTask`, or by calling [`stop_ticker()`].
+///
+/// See `RustLink/Examples/AsyncExamples.wlt` for example usage of this function.
+#[wll::export]
+fn start_ticker(interval_ms: mint) -> mint {
+    let interval = Duration::from_millis(interval_ms as u64);
+
+    let (task, handle) = AsyncTaskObject::spawn_with_thread_joinable(
+        move |task: AsyncTaskObject, cancelled: CancellationSignal| {
+            let mut count: i64 = 0;
+
+            while !cancelled.wait_timeout(interval) {
+                count += 1;
+
+                let mut data = DataStore::new();
+                data.add_i64(count);
+
+                task.raise_async_event("tick", data);
+            }
+        },
+    );
+
+    let task_id = task.id();
+
+    TICKERS.lock().unwrap().insert(task_id, (task, handle));
+
+    task_id
+}
+
+/// Remove the ticker task with ID `task_id`, and wait for its background thread to
+/// finish.
+///
+/// Returns `True` if the background thread finished without panicking, and `False` if
+/// `task_id` is not the ID of a task started by [`start_ticker()`].
+#[wll::export]
+fn stop_ticker(task_id: mint) -> bool {
+    let (task, handle) = match TICKERS.lock().unwrap().remove(&task_id) {
+        Some(ticker) => ticker,
+        None => return false,
+    };
+
+    task.remove();
+
+    handle.join().is_ok()
+}
+
+/// Returns `True` if the background thread of the ticker task with ID `task_id` has
+/// finished, without removing the task.
+///
+/// Returns `False` if `task_id` is not the ID of a task started by [`start_ticker()`].
+#[wll::export]
+fn ticker_finished(task_id: mint) -> bool {
+    match TICKERS.lock().unwrap().get(&task_id) {
+        Some((_, handle)) => handle.is_finished(),
+        None => false,
+    }
+}
@@ -0,0 +1,38 @@
+use std::{thread, time::Duration};
+
+use wolfram_library_link::{self as wll, sys::mint, AsyncTaskObject, DataStore};
+
+wll::generate_loader![load_async_export_functions];
+
+/// Raise a `"tick"` event `count` times, waiting `interval_ms` milliseconds between
+/// each event.
+///
+/// This function is run on a background thread. The library function generated by
+/// `#[export(async)]` takes the `count` and `interval_ms` arguments, starts a new
+/// asynchronous task, and returns the task ID.
+///
+/// See `RustLink/Examples/AsyncExamples.wlt` for example usage of this function.
+#[wll::export(async)]
+fn tick(task: AsyncTaskObject, count: mint, interval_ms: mint) {
+    for i in 1..=count {
+        if !task.is_alive() {
+            break;
+        }
+
+        thread::sleep(Duration::from_millis(interval_ms as u64));
+
+        let mut data = DataStore::new();
+        data.add_i64(i);
+
+        task.raise_async_event("tick", data);
+    }
+}
+
+/// Raise a single `"greeting"` event containing a message addressed to `name`.
+#[wll::export(async)]
+fn greet(task: AsyncTaskObject, name: String) {
+    let mut data = DataStore::new();
+    data.add_str(&format!("Hello, {}!", name));
+
+    task.raise_async_event("greeting", data);
+}
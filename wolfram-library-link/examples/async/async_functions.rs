@@ -0,0 +1,60 @@
+use std::{
+    sync::atomic::{AtomicI64, Ordering},
+    thread,
+    time::Duration,
+};
+
+use futures::channel::oneshot;
+
+use wolfram_library_link as wll;
+
+/// Number of [`wait_for_abort_async()`] futures that have been dropped.
+static DROPPED_COUNT: AtomicI64 = AtomicI64::new(0);
+
+/// Returns a future that completes after `duration` has elapsed.
+///
+/// This stands in for an async-only API, like an HTTP client or database driver.
+async fn sleep(duration: Duration) {
+    let (sender, receiver) = oneshot::channel();
+
+    thread::spawn(move || {
+        thread::sleep(duration);
+        let _ = sender.send(());
+    });
+
+    let _ = receiver.await;
+}
+
+/// Returns the square of `x`, after waiting for `delay_ms` milliseconds.
+///
+/// The calling evaluation blocks until the returned future completes.
+#[wll::export]
+async fn delayed_square(x: i64, delay_ms: i64) -> i64 {
+    sleep(Duration::from_millis(delay_ms as u64)).await;
+
+    x * x
+}
+
+/// This function will wait forever until a Wolfram Language abort occurs, at which
+/// point the future is dropped.
+#[wll::export]
+async fn wait_for_abort_async() -> i64 {
+    /// Records that the future was dropped when it is cancelled.
+    struct OnDrop;
+
+    impl Drop for OnDrop {
+        fn drop(&mut self) {
+            DROPPED_COUNT.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let _on_drop = OnDrop;
+
+    futures::future::pending::<i64>().await
+}
+
+/// Returns the number of [`wait_for_abort_async()`] futures that have been dropped.
+#[wll::export]
+fn dropped_count() -> i64 {
+    DROPPED_COUNT.load(Ordering::SeqCst)
+}
@@ -0,0 +1,66 @@
+use wolfram_library_link::{
+    self as wll,
+    expr::{Expr, ExprKind, Symbol},
+    sys::mint,
+    AsyncTaskObject, DataStore,
+};
+
+/// Start the main thread executor.
+///
+/// See `RustLink/Examples/AsyncExamples.wlt` for example usage of this function.
+#[wll::export]
+fn start_executor() -> mint {
+    wll::start_main_thread_executor()
+}
+
+/// Run any closures that are waiting to be run on the main thread.
+///
+/// This is called by the event handler of the task created by [`start_executor()`].
+#[wll::export]
+fn run_jobs() -> mint {
+    wll::run_main_thread_jobs() as mint
+}
+
+/// Start an asynchronous task whose background thread evaluates `code` using the
+/// Wolfram Kernel, and raises a `"result"` event containing the result as a string.
+///
+/// If the evaluation could not be run, e.g. because the executor task was removed, an
+/// `"error"` event containing a message is raised instead.
+#[wll::export]
+fn start_background_evaluation(code: String) -> mint {
+    let task = AsyncTaskObject::spawn_with_thread(move |task: AsyncTaskObject| {
+        // ToString[ToExpression[code]]
+        let expr = Expr::normal(Symbol::new("System`ToString"), vec![Expr::normal(
+            Symbol::new("System`ToExpression"),
+            vec![Expr::string(code.clone())],
+        )]);
+
+        // evaluate() can only be called from the main Kernel thread.
+        let result: Expr =
+            match wll::spawn_on_main_thread(move || wll::evaluate(&expr)).wait() {
+                Ok(result) => result,
+                Err(payload) => {
+                    let message = match payload.downcast_ref::<&str>() {
+                        Some(message) => message.to_string(),
+                        None => "evaluation panicked".to_owned(),
+                    };
+
+                    let mut data = DataStore::new();
+                    data.add_str(&message);
+                    task.raise_async_event("error", data);
+                    return;
+                },
+            };
+
+        let mut data = DataStore::new();
+
+        match result.kind() {
+            ExprKind::String(string) => data.add_str(string),
+            _ => panic!("expected String result, got: {}", result),
+        }
+
+        task.raise_async_event("result", data);
+    });
+
+    task.id()
+}
@@ -0,0 +1,52 @@
+use std::{thread, time::Duration};
+
+use wolfram_library_link::{self as wll, sys::mint, AsyncTaskObject, Progress};
+
+/// Returns Wolfram Language code for a function that runs a task started by one of the
+/// functions below, displays its progress, and returns its result.
+#[wll::export]
+fn progress_handler_code() -> String {
+    Progress::wolfram_handler_code().to_owned()
+}
+
+/// Start an asynchronous task that slowly computes the sum of the integers 1 through
+/// `n`, reporting its progress as it goes.
+///
+/// See `RustLink/Examples/AsyncExamples.wlt` for example usage of this function.
+#[wll::export]
+fn start_slow_sum(n: mint) -> mint {
+    let task = AsyncTaskObject::spawn_with_progress(move |progress: &mut Progress| {
+        let mut sum: i64 = 0;
+
+        for i in 1..=n {
+            if progress.is_cancelled() {
+                return Err("cancelled".to_owned());
+            }
+
+            sum += i;
+
+            progress.update_with_message(i as f64 / n as f64, &format!("added {}", i));
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        Ok(sum)
+    });
+
+    task.id()
+}
+
+/// Start an asynchronous task that fails if `n` is negative, and otherwise returns `n`.
+#[wll::export]
+fn start_checked(n: mint) -> mint {
+    let task = AsyncTaskObject::spawn_with_progress(move |progress: &mut Progress| {
+        progress.update(0.5);
+
+        if n < 0 {
+            return Err(format!("expected a non-negative integer, got {}", n));
+        }
+
+        Ok(n)
+    });
+
+    task.id()
+}
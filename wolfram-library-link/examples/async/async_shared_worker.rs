@@ -0,0 +1,80 @@
+use std::{
+    sync::{mpsc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use once_cell::sync::Lazy;
+
+use wolfram_library_link::{
+    self as wll, sys::mint, AsyncEventSender, AsyncTaskObject, DataStore,
+};
+
+/// A single background thread that raises events for every task started by
+/// [`start_countdown()`].
+static WORKER: Lazy<Mutex<mpsc::Sender<Countdown>>> = Lazy::new(|| {
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || worker_thread_function(receiver));
+
+    Mutex::new(sender)
+});
+
+struct Countdown {
+    events: AsyncEventSender,
+    remaining: mint,
+}
+
+/// Start an asynchronous task that raises a `"tick"` event `count` times, counting down
+/// to 1.
+///
+/// The task is created without a dedicated thread. Instead, its [`AsyncEventSender`] is
+/// given to a single shared worker thread that services all countdown tasks.
+///
+/// See `RustLink/Examples/AsyncExamples.wlt` for example usage of this function.
+#[wll::export]
+fn start_countdown(count: mint) -> mint {
+    let (task, events) = AsyncTaskObject::new_without_thread();
+
+    WORKER
+        .lock()
+        .unwrap()
+        .send(Countdown {
+            events,
+            remaining: count,
+        })
+        .expect("countdown worker thread has stopped");
+
+    task.id()
+}
+
+fn worker_thread_function(receiver: mpsc::Receiver<Countdown>) {
+    let mut countdowns: Vec<Countdown> = Vec::new();
+
+    loop {
+        // Accept any newly started countdowns, waiting for one if there is currently
+        // no work to do.
+        if countdowns.is_empty() {
+            match receiver.recv() {
+                Ok(countdown) => countdowns.push(countdown),
+                Err(mpsc::RecvError) => return,
+            }
+        }
+        countdowns.extend(receiver.try_iter());
+
+        for countdown in &mut countdowns {
+            let mut data = DataStore::new();
+            data.add_i64(countdown.remaining);
+
+            countdown.events.raise_async_event("tick", data);
+            countdown.remaining -= 1;
+        }
+
+        // Stop servicing countdowns that have finished, or whose task was stopped from
+        // the Wolfram Language.
+        countdowns
+            .retain(|countdown| countdown.remaining > 0 && countdown.events.is_alive());
+
+        thread::sleep(Duration::from_millis(10));
+    }
+}
@@ -0,0 +1,153 @@
+use std::{
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    thread,
+};
+
+use futures::{
+    future,
+    stream::{self, StreamExt},
+};
+
+use wolfram_library_link::{self as wll, sys::mint, AsyncTaskObject, DataStore};
+
+/// Set when the stream created by [`start_pending_stream()`] is dropped.
+static PENDING_STREAM_DROPPED: AtomicBool = AtomicBool::new(false);
+
+/// Set when the future created by [`start_cancellation_watchers()`] is dropped.
+static PENDING_FUTURE_DROPPED: AtomicBool = AtomicBool::new(false);
+
+/// Number of [`start_cancellation_watchers()`] threads that saw their task cancelled.
+static CANCELLED_WATCHERS: AtomicUsize = AtomicUsize::new(0);
+
+/// Start an asynchronous task that raises a `"count"` event for each of the numbers 1
+/// through `n`, and then finishes.
+///
+/// Unlike the `async_file_watcher.rs` example, this task does not use a dedicated
+/// thread. The stream is driven by an executor that is shared by every task spawned
+/// using [`AsyncTaskObject::spawn_stream()`].
+///
+/// See `RustLink/Examples/AsyncExamples.wlt` for example usage of this function.
+#[wll::export]
+fn start_counter_stream(n: mint) -> mint {
+    let counter = stream::iter(1..=n).map(|i| {
+        let mut data = DataStore::new();
+        data.add_i64(i);
+
+        ("count".to_owned(), data)
+    });
+
+    let sender = AsyncTaskObject::spawn_stream(counter);
+
+    sender.task_id()
+}
+
+/// Start an asynchronous task that computes the sum of the numbers 1 through `n` in a
+/// [`Future`][std::future::Future], and raises a `"sum"` event with the result.
+#[wll::export]
+fn start_sum_future(n: mint) -> mint {
+    let sender = AsyncTaskObject::spawn_future(move |task: AsyncTaskObject| async move {
+        let sum: mint = stream::iter(1..=n)
+            .fold(0, |acc, i| async move { acc + i })
+            .await;
+
+        let mut data = DataStore::new();
+        data.add_i64(sum);
+
+        task.raise_async_event("sum", data);
+    });
+
+    sender.task_id()
+}
+
+/// Start an asynchronous task whose future panics, which causes an `"error"` event to
+/// be raised with the panic message.
+#[wll::export]
+fn start_panicking_future() -> mint {
+    let sender = AsyncTaskObject::spawn_future(|_: AsyncTaskObject| async {
+        panic!("future failed");
+    });
+
+    sender.task_id()
+}
+
+/// Start an asynchronous task with a stream that never produces an item.
+///
+/// The stream is dropped when the task is removed, which can be checked using
+/// [`pending_stream_dropped()`].
+#[wll::export]
+fn start_pending_stream() -> mint {
+    struct OnDrop;
+
+    impl Drop for OnDrop {
+        fn drop(&mut self) {
+            PENDING_STREAM_DROPPED.store(true, Ordering::SeqCst);
+        }
+    }
+
+    PENDING_STREAM_DROPPED.store(false, Ordering::SeqCst);
+
+    let on_drop = OnDrop;
+    let pending = stream::pending().map(move |()| {
+        let _ = &on_drop;
+        ("never".to_owned(), DataStore::new())
+    });
+
+    let sender = AsyncTaskObject::spawn_stream(pending);
+
+    sender.task_id()
+}
+
+#[wll::export]
+fn pending_stream_dropped() -> bool {
+    PENDING_STREAM_DROPPED.load(Ordering::SeqCst)
+}
+
+/// Start an asynchronous task with a future that never completes, and `n` threads that
+/// each wait for the task to be cancelled.
+///
+/// The future is dropped when the task is removed, which can be checked using
+/// [`pending_future_dropped()`], and each thread then increments the count returned by
+/// [`cancelled_watchers()`].
+#[wll::export]
+fn start_cancellation_watchers(n: mint) -> mint {
+    struct OnDrop;
+
+    impl Drop for OnDrop {
+        fn drop(&mut self) {
+            PENDING_FUTURE_DROPPED.store(true, Ordering::SeqCst);
+        }
+    }
+
+    PENDING_FUTURE_DROPPED.store(false, Ordering::SeqCst);
+    CANCELLED_WATCHERS.store(0, Ordering::SeqCst);
+
+    let sender = AsyncTaskObject::spawn_future(|task: AsyncTaskObject| {
+        for _ in 0..n {
+            let cancelled = task.cancellation_signal().cancelled();
+
+            thread::spawn(move || {
+                futures::executor::block_on(cancelled);
+
+                CANCELLED_WATCHERS.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+
+        async move {
+            let _on_drop = OnDrop;
+
+            future::pending::<()>().await
+        }
+    });
+
+    sender.task_id()
+}
+
+#[wll::export]
+fn pending_future_dropped() -> bool {
+    PENDING_FUTURE_DROPPED.load(Ordering::SeqCst)
+}
+
+#[wll::export]
+fn cancelled_watchers() -> mint {
+    CANCELLED_WATCHERS.load(Ordering::SeqCst) as mint
+}
@@ -0,0 +1,169 @@
+//! This example demonstrates how WSTP functions can read and return Wolfram Language
+//! numbers that do not fit in an `i64` or `f64` without losing any digits:
+//!
+//! * big integers, as [`BigInt`] (requires the `"num-bigint"` feature)
+//! * rationals, as [`BigRational`] (requires the `"num-rational"` feature)
+//! * arbitrary-precision reals, as [`BigReal`]
+
+use num_bigint::BigInt;
+use num_rational::BigRational;
+
+use wolfram_library_link::{
+    self as wll,
+    expr::{Expr, Symbol},
+    Args, BigReal, RealPrecision,
+};
+
+// The loader can be loaded and used by evaluating:
+//
+// ```
+// loadFunctions = LibraryFunctionLoad[
+//     "libbig_numbers",
+//     "load_big_numbers_functions",
+//     LinkObject,
+//     LinkObject
+// ];
+//
+// $functions = loadFunctions["libbig_numbers"];
+// ```
+wll::generate_loader!(load_big_numbers_functions);
+
+//======================================
+// Integers and rationals
+//======================================
+
+//------------------
+// big_factorial()
+//------------------
+
+/// Compute `n!` exactly.
+///
+/// ```wolfram
+/// bigFactorial = $functions["big_factorial"];
+///
+/// bigFactorial[30]    (* Returns 265252859812191058636308480000000 *)
+/// ```
+#[wll::export(wstp)]
+fn big_factorial(mut args: Args) {
+    let n: i64 = args.next().expect("expected Integer argument");
+
+    let factorial = (1..=n).fold(BigInt::from(1), |product, k| product * k);
+
+    let mut result = args.into_result_writer().unwrap();
+
+    result.put(factorial).unwrap();
+}
+
+//------------------
+// rational_total()
+//------------------
+
+/// Return the exact total of the integer and rational arguments.
+///
+/// ```wolfram
+/// rationalTotal = $functions["rational_total"];
+///
+/// rationalTotal[1/3, 1/6, 10^30]    (* Returns 2000000000000000000000000000001/2 *)
+/// ```
+#[wll::export(wstp)]
+fn rational_total(mut args: Args) {
+    let mut total = BigRational::from_integer(BigInt::from(0));
+
+    while args.remaining() > 0 {
+        let value: BigRational = match args.next() {
+            Ok(value) => value,
+            Err(err) => panic!("{}", err),
+        };
+
+        total += value;
+    }
+
+    let mut result = args.into_result_writer().unwrap();
+
+    result.put(total).unwrap();
+}
+
+//------------------
+// harmonic_numbers()
+//------------------
+
+/// Return the list of harmonic numbers `{1, 3/2, 11/6, ..., HarmonicNumber[n]}`, with
+/// each element computed exactly and written to the link as it is produced.
+///
+/// ```wolfram
+/// harmonicNumbers = $functions["harmonic_numbers"];
+///
+/// harmonicNumbers[4]    (* Returns {1, 3/2, 11/6, 25/12} *)
+/// ```
+#[wll::export(wstp)]
+fn harmonic_numbers(mut args: Args) -> impl ExactSizeIterator<Item = BigRational> {
+    let n: i64 = args.next().expect("expected Integer argument");
+
+    let mut total = BigRational::from_integer(BigInt::from(0));
+
+    (0..usize::try_from(n).unwrap()).map(move |k| {
+        total += BigRational::new(BigInt::from(1), BigInt::from(k + 1));
+        total.clone()
+    })
+}
+
+//======================================
+// Arbitrary-precision reals
+//======================================
+
+//------------------
+// negate_real()
+//------------------
+
+/// Negate a real number, preserving all of its digits and its precision.
+///
+/// ```wolfram
+/// negateReal = $functions["negate_real"];
+///
+/// negateReal[N[Pi, 30]]    (* Returns -3.14159265358979323846264338328`30. *)
+/// ```
+#[wll::export(wstp)]
+fn negate_real(mut args: Args) {
+    let real: BigReal = match args.next() {
+        Ok(real) => real,
+        Err(err) => panic!("{}", err),
+    };
+
+    let significand = match real.significand().strip_prefix('-') {
+        Some(positive) => positive.to_owned(),
+        None => format!("-{}", real.significand()),
+    };
+
+    let negated = BigReal::new(&significand, real.exponent(), real.precision()).unwrap();
+
+    let mut result = args.into_result_writer().unwrap();
+
+    result.put(negated).unwrap();
+}
+
+//------------------
+// real_precision()
+//------------------
+
+/// Return the precision of a real number, as read from the link.
+///
+/// ```wolfram
+/// realPrecision = $functions["real_precision"];
+///
+/// realPrecision[N[1/3, 20]]    (* Returns 20. *)
+/// realPrecision[1.5]           (* Returns MachinePrecision *)
+/// realPrecision[0``10]         (* Returns "Accuracy" -> 10. *)
+/// ```
+#[wll::export(wstp)]
+fn real_precision(mut args: Args) -> Expr {
+    let real: BigReal = match args.next() {
+        Ok(real) => real,
+        Err(err) => panic!("{}", err),
+    };
+
+    match real.precision() {
+        RealPrecision::Machine => Expr::symbol(Symbol::new("System`MachinePrecision")),
+        RealPrecision::Precision(precision) => Expr::real(precision),
+        RealPrecision::Accuracy(accuracy) => Expr::rule("Accuracy", Expr::real(accuracy)),
+    }
+}
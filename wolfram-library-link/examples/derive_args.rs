@@ -0,0 +1,114 @@
+//! This example demonstrates how `#[derive(FromArg, IntoArg)]` can be used to pass
+//! fieldless enums and newtype structs to and from native LibraryLink functions.
+
+use wolfram_library_link::{self as wll, FromArg, IntoArg};
+
+wll::generate_loader!(load_derive_args_functions);
+
+//======================================
+// Enums
+//======================================
+
+/// Passed as one of the strings `"Linear"`, `"Cubic"`, or `"Nearest"`.
+#[derive(FromArg, IntoArg)]
+enum Interpolation {
+    Linear,
+    Cubic,
+    #[arg(name = "Nearest")]
+    NearestNeighbor,
+}
+
+/// Passed as one of the integers `0`, `90`, `180`, or `270`.
+#[derive(Clone, Copy, FromArg, IntoArg)]
+#[arg(integer)]
+enum Rotation {
+    None = 0,
+    Quarter = 90,
+    Half = 180,
+    ThreeQuarters = 270,
+}
+
+//------------------
+// interpolate()
+//------------------
+
+/// Interpolate between `a` and `b` at position `t`, using the specified method.
+///
+/// ```wolfram
+/// interpolate = LibraryFunctionLoad[
+///     "libderive_args",
+///     "interpolate",
+///     {Real, Real, Real, String},
+///     Real
+/// ];
+///
+/// interpolate[0.0, 10.0, 0.25, "Cubic"]    (* Returns 1.5625 *)
+/// interpolate[0.0, 10.0, 0.25, "Other"]    (* Returns LibraryFunctionError["LIBRARY_TYPE_ERROR", 1] *)
+/// ```
+#[wll::export]
+fn interpolate(a: f64, b: f64, t: f64, method: Interpolation) -> f64 {
+    let t = match method {
+        Interpolation::Linear => t,
+        // Smoothstep
+        Interpolation::Cubic => t * t * (3.0 - 2.0 * t),
+        Interpolation::NearestNeighbor => t.round(),
+    };
+
+    a + (b - a) * t
+}
+
+//------------------
+// next_method()
+//------------------
+
+/// Return the interpolation method that follows `method`.
+#[wll::export]
+fn next_method(method: Interpolation) -> Interpolation {
+    match method {
+        Interpolation::Linear => Interpolation::Cubic,
+        Interpolation::Cubic => Interpolation::NearestNeighbor,
+        Interpolation::NearestNeighbor => Interpolation::Linear,
+    }
+}
+
+//------------------
+// rotate()
+//------------------
+
+/// Add two rotations.
+#[wll::export]
+fn rotate(first: Rotation, second: Rotation) -> Rotation {
+    match (first as i64 + second as i64) % 360 {
+        0 => Rotation::None,
+        90 => Rotation::Quarter,
+        180 => Rotation::Half,
+        _ => Rotation::ThreeQuarters,
+    }
+}
+
+//======================================
+// Newtypes
+//======================================
+
+#[derive(FromArg, IntoArg)]
+struct Meters(f64);
+
+#[derive(FromArg, IntoArg)]
+struct Feet {
+    value: f64,
+}
+
+//------------------
+// meters_to_feet()
+//------------------
+
+/// Convert a length in meters to feet.
+///
+/// Newtypes are passed as the type of their field, so this function has the
+/// LibraryLink type signature `{Real} -> Real`.
+#[wll::export]
+fn meters_to_feet(length: Meters) -> Feet {
+    Feet {
+        value: length.0 / 0.3048,
+    }
+}
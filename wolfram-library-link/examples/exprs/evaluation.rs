@@ -0,0 +1,100 @@
+use wolfram_library_link::{
+    self as wll,
+    expr::{Expr, Symbol},
+    Evaluation, EvaluationOutcome, EvaluationOutput,
+};
+
+wll::generate_loader!(load_evaluation_functions);
+
+/// Evaluate the expression held in `Hold[expr]`, returning a list of the form
+/// `{outcome, output}` that describes how the evaluation ended and the messages and
+/// `Print[..]` output it generated.
+///
+/// See `RustLink/Examples/Evaluation.wlt` for example usage of this function.
+#[wll::export(wstp)]
+fn evaluate_captured(args: Vec<Expr>) -> Expr {
+    let expr = match args.as_slice() {
+        [held] if held.has_normal_head(&Symbol::new("System`Hold")) => held
+            .normal_part(0)
+            .expect("Hold[..] expression has no elements")
+            .clone(),
+        _ => panic!("expected a single Hold[expr] argument, got: {:?}", args),
+    };
+
+    let (outcome, output) = Evaluation::run(&expr)
+        .expect("evaluation failed")
+        .into_parts();
+
+    let outcome = match outcome {
+        EvaluationOutcome::Returned(value) => {
+            Expr::list(vec![Expr::string("Returned"), value])
+        },
+        EvaluationOutcome::Aborted => Expr::list(vec![Expr::string("Aborted")]),
+        EvaluationOutcome::Thrown { value, tag: None } => {
+            Expr::list(vec![Expr::string("Thrown"), value])
+        },
+        EvaluationOutcome::Thrown {
+            value,
+            tag: Some(tag),
+        } => Expr::list(vec![Expr::string("Thrown"), value, tag]),
+    };
+
+    let output = output
+        .into_iter()
+        .map(|output| match output {
+            EvaluationOutput::Print(text) => {
+                Expr::list(vec![Expr::string("Print"), Expr::string(text)])
+            },
+            EvaluationOutput::Message { name, text } => Expr::list(vec![
+                Expr::string("Message"),
+                Expr::string(name),
+                Expr::string(text),
+            ]),
+        })
+        .collect();
+
+    Expr::list(vec![outcome, Expr::list(output)])
+}
+
+/// Evaluate `Range[n]` and compute the sum of the resulting integers in Rust.
+#[wll::export]
+fn sum_range(n: i64) -> i64 {
+    let range = Expr::normal(Symbol::new("System`Range"), vec![Expr::from(n)]);
+
+    let values: Vec<i64> = wll::evaluate_as(&range).expect("Range[n] was not a list");
+
+    values.iter().sum()
+}
+
+/// Increment the value of the Wolfram Language symbol `` Global`$RustCounter ``,
+/// treating it as 0 if it has no value, and return the new value.
+#[wll::export]
+fn increment_counter() -> i64 {
+    let counter = Symbol::new("Global`$RustCounter");
+
+    let value: i64 = wll::symbol_value(&counter)
+        .expect("failed to get value of $RustCounter")
+        .unwrap_or(0);
+
+    wll::set_symbol_value(&counter, Expr::from(value + 1))
+        .expect("failed to set value of $RustCounter");
+
+    value + 1
+}
+
+/// Set the value of `symbol` to `value`, returning the error message if the assignment
+/// failed.
+#[wll::export(wstp)]
+fn try_set_value(args: Vec<Expr>) -> Expr {
+    let (symbol, value) = match args.as_slice() {
+        [symbol, value] => (symbol, value),
+        _ => panic!("expected two arguments, got: {:?}", args),
+    };
+
+    let symbol: Symbol = wll::FromExpr::from_expr(symbol).expect("expected a symbol");
+
+    match wll::set_symbol_value(&symbol, value.clone()) {
+        Ok(()) => Expr::symbol(Symbol::new("System`Null")),
+        Err(err) => Expr::string(err.to_string()),
+    }
+}
@@ -0,0 +1,76 @@
+use wolfram_library_link::{self as wll, expr::Expr, expr_match, wl};
+
+wll::generate_loader!(load_expr_match_functions);
+
+/// Compute the area of a `Disk[..]`, `Rectangle[..]`, or `Polygon[..]` with real
+/// coordinates.
+///
+/// If the argument is not one of the supported shapes, a `Failure[..]` describing the
+/// expected patterns is returned.
+#[wll::export(wstp)]
+fn shape_area(args: Vec<Expr>) -> Expr {
+    let area = expr_match!(&Expr::list(args), {
+        {Disk[{_Real, _Real}, r_Real]} => std::f64::consts::PI * r * r,
+        {Rectangle[{x1_Real, y1_Real}, {x2_Real, y2_Real}]} => {
+            ((x2 - x1) * (y2 - y1)).abs()
+        },
+        {Polygon[{points__List}]} => polygon_area(&points),
+    });
+
+    match area {
+        Ok(area) => Expr::real(area),
+        Err(err) => {
+            let message = err.to_string();
+
+            wl!(Failure["NoMatch", <|"Message" -> #message|>])
+        },
+    }
+}
+
+/// Compute the area of a polygon using the shoelace formula.
+fn polygon_area(points: &[&Expr]) -> f64 {
+    let coordinates: Vec<(f64, f64)> = points
+        .iter()
+        .map(|point| {
+            expr_match!(point, {
+                {x_Real, y_Real} => (x, y),
+            })
+            .expect("invalid polygon point")
+        })
+        .collect();
+
+    let twice_area: f64 = coordinates
+        .iter()
+        .zip(coordinates.iter().cycle().skip(1))
+        .map(|((x1, y1), (x2, y2))| x1 * y2 - x2 * y1)
+        .sum();
+
+    twice_area.abs() / 2.0
+}
+
+/// Join a list of strings, using the separator given by an optional
+/// `"Separator" -> sep` option.
+///
+/// ```wolfram
+/// joinStrings[{"a", "b", "c"}, "Separator" -> "-"]
+/// ```
+#[wll::export(wstp)]
+fn join_strings(args: Vec<Expr>) -> Expr {
+    let args = Expr::list(args);
+
+    let (words, options) = expr_match!(&args, {
+        {{words___String}, options___Rule} => (words, options),
+    })
+    .unwrap_or_else(|err| panic!("{}", err));
+
+    let mut separator = "";
+
+    for option in options {
+        expr_match!(option, {
+            "Separator" -> sep_String => separator = sep,
+        })
+        .unwrap_or_else(|err| panic!("unsupported option: {}", err.expr()));
+    }
+
+    Expr::string(words.join(separator))
+}
@@ -0,0 +1,49 @@
+use wolfram_library_link::{
+    self as wll,
+    expr::{Expr, ExprKind},
+    wxf::Wxf,
+};
+
+wll::generate_loader!(load_wxf_functions);
+
+/// Reverse the elements of a list, which is passed and returned as WXF data.
+#[wll::export]
+fn reverse_list(list: Wxf) -> Wxf {
+    let Wxf(list) = list;
+
+    let mut elements: Vec<Expr> = match list.kind() {
+        ExprKind::Normal(normal) => normal.elements().to_vec(),
+        _ => panic!("expected a list, got: {}", list),
+    };
+
+    elements.reverse();
+
+    Wxf(Expr::list(elements))
+}
+
+/// Return an `n` by `n` multiplication table, which is serialized as a packed array.
+#[wll::export]
+fn multiplication_table(n: i64) -> Wxf {
+    let rows = (1..=n)
+        .map(|i| Expr::list((1..=n).map(|j| Expr::from(i * j)).collect()))
+        .collect();
+
+    Wxf(Expr::list(rows))
+}
+
+/// Count the number of atomic subexpressions of `expr`, like `LeafCount`.
+#[wll::export]
+fn leaf_count(expr: Wxf) -> i64 {
+    fn count(expr: &Expr) -> i64 {
+        match expr.kind() {
+            ExprKind::Normal(normal) => {
+                count(normal.head()) + normal.elements().iter().map(count).sum::<i64>()
+            },
+            _ => 1,
+        }
+    }
+
+    let Wxf(expr) = expr;
+
+    count(&expr)
+}
@@ -0,0 +1,100 @@
+//! This example demonstrates how a native LibraryLink function can return several values
+//! at once, by returning a tuple or a struct that is converted into a "DataStore".
+//!
+//! The `#[export(unpack = "...")]` argument is used to have the loader generated by
+//! `generate_loader!` convert the returned "DataStore" into a `List` or `Association`.
+
+use wolfram_library_link::{self as wll, IntoArg, IntoDataStore};
+
+// The loader can be loaded and used by evaluating:
+//
+// ```
+// loadFunctions = LibraryFunctionLoad[
+//     "libmultiple_returns",
+//     "load_multiple_returns_functions",
+//     LinkObject,
+//     LinkObject
+// ];
+//
+// $functions = loadFunctions["libmultiple_returns"];
+// ```
+wll::generate_loader!(load_multiple_returns_functions);
+
+//======================================
+// Tuples
+//======================================
+
+//------------------
+// div_rem()
+//------------------
+
+/// Return the quotient and remainder of `a / b`.
+///
+/// ```wolfram
+/// divRem = $functions["div_rem"];
+///
+/// divRem[17, 5]    (* Returns Developer`DataStore[3, 2] *)
+/// ```
+#[wll::export]
+fn div_rem(a: i64, b: i64) -> (i64, i64) {
+    (a / b, a % b)
+}
+
+//------------------
+// split_name()
+//------------------
+
+/// Split a name at the first space, returning the parts as a list.
+///
+/// ```wolfram
+/// splitName = $functions["split_name"];
+///
+/// splitName["Ada Lovelace"]    (* Returns {"Ada", "Lovelace"} *)
+/// ```
+#[wll::export(unpack = "List")]
+fn split_name(name: String) -> (String, String) {
+    match name.split_once(' ') {
+        Some((first, last)) => (first.to_owned(), last.to_owned()),
+        None => (name, String::new()),
+    }
+}
+
+//======================================
+// Structs
+//======================================
+
+#[derive(IntoDataStore, IntoArg)]
+#[arg(data_store)]
+struct Summary {
+    count: i64,
+    mean: f64,
+    range: (f64, f64),
+}
+
+//------------------
+// summarize()
+//------------------
+
+/// Return the number of values, their mean, and their minimum and maximum.
+///
+/// Nested data stores are converted too, so the `range` field is returned as a list.
+///
+/// ```wolfram
+/// summarize = $functions["summarize"];
+///
+/// summarize[{1.0, 2.0, 6.0}]
+///     (* Returns <|"count" -> 3, "mean" -> 3., "range" -> {1., 6.}|> *)
+/// ```
+#[wll::export(unpack = "Association")]
+fn summarize(values: &wll::NumericArray<f64>) -> Summary {
+    let values = values.as_slice();
+
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+    Summary {
+        count: values.len() as i64,
+        mean: values.iter().sum::<f64>() / values.len() as f64,
+        range: (min, max),
+    }
+}
@@ -1,8 +1,10 @@
 use std::os::raw::c_int;
 use wolfram_library_link::{
     self as wll,
+    expr::Expr,
     sys::{self, WolframLibraryData},
-    DataStore, NumericArray,
+    ColorSpace, DataStore, FromDataStore, Image, IntoDataStore, NumericArray, Pixel,
+    UninitImage,
 };
 
 
@@ -81,6 +83,26 @@ fn test_named_numeric_array_data_store() -> DataStore {
     data
 }
 
+#[wll::export]
+fn test_named_image_data_store() -> DataStore {
+    let mut image: UninitImage<u8> = UninitImage::new_2d(2, 1, 1, ColorSpace::Gray, true);
+    image.set(Pixel::D2([1, 1]), 1, 0);
+    image.set(Pixel::D2([1, 2]), 1, 255);
+    let image: Image<u8> = unsafe { image.assume_init() };
+
+    let mut data = DataStore::new();
+    data.add_named_image("image", image);
+
+    data
+}
+
+#[wll::export]
+fn test_data_store_node_type_names(ds: DataStore) -> String {
+    let names: Vec<&str> = ds.nodes().map(|node| node.value().type_name()).collect();
+
+    names.join(",")
+}
+
 #[wll::export]
 fn test_nested_data_store() -> DataStore {
     let mut inner = DataStore::new();
@@ -173,3 +195,123 @@ fn test_data_store_nodes() {
         assert!(nodes.next().is_none());
     }
 }
+
+//======================================
+// Keyed access
+//======================================
+
+#[wll::export]
+fn test_data_store_keyed_access(ds: DataStore) -> String {
+    assert!(ds.contains_key("path"));
+    assert!(!ds.contains_key("missing"));
+
+    // Typed getters return `None` if the value has a different type.
+    assert_eq!(ds.get_i64("path"), None);
+    assert_eq!(ds.get_str("missing"), None);
+
+    let path = ds.get_str("path").expect("missing \"path\" node");
+    let count = ds.get_i64("count").expect("missing \"count\" node");
+    let verbose = ds.get_bool("verbose").expect("missing \"verbose\" node");
+
+    format!("{} {} {}", path, count, verbose)
+}
+
+#[wll::export]
+fn test_data_store_to_map(ds: DataStore) -> String {
+    let map = ds.to_map();
+
+    let mut entries: Vec<String> = map
+        .iter()
+        .map(|(name, value)| format!("{}: {}", name, value.type_name()))
+        .collect();
+    entries.sort();
+
+    entries.join(", ")
+}
+
+#[wll::export]
+fn test_data_store_from_iterator() -> DataStore {
+    let mut store: DataStore = (1..=3).map(|i| (format!("x{}", i), i * i)).collect();
+
+    store.extend([("done", true)]);
+
+    store
+}
+
+//======================================
+// Expr conversions
+//======================================
+
+#[wll::export(wstp)]
+fn test_data_store_expr_round_trip(args: Vec<Expr>) -> Expr {
+    assert_eq!(args.len(), 1, "expected 1 argument, got {}", args.len());
+
+    let result = DataStore::from_expr(&args[0])
+        .map_err(|err| err.to_string())
+        .and_then(|store| store.to_expr().map_err(|err| err.to_string()));
+
+    match result {
+        Ok(expr) => expr,
+        Err(message) => Expr::string(message),
+    }
+}
+
+#[wll::export]
+fn test_data_store_display(ds: DataStore) -> String {
+    ds.to_string()
+}
+
+//======================================
+// Derived conversions
+//======================================
+
+#[derive(IntoDataStore, FromDataStore)]
+struct Measurement {
+    label: String,
+    count: i64,
+    values: NumericArray<f64>,
+    range: Range,
+}
+
+#[derive(IntoDataStore, FromDataStore)]
+struct Range(f64, f64);
+
+#[wll::export]
+fn test_derive_into_data_store() -> DataStore {
+    let measurement = Measurement {
+        label: "temperature".to_owned(),
+        count: 3,
+        values: NumericArray::from_slice(&[20.5, 21.0, 19.5]),
+        range: Range(19.5, 21.0),
+    };
+
+    measurement.into_data_store()
+}
+
+/// Describe the `Measurement` stored in `store`, or the error that occurred reading it.
+#[wll::export]
+fn test_derive_from_data_store(store: DataStore) -> String {
+    match Measurement::from_data_store(&store) {
+        Ok(Measurement {
+            label,
+            count,
+            values,
+            range: Range(min, max),
+        }) => format!(
+            "{} {} {:?} {} {}",
+            label,
+            count,
+            values.as_slice(),
+            min,
+            max
+        ),
+        Err(err) => err.to_string(),
+    }
+}
+
+#[wll::export]
+fn test_tuple_data_store_round_trip(store: DataStore) -> DataStore {
+    let tuple: (i64, String, bool) = FromDataStore::from_data_store(&store).unwrap();
+
+    tuple.into_data_store()
+}
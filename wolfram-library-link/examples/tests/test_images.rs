@@ -114,3 +114,144 @@ fn test_create_color_rgb_f32_image() -> Image<f32> {
 
     unsafe { image.assume_init() }
 }
+
+//======================================
+// 3D images and type conversions
+//======================================
+
+/// Create a 3D image with two slices, where each pixel value is equal to its position in
+/// the order returned by `pixel_positions()`.
+#[wll::export]
+fn test_create_3d_image() -> Image<u8> {
+    let slices = 2;
+    let width = 2;
+    let height = 1;
+    let channels = 1;
+
+    let mut image = UninitImage::<u8>::new_3d(
+        slices,
+        width,
+        height,
+        channels,
+        ColorSpace::Automatic,
+        false,
+    );
+
+    for (index, pixel) in image.pixel_positions().enumerate() {
+        image.set(pixel, 1, index as u8 + 1);
+    }
+
+    unsafe { image.assume_init() }
+}
+
+/// Get the value of every channel of every pixel in `image`, in the order returned by
+/// `pixel_positions()`.
+#[wll::export]
+fn test_image_pixel_values(image: &Image<u8>) -> NumericArray<u8> {
+    let values: Vec<u8> = image
+        .pixel_positions()
+        .flat_map(|pixel| {
+            (1..=image.channels()).map(move |channel| image.get(pixel, channel).unwrap())
+        })
+        .collect();
+
+    NumericArray::from_slice(&values)
+}
+
+/// Get the slice indices of every pixel in `image`.
+#[wll::export]
+fn test_image_pixel_slices(image: &Image<u8>) -> NumericArray<i64> {
+    let slices: Vec<i64> = image
+        .pixel_positions()
+        .map(|pixel| pixel.slice().map(|slice| slice as i64).unwrap_or(0))
+        .collect();
+
+    NumericArray::from_slice(&slices)
+}
+
+#[wll::export]
+fn test_image_convert_to_real32(image: &Image<u8>, interleaving: bool) -> Image<f32> {
+    image.convert_to(interleaving).unwrap()
+}
+
+//======================================
+// Pixel iteration and mapping
+//======================================
+
+/// Returns true if mutable access to the data of a shared image is prevented.
+#[wll::export]
+fn test_image_as_slice_mut_shared(mut image: Image<u8>) -> bool {
+    image.as_slice_mut().is_none()
+}
+
+#[wll::export]
+fn test_image_as_slice_mut() -> Image<u8> {
+    let mut image = UninitImage::<u8>::new_2d(2, 1, 1, ColorSpace::Gray, false);
+    image.zero();
+    let mut image = unsafe { image.assume_init() };
+
+    image.as_slice_mut().unwrap().copy_from_slice(&[10, 20]);
+
+    image
+}
+
+#[wll::export]
+fn test_image_channel_values(image: &Image<u8>, channel: i64) -> NumericArray<u8> {
+    let values: Vec<u8> = image.channel_values(channel as usize).collect();
+
+    NumericArray::from_slice(&values)
+}
+
+/// Compute the sum of every channel of every pixel in each row of `image`.
+#[wll::export]
+fn test_image_row_sums(image: &Image<u8>) -> NumericArray<i64> {
+    let sums: Vec<i64> = image
+        .rows()
+        .map(|row| row.flatten().map(i64::from).sum())
+        .collect();
+
+    NumericArray::from_slice(&sums)
+}
+
+/// Convert an RGB image into a grayscale image by averaging the color channels.
+#[wll::export]
+fn test_map_pixels_rgb_to_gray(image: &Image<u8>) -> Image<f32> {
+    image.map_pixels(1, |rgb, gray| {
+        let sum: f32 = rgb.iter().map(|&value| f32::from(value)).sum();
+
+        gray[0] = sum / (3.0 * 255.0);
+    })
+}
+
+#[wll::export]
+fn test_par_map_pixels_invert(image: &Image<u8>) -> Image<u8> {
+    image.par_map_pixels(image.channels(), |pixel, inverted| {
+        for (input, output) in pixel.iter().zip(inverted) {
+            *output = u8::MAX - input;
+        }
+    })
+}
+
+//======================================
+// `image` crate conversions
+//======================================
+
+/// Round-trip an image through [`image::DynamicImage`].
+#[cfg(feature = "image")]
+#[wll::export]
+fn test_dynamic_image_round_trip(image: image::DynamicImage) -> image::DynamicImage {
+    image
+}
+
+/// Invert the colors of an RGB image using an [`image::ImageBuffer`].
+#[cfg(feature = "image")]
+#[wll::export]
+fn test_invert_rgb_u8_image_buffer(image: &Image<u8>) -> Image<u8> {
+    let mut buffer: image::RgbImage = image
+        .to_image_buffer()
+        .expect("expected 3 channel RGB image");
+
+    image::imageops::invert(&mut buffer);
+
+    Image::from_image_buffer(&buffer)
+}
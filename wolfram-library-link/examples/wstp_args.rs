@@ -0,0 +1,398 @@
+//! This example demonstrates how the `Args` and `ResultWriter` types can be used to read
+//! the arguments of WSTP functions on demand, and how `Args`, `ResultWriter`, and
+//! returned iterators can be used to stream results back to the Kernel, without
+//! constructing intermediate `Expr` values.
+//!
+//! It also demonstrates how `NumericArray` and `Image` values can be read from and
+//! written to the link as packed arrays, and converted to and from `Expr`.
+
+use wolfram_library_link::{
+    self as wll,
+    expr::Expr,
+    wstp::{self, Link},
+    Args, FromExpr, Image, IntoLink, NumericArray, ToExpr,
+};
+
+// The loader can be loaded and used by evaluating:
+//
+// ```
+// loadFunctions = LibraryFunctionLoad[
+//     "libwstp_args",
+//     "load_wstp_args_functions",
+//     LinkObject,
+//     LinkObject
+// ];
+//
+// $functions = loadFunctions["libwstp_args"];
+// ```
+wll::generate_loader!(load_wstp_args_functions);
+
+//======================================
+// Reading arguments
+//======================================
+
+//------------------
+// real_mean()
+//------------------
+
+/// Compute the mean of a packed array of reals, which is read directly from the link.
+///
+/// ```wolfram
+/// realMean = $functions["real_mean"];
+///
+/// realMean[{1.0, 2.0, 3.0, 4.0}]    (* Returns 2.5 *)
+/// ```
+#[wll::export(wstp)]
+fn real_mean(mut args: Args) -> Expr {
+    if args.len() != 1 {
+        panic!("expected 1 argument, got {}", args.len());
+    }
+
+    let array = args.next_f64_array().unwrap();
+    let data = array.data();
+
+    Expr::real(data.iter().sum::<f64>() / data.len() as f64)
+}
+
+//------------------
+// total_string_length()
+//------------------
+
+/// Return the total length of the string arguments, borrowing each string from the
+/// link instead of copying it.
+///
+/// ```wolfram
+/// totalStringLength = $functions["total_string_length"];
+///
+/// totalStringLength["Foo", "Bar", "Baz"]    (* Returns 9 *)
+/// ```
+#[wll::export(wstp)]
+fn total_string_length(mut args: Args) -> Expr {
+    let mut total: usize = 0;
+
+    while args.remaining() > 0 {
+        let string = args.next_str().expect("expected String argument");
+        total += string.as_str().chars().count();
+    }
+
+    Expr::from(i64::try_from(total).unwrap())
+}
+
+//------------------
+// select_argument()
+//------------------
+
+/// Return the argument at position `n`, skipping the arguments before it without
+/// reading them into memory.
+///
+/// ```wolfram
+/// selectArgument = $functions["select_argument"];
+///
+/// selectArgument[2, a, f[x], "c"]    (* Returns f[x] *)
+/// ```
+#[wll::export(wstp)]
+fn select_argument(mut args: Args) -> Expr {
+    let n: i64 = args.next().expect("expected Integer argument");
+
+    for _ in 1..n {
+        args.skip().unwrap();
+    }
+
+    match args.next::<Expr>() {
+        Ok(expr) => expr,
+        Err(err) => panic!("{}", err),
+    }
+}
+
+//======================================
+// Writing results
+//======================================
+
+//------------------
+// squares_table()
+//------------------
+
+/// Return the list `{{1, 1}, {2, 4}, ..., {n, n^2}}`, writing each element to the link
+/// as it is computed.
+///
+/// ```wolfram
+/// squaresTable = $functions["squares_table"];
+///
+/// squaresTable[3]    (* Returns {{1, 1}, {2, 4}, {3, 9}} *)
+/// ```
+#[wll::export(wstp)]
+fn squares_table(mut args: Args) {
+    let n: i64 = args.next().expect("expected Integer argument");
+
+    let mut result = args.into_result_writer().unwrap();
+
+    result.put_list(usize::try_from(n).unwrap()).unwrap();
+
+    for i in 1..=n {
+        result.put_i64_array(&[i, i * i], &[2]).unwrap();
+    }
+}
+
+//------------------
+// string_lengths()
+//------------------
+
+/// Return an association from each string argument to its length.
+///
+/// ```wolfram
+/// stringLengths = $functions["string_lengths"];
+///
+/// stringLengths["a", "bcd"]    (* Returns <|"a" -> 1, "bcd" -> 3|> *)
+/// ```
+#[wll::export(wstp)]
+fn string_lengths(mut args: Args) {
+    let strings: Vec<String> = (0..args.len())
+        .map(|_| args.next().expect("expected String argument"))
+        .collect();
+
+    let mut result = args.into_result_writer().unwrap();
+
+    result
+        .put_function("System`Association", strings.len())
+        .unwrap();
+
+    for string in &strings {
+        result.put_function("System`Rule", 2).unwrap();
+        result.put_str(string).unwrap();
+        result.put_i64(string.chars().count() as i64).unwrap();
+    }
+
+    assert!(result.is_complete());
+}
+
+//======================================
+// Returning iterators
+//======================================
+
+//------------------
+// powers_table()
+//------------------
+
+/// Return the list `{{1, 1, 1}, {2, 4, 8}, ..., {n, n^2, n^3}}`. Each row is written to
+/// the link as it is produced by the returned iterator.
+///
+/// ```wolfram
+/// powersTable = $functions["powers_table"];
+///
+/// powersTable[3]    (* Returns {{1, 1, 1}, {2, 4, 8}, {3, 9, 27}} *)
+/// ```
+#[wll::export(wstp)]
+fn powers_table(mut args: Args) -> impl ExactSizeIterator<Item = (i64, i64, i64)> {
+    let n: i64 = args.next().expect("expected Integer argument");
+
+    (0..usize::try_from(n).unwrap()).map(|i| {
+        let i = i as i64 + 1;
+        (i, i * i, i * i * i)
+    })
+}
+
+//------------------
+// panic_after()
+//------------------
+
+/// Return a list of `len` strings, where the iterator panics after producing `count`
+/// elements.
+///
+/// The elements written before the panic are kept, and the panic is returned as a
+/// `Failure["RustPanic", ...]` element.
+///
+/// ```wolfram
+/// panicAfter = $functions["panic_after"];
+///
+/// panicAfter[2, 5]    (* Returns {"row 1", "row 2", Failure["RustPanic", ...]} *)
+/// ```
+#[wll::export(wstp)]
+fn panic_after(args: Vec<Expr>) -> impl ExactSizeIterator<Item = String> {
+    let args = Expr::list(args);
+
+    let (count, len) = wll::expr_match!(&args, {
+        {count_Integer, len_Integer} => (count, len),
+    })
+    .unwrap();
+
+    (0..usize::try_from(len).unwrap()).map(move |i| {
+        if i as i64 == count {
+            panic!("stopped after {} rows", count);
+        }
+
+        format!("row {}", i + 1)
+    })
+}
+
+//------------------
+// panic_in_field()
+//------------------
+
+/// Return the list `{{1, 1}, {2, 4}, ..., {len, len^2}}`, where writing the second field
+/// of row `row` to the link panics.
+///
+/// The row containing the panic is replaced by a `Failure["RustPanic", ...]` element,
+/// without any of the fields written before the panic.
+///
+/// ```wolfram
+/// panicInField = $functions["panic_in_field"];
+///
+/// panicInField[2, 3]    (* Returns {{1, 1}, Failure["RustPanic", ...]} *)
+/// ```
+#[wll::export(wstp)]
+fn panic_in_field(args: Vec<Expr>) -> impl ExactSizeIterator<Item = (i64, Square)> {
+    let args = Expr::list(args);
+
+    let (row, len) = wll::expr_match!(&args, {
+        {row_Integer, len_Integer} => (row, len),
+    })
+    .unwrap();
+
+    (0..usize::try_from(len).unwrap()).map(move |i| {
+        let i = i as i64 + 1;
+        let square = Square {
+            value: i,
+            panics: i == row,
+        };
+
+        (i, square)
+    })
+}
+
+/// The square of `value`, computed when it is written to the link.
+struct Square {
+    value: i64,
+    panics: bool,
+}
+
+impl IntoLink for Square {
+    fn into_link(self, link: &mut Link) -> Result<(), wstp::Error> {
+        if self.panics {
+            panic!("unable to square {}", self.value);
+        }
+
+        (self.value * self.value).into_link(link)
+    }
+}
+
+//======================================
+// Numeric arrays and images
+//======================================
+
+//------------------
+// scale_numeric_array()
+//------------------
+
+/// Multiply the elements of a `NumericArray` of reals by a factor, and return the result
+/// as a rule labeled with the first argument.
+///
+/// The array data is read from and written to the link as a packed array.
+///
+/// ```wolfram
+/// scaleNumericArray = $functions["scale_numeric_array"];
+///
+/// scaleNumericArray["doubled", NumericArray[{1.0, 2.0}, "Real64"], 2.0]
+///     (* Returns "doubled" -> NumericArray[{2., 4.}, "Real64"] *)
+/// ```
+#[wll::export(wstp)]
+fn scale_numeric_array(mut args: Args) {
+    let label: Expr = args.next().unwrap();
+    let array: NumericArray<f64> = args.next().expect("expected Real64 NumericArray");
+    let factor: f64 = args.next().expect("expected Real argument");
+
+    let scaled: Vec<f64> = array
+        .as_slice()
+        .iter()
+        .map(|value| value * factor)
+        .collect();
+    let scaled = NumericArray::from_array(array.dimensions(), &scaled);
+
+    let mut result = args.into_result_writer().unwrap();
+
+    result.put_function("System`Rule", 2).unwrap();
+    result.put_expr(&label).unwrap();
+    result.put(&scaled).unwrap();
+}
+
+//------------------
+// image_mean()
+//------------------
+
+/// Return the mean of the byte values of an image.
+///
+/// ```wolfram
+/// imageMean = $functions["image_mean"];
+///
+/// imageMean[Image[{{0, 100}, {200, 100}}, "Byte"]]    (* Returns 100. *)
+/// ```
+#[wll::export(wstp)]
+fn image_mean(mut args: Args) -> Expr {
+    let image: Image<u8> = args.next().expect("expected Byte Image");
+
+    byte_mean(&image).to_expr().unwrap()
+}
+
+//------------------
+// image_means()
+//------------------
+
+/// Return the mean of the byte values of each image argument, or the error message for
+/// each argument that could not be read as a `"Byte"` image.
+///
+/// An argument that fails to read is skipped, so the arguments after it are still read.
+///
+/// ```wolfram
+/// imageMeans = $functions["image_means"];
+///
+/// imageMeans[Image[{{0, 100}}, "Byte"], Image[{{0.5}}, "Real32"], Image[{{10}}, "Byte"]]
+///     (* Returns {50., "...", 10.} *)
+/// ```
+#[wll::export(wstp)]
+fn image_means(mut args: Args) -> Expr {
+    let mut means = Vec::new();
+
+    while args.remaining() > 0 {
+        let mean = match args.next::<Image<u8>>() {
+            Ok(image) => byte_mean(&image).to_expr().unwrap(),
+            Err(err) => Expr::string(err.to_string()),
+        };
+
+        means.push(mean);
+    }
+
+    Expr::list(means)
+}
+
+fn byte_mean(image: &Image<u8>) -> f64 {
+    let data = image.as_slice();
+    let total: f64 = data.iter().map(|&value| f64::from(value)).sum();
+
+    total / data.len() as f64
+}
+
+//------------------
+// reverse_numeric_array()
+//------------------
+
+/// Reverse the elements of a one-dimensional array of integers, which can be either a
+/// `NumericArray` or a `List`, and return the result as a `NumericArray`.
+///
+/// This function converts its argument using `FromExpr`, and its result using `ToExpr`.
+///
+/// ```wolfram
+/// reverseNumericArray = $functions["reverse_numeric_array"];
+///
+/// reverseNumericArray[{1, 2, 3}]    (* Returns NumericArray[{3, 2, 1}, "Integer32"] *)
+/// ```
+#[wll::export(wstp)]
+fn reverse_numeric_array(args: Vec<Expr>) -> Expr {
+    let array = match args.as_slice() {
+        [array] => NumericArray::<i32>::from_expr(array).unwrap(),
+        _ => panic!("expected 1 argument, got {}", args.len()),
+    };
+
+    let mut data = array.as_slice().to_vec();
+    data.reverse();
+
+    NumericArray::from_slice(&data).to_expr().unwrap()
+}
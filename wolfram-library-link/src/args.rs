@@ -5,16 +5,21 @@ use std::{
     cell::RefCell,
     ffi::{CStr, CString},
     os::raw::c_char,
+    panic::AssertUnwindSafe,
 };
 
 use ref_cast::RefCast;
 
 use crate::{
     expr::{Expr, Symbol},
+    macro_utils::{self, ArgTypeError, NativeCallError},
     rtl,
     sys::{self, mint, mreal, MArgument},
     wstp::Link,
-    DataStore, Image, NumericArray,
+    wstp_args::{write_list_from_iter, CallState, IntoLink},
+    wxf::{self, Wxf},
+    Args, AsyncTaskObject, DataStore, Image, IntoDataStore, IntoDataStoreNode,
+    NumericArray,
 };
 
 /// Trait implemented for types that can be passed via an [`MArgument`].
@@ -22,6 +27,20 @@ pub trait FromArg<'a> {
     #[allow(missing_docs)]
     unsafe fn from_arg(arg: &'a MArgument) -> Self;
 
+    /// Convert `arg`, returning an error instead of panicking if it has a value that is
+    /// not valid for this type.
+    ///
+    /// This is used by the wrapper generated by [`#[export]`][crate::export] to return
+    /// `LIBRARY_TYPE_ERROR`. The default implementation calls
+    /// [`from_arg()`][FromArg::from_arg].
+    #[doc(hidden)]
+    unsafe fn try_from_arg(arg: &'a MArgument) -> Result<Self, ArgTypeError>
+    where
+        Self: Sized,
+    {
+        Ok(Self::from_arg(arg))
+    }
+
     /// Return the *LibraryLink* parameter type as a Wolfram Language expression.
     ///
     /// ```
@@ -93,6 +112,23 @@ pub trait NativeFunction<'a> {
     /// type signature for functions exported by [`#[export]`][crate::export].
     // Note: This method takes `self` so that it is object safe.
     fn signature(&self) -> Result<(Vec<Expr>, Expr), String>;
+
+    /// Call the function, returning an error if it finished without writing a return
+    /// value to `ret`.
+    ///
+    /// This is used by the wrapper generated by [`#[export]`][crate::export] to choose
+    /// the error code returned to the Kernel. The default implementation calls
+    /// [`call()`][NativeFunction::call].
+    #[doc(hidden)]
+    unsafe fn try_call(
+        &self,
+        args: &'a [MArgument],
+        ret: MArgument,
+    ) -> Result<(), NativeCallError> {
+        self.call(args, ret);
+
+        Ok(())
+    }
 }
 
 /// Trait implemented for any function whose parameters and return type can be passed
@@ -106,11 +142,69 @@ pub trait NativeFunction<'a> {
 /// * `fn(_: &mut Link)`
 /// * `fn(_: Vec<Expr>) -> Expr`
 /// * `fn(_: Vec<Expr>)`
+/// * `fn(_: Args) -> Expr`
+/// * `fn(_: Args)`
+///
+/// [`#[export(wstp)]`][crate::export#exportwstp] functions can also return
+/// `impl ExactSizeIterator<Item = T>` for any `T: IntoLink`, in which case the elements
+/// are written to the link one at a time as a `List`. The wrapper used to do this is an
+/// implementation detail.
 pub trait WstpFunction {
     /// Call the function using the [`Link`] object passed by the Kernel.
     unsafe fn call(&self, link: &mut Link);
 }
 
+/// Trait implemented for any function that can be run as the body of a Wolfram Language
+/// asynchronous task.
+///
+/// [`#[export(async)]`][crate::export#exportasync] can only be used with
+/// functions that implement this trait.
+///
+/// A function implements this trait if its first parameter is an [`AsyncTaskObject`],
+/// its remaining parameters implement [`FromArg`] for any lifetime and are
+/// [`Send`] + `'static`, and it returns `()`.
+pub trait AsyncFunction {
+    /// Start a new asynchronous task that calls this function on a background thread,
+    /// using the arguments stored in the raw LibraryLink [`MArgument`] fields.
+    ///
+    /// The ID of the new task is written to `ret`.
+    ///
+    /// # Safety
+    ///
+    /// `args` must contain the arguments of a LibraryLink function call whose parameter
+    /// types match [`signature()`][AsyncFunction::signature], and `ret` must be the
+    /// uninitialized return value of that call.
+    unsafe fn call(&self, args: &[MArgument], ret: MArgument);
+
+    /// Get the type signature of the LibraryLink function that starts the asynchronous
+    /// task, suitable for use in
+    /// [`LibraryFunctionLoad`][ref/LibraryFunctionLoad]<code>[_, _, <i>parameters</i>, <i>ret</i>]</code>.
+    ///
+    /// The return type is always `Integer`, the ID of the new task.
+    ///
+    /// [ref/LibraryFunctionLoad]: https://reference.wolfram.com/language/ref/LibraryFunctionLoad.html
+    // Note: This method takes `self` so that it is object safe.
+    fn signature(&self) -> Result<(Vec<Expr>, Expr), String>;
+
+    /// Start the asynchronous task, returning an error instead of panicking if an
+    /// argument has a value that is not valid for its parameter type.
+    ///
+    /// This is used by the wrapper generated by
+    /// [`#[export(async)]`][crate::export#exportasync] to return
+    /// `LIBRARY_TYPE_ERROR`. The default implementation calls
+    /// [`call()`][AsyncFunction::call].
+    #[doc(hidden)]
+    unsafe fn try_call(
+        &self,
+        args: &[MArgument],
+        ret: MArgument,
+    ) -> Result<(), ArgTypeError> {
+        self.call(args, ret);
+
+        Ok(())
+    }
+}
+
 //======================================
 // FromArg Impls
 //======================================
@@ -431,6 +525,45 @@ impl<'a> FromArg<'a> for Image<()> {
     }
 }
 
+/// Requires the `"image"` feature.
+///
+/// # Panics
+///
+/// [`from_arg()`][FromArg::from_arg] will panic if the image cannot be converted using
+/// [`Image::to_dynamic_image()`]. Functions generated by [`#[export]`][crate::export]
+/// return `LIBRARY_TYPE_ERROR` instead.
+#[cfg(feature = "image")]
+impl<'a> FromArg<'a> for ::image::DynamicImage {
+    unsafe fn from_arg(arg: &'a MArgument) -> ::image::DynamicImage {
+        match Self::try_from_arg(arg) {
+            Ok(image) => image,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    unsafe fn try_from_arg(
+        arg: &'a MArgument,
+    ) -> Result<::image::DynamicImage, ArgTypeError> {
+        let image: &Image = Image::ref_cast(&*arg.image);
+
+        image.to_dynamic_image().map_err(|err| {
+            macro_utils::arg_type_error(
+                "DynamicImage",
+                &err,
+                "a 2D image supported by the image crate",
+            )
+        })
+    }
+
+    fn parameter_type() -> Expr {
+        // {Image, "Constant"}
+        Expr::normal(Symbol::new("System`List"), vec![
+            Expr::from(Symbol::new("System`Image")),
+            Expr::string("Constant"),
+        ])
+    }
+}
+
 //--------------------------------------
 // DataStore
 //--------------------------------------
@@ -456,6 +589,47 @@ impl<'a> FromArg<'a> for &'a DataStore {
     }
 }
 
+//--------------------------------------
+// Wxf
+//--------------------------------------
+
+/// # Panics
+///
+/// [`from_arg()`][FromArg::from_arg] will panic if the argument is not valid WXF data.
+/// Functions generated by [`#[export]`][crate::export] return `LIBRARY_TYPE_ERROR`
+/// instead.
+impl FromArg<'_> for Wxf {
+    unsafe fn from_arg(arg: &MArgument) -> Wxf {
+        match Self::try_from_arg(arg) {
+            Ok(wxf) => wxf,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    unsafe fn try_from_arg(arg: &MArgument) -> Result<Wxf, ArgTypeError> {
+        let bytes: &NumericArray<u8> = NumericArray::ref_cast(&*arg.numeric);
+
+        wxf::deserialize(bytes.as_slice())
+            .map(Wxf)
+            .map_err(|err| macro_utils::arg_type_error("Wxf", &err, "valid WXF data"))
+    }
+
+    fn parameter_type() -> Expr {
+        // {LibraryDataType[ByteArray], "Constant"}
+        Expr::normal(Symbol::new("System`List"), vec![
+            byte_array_type(),
+            Expr::string("Constant"),
+        ])
+    }
+}
+
+/// `LibraryDataType[ByteArray]`
+fn byte_array_type() -> Expr {
+    Expr::normal(Symbol::new("System`LibraryDataType"), vec![Expr::from(
+        Symbol::new("System`ByteArray"),
+    )])
+}
+
 //======================================
 // impl IntoArg
 //======================================
@@ -693,6 +867,18 @@ impl<T: crate::ImageData> IntoArg for Image<T> {
     }
 }
 
+/// Requires the `"image"` feature.
+#[cfg(feature = "image")]
+impl IntoArg for ::image::DynamicImage {
+    unsafe fn into_arg(self, arg: MArgument) {
+        *arg.image = Image::from_dynamic_image(&self).into_raw();
+    }
+
+    fn return_type() -> Expr {
+        Expr::from(Symbol::new("System`Image"))
+    }
+}
+
 impl IntoArg for DataStore {
     unsafe fn into_arg(self, arg: MArgument) {
         *arg.tensor = self.into_raw() as *mut _;
@@ -703,6 +889,62 @@ impl IntoArg for DataStore {
     }
 }
 
+macro_rules! impl_IntoArg_for_tuple {
+    ($($type:ident),*) => {
+        /// Returned as a [`DataStore`] containing an unnamed node for each element.
+        ///
+        /// Use [`#[export(unpack = "List")]`][crate::export] to have the generated
+        /// loader convert the returned data store into a `List`.
+        impl<$($type: IntoDataStoreNode),*> IntoArg for ($($type,)*) {
+            unsafe fn into_arg(self, arg: MArgument) {
+                self.into_data_store().into_arg(arg)
+            }
+
+            fn return_type() -> Expr {
+                DataStore::return_type()
+            }
+        }
+
+        impl<$($type: IntoDataStoreNode),*> crate::macro_utils::ReturnsDataStore
+            for ($($type,)*)
+        {
+        }
+    };
+}
+
+impl_IntoArg_for_tuple!(A);
+impl_IntoArg_for_tuple!(A, B);
+impl_IntoArg_for_tuple!(A, B, C);
+impl_IntoArg_for_tuple!(A, B, C, D);
+impl_IntoArg_for_tuple!(A, B, C, D, E);
+impl_IntoArg_for_tuple!(A, B, C, D, E, F);
+impl_IntoArg_for_tuple!(A, B, C, D, E, F, G);
+impl_IntoArg_for_tuple!(A, B, C, D, E, F, G, H);
+impl_IntoArg_for_tuple!(A, B, C, D, E, F, G, H, I);
+impl_IntoArg_for_tuple!(A, B, C, D, E, F, G, H, I, J);
+impl_IntoArg_for_tuple!(A, B, C, D, E, F, G, H, I, J, K);
+impl_IntoArg_for_tuple!(A, B, C, D, E, F, G, H, I, J, K, L);
+
+/// # Panics
+///
+/// [`into_arg()`][IntoArg::into_arg] will panic if the expression cannot be serialized.
+impl IntoArg for Wxf {
+    unsafe fn into_arg(self, arg: MArgument) {
+        let Wxf(expr) = self;
+
+        let bytes = match wxf::serialize(&expr) {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("unable to serialize WXF return value: {}", err),
+        };
+
+        NumericArray::from_slice(&bytes).into_arg(arg)
+    }
+
+    fn return_type() -> Expr {
+        byte_array_type()
+    }
+}
+
 //======================================
 // impl NativeFunction
 //======================================
@@ -757,8 +999,18 @@ macro_rules! impl_NativeFunction {
             $($type: FromArg<'a>),*
         {
             unsafe fn call(&self, args: &'a [MArgument], ret: MArgument) {
+                if let Err(NativeCallError::ArgType(err)) = self.try_call(args, ret) {
+                    panic!("{}", err);
+                }
+            }
+
+            unsafe fn try_call(
+                &self,
+                args: &'a [MArgument],
+                ret: MArgument,
+            ) -> Result<(), NativeCallError> {
                 // Re-use the $type name as the local variable names. E.g.
-                //     let A1 = A1::from_arg(..);
+                //     let A1 = A1::try_from_arg(..)?;
                 // This works because types and variable names are different namespaces.
                 #[allow(non_snake_case)]
                 let [$($type,)*] = match args {
@@ -772,12 +1024,15 @@ macro_rules! impl_NativeFunction {
 
                 $(
                     #[allow(non_snake_case)]
-                    let $type: $type = $type::from_arg($type);
+                    let $type: $type =
+                        $type::try_from_arg($type).map_err(NativeCallError::ArgType)?;
                 )*
 
                 let result: R = self($($type,)*);
 
                 result.into_arg(ret);
+
+                Ok(())
             }
 
             fn signature(&self) -> Result<(Vec<Expr>, Expr), String> {
@@ -830,6 +1085,96 @@ impl_NativeFunction!(A1, A2, A3, A4, A5, A6, A7, A8, A9, A10);
 impl_NativeFunction!(A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11);
 impl_NativeFunction!(A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12);
 
+//--------------------------------------
+// impl NativeFunction for `async fn`
+//--------------------------------------
+
+/// Wrapper used by [`#[export]`][crate::export] to export an `async fn`.
+///
+/// The future returned by the wrapped function is run to completion on the executor
+/// shared with [`AsyncTaskObject::spawn_future()`], while the calling thread checks for
+/// aborts. If the evaluation is aborted first, the future is dropped, no return value is
+/// written, and [`try_call()`][NativeFunction::try_call] returns
+/// [`NativeCallError::Aborted`].
+#[cfg(feature = "futures")]
+#[doc(hidden)]
+pub struct BlockOn<F>(pub F);
+
+macro_rules! impl_NativeFunction_for_BlockOn {
+    ($($type:ident),*) => {
+        #[cfg(feature = "futures")]
+        impl<'a, $($type,)* Fut> NativeFunction<'a> for BlockOn<fn($($type),*) -> Fut>
+        where
+            Fut: std::future::Future + Send + 'static,
+            Fut::Output: IntoArg + Send + 'static,
+            $($type: FromArg<'a>),*
+        {
+            unsafe fn call(&self, args: &'a [MArgument], ret: MArgument) {
+                // If the evaluation was aborted, the Kernel ignores the return value.
+                if let Err(NativeCallError::ArgType(err)) = self.try_call(args, ret) {
+                    panic!("{}", err);
+                }
+            }
+
+            unsafe fn try_call(
+                &self,
+                args: &'a [MArgument],
+                ret: MArgument,
+            ) -> Result<(), NativeCallError> {
+                // Re-use the $type name as the local variable names. See
+                // impl_NativeFunction!.
+                #[allow(non_snake_case)]
+                let ($($type,)*) = match args {
+                    [$($type,)*] => ($($type,)*),
+                    _ => panic!(
+                        "LibraryLink function number of arguments ({}) does not match \
+                        number of parameters",
+                        args.len()
+                    ),
+                };
+
+                $(
+                    #[allow(non_snake_case)]
+                    let $type: $type =
+                        $type::try_from_arg($type).map_err(NativeCallError::ArgType)?;
+                )*
+
+                let BlockOn(func) = *self;
+
+                let future: Fut = func($($type,)*);
+
+                match crate::async_tasks::block_on_abortable(future) {
+                    Some(result) => {
+                        result.into_arg(ret);
+                        Ok(())
+                    },
+                    None => Err(NativeCallError::Aborted),
+                }
+            }
+
+            fn signature(&self) -> Result<(Vec<Expr>, Expr), String> {
+                let param_tys = vec![$($type::parameter_type()),*];
+
+                Ok((param_tys, <Fut::Output as IntoArg>::return_type()))
+            }
+        }
+    }
+}
+
+impl_NativeFunction_for_BlockOn!();
+impl_NativeFunction_for_BlockOn!(A1);
+impl_NativeFunction_for_BlockOn!(A1, A2);
+impl_NativeFunction_for_BlockOn!(A1, A2, A3);
+impl_NativeFunction_for_BlockOn!(A1, A2, A3, A4);
+impl_NativeFunction_for_BlockOn!(A1, A2, A3, A4, A5);
+impl_NativeFunction_for_BlockOn!(A1, A2, A3, A4, A5, A6);
+impl_NativeFunction_for_BlockOn!(A1, A2, A3, A4, A5, A6, A7);
+impl_NativeFunction_for_BlockOn!(A1, A2, A3, A4, A5, A6, A7, A8);
+impl_NativeFunction_for_BlockOn!(A1, A2, A3, A4, A5, A6, A7, A8, A9);
+impl_NativeFunction_for_BlockOn!(A1, A2, A3, A4, A5, A6, A7, A8, A9, A10);
+impl_NativeFunction_for_BlockOn!(A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11);
+impl_NativeFunction_for_BlockOn!(A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12);
+
 //======================================
 // impl WstpFunction
 //======================================
@@ -938,6 +1283,174 @@ impl WstpFunction for fn(Vec<Expr>) {
     }
 }
 
+/// Implement [`WstpFunction`] for functions that read their arguments lazily using
+/// [`Args`], and return an [`Expr`].
+///
+/// See [`Args`] for an example.
+impl WstpFunction for fn(Args) -> Expr {
+    unsafe fn call(&self, link: &mut Link) {
+        let state = match get_args_count(link) {
+            Ok(count) => CallState::new(count),
+            Err(message) => panic!("WstpFunction: {}", message),
+        };
+
+        let result: Expr = self(state.args(link));
+
+        state.finish_with(link, |link| link.put_expr(&result));
+    }
+}
+
+/// Implement [`WstpFunction`] for functions that read their arguments lazily using
+/// [`Args`], and optionally write their result using a [`ResultWriter`][crate::ResultWriter].
+///
+/// See [`ResultWriter`][crate::ResultWriter] for an example.
+impl WstpFunction for fn(Args) {
+    unsafe fn call(&self, link: &mut Link) {
+        let state = match get_args_count(link) {
+            Ok(count) => CallState::new(count),
+            Err(message) => panic!("WstpFunction: {}", message),
+        };
+
+        let () = self(state.args(link));
+
+        state.finish(link);
+    }
+}
+
+/// Wrapper used by [`#[export(wstp)]`][crate::export#exportwstp] for functions that
+/// return `impl ExactSizeIterator`. The elements produced by the returned iterator are
+/// written to the link one at a time, as a `List`.
+///
+/// See `write_list_from_iter()` for how panics in the iterator are handled.
+#[doc(hidden)]
+pub struct StreamList<F>(pub F);
+
+impl<I> WstpFunction for StreamList<fn(Vec<Expr>) -> I>
+where
+    I: ExactSizeIterator,
+    I::Item: IntoLink,
+{
+    unsafe fn call(&self, link: &mut Link) {
+        let args: Vec<Expr> = match get_args_list(link) {
+            Ok(args) => args,
+            Err(message) => panic!("WstpFunction: {}", message),
+        };
+
+        let StreamList(func) = *self;
+
+        let iter: I = func(args);
+
+        if let Err(err) = write_list_from_iter(link, iter) {
+            panic!(
+                "WstpFunction: WSTP error writing return List expression to link: {}",
+                err
+            );
+        }
+    }
+}
+
+impl<I> WstpFunction for StreamList<fn(Args) -> I>
+where
+    I: ExactSizeIterator,
+    I::Item: IntoLink,
+{
+    unsafe fn call(&self, link: &mut Link) {
+        let state = match get_args_count(link) {
+            Ok(count) => CallState::new(count),
+            Err(message) => panic!("WstpFunction: {}", message),
+        };
+
+        let StreamList(func) = *self;
+
+        let iter: I = func(state.args(link));
+
+        state.finish_with(link, |link| write_list_from_iter(link, iter));
+    }
+}
+
+//======================================
+// impl AsyncFunction
+//======================================
+
+macro_rules! impl_AsyncFunction {
+    ($($type:ident),*) => {
+        impl<$($type),*> AsyncFunction for fn(AsyncTaskObject $(, $type)*)
+        where
+            $($type: for<'a> FromArg<'a> + Send + 'static),*
+        {
+            unsafe fn call(&self, args: &[MArgument], ret: MArgument) {
+                if let Err(err) = self.try_call(args, ret) {
+                    panic!("{}", err);
+                }
+            }
+
+            unsafe fn try_call(
+                &self,
+                args: &[MArgument],
+                ret: MArgument,
+            ) -> Result<(), ArgTypeError> {
+                // Re-use the $type name as the local variable names. See
+                // impl_NativeFunction!.
+                #[allow(non_snake_case)]
+                let ($($type,)*) = match args {
+                    [$($type,)*] => ($($type,)*),
+                    _ => panic!(
+                        "LibraryLink function number of arguments ({}) does not match \
+                        number of parameters",
+                        args.len()
+                    ),
+                };
+
+                $(
+                    #[allow(non_snake_case)]
+                    let $type: $type = $type::try_from_arg($type)?;
+                )*
+
+                let func: fn(AsyncTaskObject $(, $type)*) = *self;
+
+                // The arguments are only used by the background thread, which catches
+                // any panic before dropping them.
+                let args = AssertUnwindSafe(($($type,)*));
+
+                let task = crate::async_tasks::spawn_async_task_with_thread(
+                    move |task: AsyncTaskObject| {
+                        // Move the whole `args` value into this closure, not just its
+                        // fields.
+                        let args = args;
+                        #[allow(non_snake_case)]
+                        let AssertUnwindSafe(($($type,)*)) = args;
+
+                        func(task $(, $type)*)
+                    },
+                );
+
+                task.id().into_arg(ret);
+
+                Ok(())
+            }
+
+            fn signature(&self) -> Result<(Vec<Expr>, Expr), String> {
+                let param_tys = vec![$($type::parameter_type()),*];
+
+                Ok((param_tys, mint::return_type()))
+            }
+        }
+    }
+}
+
+impl_AsyncFunction!();
+impl_AsyncFunction!(A1);
+impl_AsyncFunction!(A1, A2);
+impl_AsyncFunction!(A1, A2, A3);
+impl_AsyncFunction!(A1, A2, A3, A4);
+impl_AsyncFunction!(A1, A2, A3, A4, A5);
+impl_AsyncFunction!(A1, A2, A3, A4, A5, A6);
+impl_AsyncFunction!(A1, A2, A3, A4, A5, A6, A7);
+impl_AsyncFunction!(A1, A2, A3, A4, A5, A6, A7, A8);
+impl_AsyncFunction!(A1, A2, A3, A4, A5, A6, A7, A8, A9);
+impl_AsyncFunction!(A1, A2, A3, A4, A5, A6, A7, A8, A9, A10);
+impl_AsyncFunction!(A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11);
+
 //----------------------------
 // Utilities
 //----------------------------
@@ -949,14 +1462,7 @@ fn get_args_list(link: &mut Link) -> Result<Vec<Expr>, String> {
 }
 
 fn get_args_list_impl(link: &mut Link) -> Result<Vec<Expr>, wstp::Error> {
-    let arg_count: usize = match link.test_head("List") {
-        Ok(count) => Ok(count),
-        Err(err) if err.code() == Some(wstp::sys::WSEGSEQ) => {
-            link.clear_error();
-            link.test_head("System`List")
-        },
-        Err(err) => Err(err),
-    }?;
+    let arg_count: usize = get_args_count_impl(link)?;
 
     let mut elements: Vec<Expr> = Vec::new();
 
@@ -967,3 +1473,21 @@ fn get_args_list_impl(link: &mut Link) -> Result<Vec<Expr>, wstp::Error> {
 
     Ok(elements)
 }
+
+/// Read the head of the argument `List` expression, returning the number of arguments.
+fn get_args_count(link: &mut Link) -> Result<usize, String> {
+    get_args_count_impl(link).map_err(|err: wstp::Error| {
+        format!("WSTP error reading argument List expression: {}", err)
+    })
+}
+
+fn get_args_count_impl(link: &mut Link) -> Result<usize, wstp::Error> {
+    match link.test_head("List") {
+        Ok(count) => Ok(count),
+        Err(err) if err.code() == Some(wstp::sys::WSEGSEQ) => {
+            link.clear_error();
+            link.test_head("System`List")
+        },
+        Err(err) => Err(err),
+    }
+}
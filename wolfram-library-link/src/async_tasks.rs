@@ -8,12 +8,28 @@
 use std::{
     ffi::{c_void, CString},
     panic,
+    sync::{Arc, Condvar, Mutex},
+    thread,
+    time::{Duration, Instant},
 };
 
-use static_assertions::assert_not_impl_any;
+use static_assertions::{assert_impl_all, assert_not_impl_any};
 
 use crate::{rtl, sys, DataStore};
 
+mod bounded;
+#[cfg(feature = "futures")]
+mod future;
+mod progress;
+
+pub use self::{
+    bounded::{BackpressurePolicy, BoundedEventSender, EventDelivery, EventQueueStats},
+    progress::Progress,
+};
+
+#[cfg(feature = "futures")]
+pub(crate) use self::future::block_on_abortable;
+
 
 /// Handle to a Wolfram Language [`AsynchronousTaskObject`][ref/AsynchronousTaskObject]<sub>WL</sub>
 /// instance.
@@ -21,6 +37,11 @@ use crate::{rtl, sys, DataStore};
 /// Use [`spawn_with_thread()`][AsyncTaskObject::spawn_with_thread] to spawn a new
 /// asynchronous task.
 ///
+/// If the `futures` feature is enabled,
+/// [`spawn_future()`][AsyncTaskObject::spawn_future] and
+/// [`spawn_stream()`][AsyncTaskObject::spawn_stream] can be used to spawn tasks that
+/// run on a shared executor instead of a dedicated thread.
+///
 /// [ref/AsynchronousTaskObject]: https://reference.wolfram.com/language/ref/AsynchronousTaskObject.html
 #[derive(Debug)]
 pub struct AsyncTaskObject(sys::mint);
@@ -28,6 +49,67 @@ pub struct AsyncTaskObject(sys::mint);
 // TODO: Determine if it would be safe for this type to implement Copy/Clone.
 assert_not_impl_any!(AsyncTaskObject: Copy, Clone);
 
+/// Handle used to raise events associated with an asynchronous task.
+///
+/// Unlike [`AsyncTaskObject`], this type implements [`Clone`], so it can be shared by
+/// any number of callbacks or threads that raise events for the same task. It is
+/// typically used with a task created by
+/// [`AsyncTaskObject::new_without_thread()`].
+///
+/// Use [`AsyncTaskObject::event_sender()`] to get the event sender for an existing task.
+#[derive(Debug, Clone)]
+pub struct AsyncEventSender {
+    task_id: sys::mint,
+}
+
+assert_impl_all!(AsyncEventSender: Send, Sync);
+
+/// Signal that becomes set when an asynchronous task is stopped or removed.
+///
+/// The signal is set when the task is stopped or removed from Wolfram Language code (for
+/// example using [`StopAsynchronousTask`][ref/StopAsynchronousTask]<sub>WL</sub> or
+/// [`RemoveAsynchronousTask`][ref/RemoveAsynchronousTask]<sub>WL</sub>), or when it is
+/// removed from Rust using [`AsyncTaskObject::remove()`].
+///
+/// Instances of this type are passed to the closure given to
+/// [`AsyncTaskObject::spawn_with_thread_joinable()`], and can also be created using
+/// [`AsyncTaskObject::cancellation_signal()`].
+///
+/// LibraryLink does not provide a notification when a task is stopped or removed, so the
+/// signal is read using [`asynchronousTaskAliveQ`][sys::st_WolframIOLibrary_Functions::asynchronousTaskAliveQ].
+/// [`wait_timeout()`][CancellationSignal::wait_timeout] checks it every 10
+/// milliseconds, so it can return up to 10 milliseconds after the task is stopped or
+/// removed.
+///
+/// If the `futures` feature is enabled, [`cancelled()`][CancellationSignal::cancelled]
+/// returns a future that completes when the signal is set.
+///
+/// [ref/StopAsynchronousTask]: https://reference.wolfram.com/language/ref/StopAsynchronousTask.html
+/// [ref/RemoveAsynchronousTask]: https://reference.wolfram.com/language/ref/RemoveAsynchronousTask.html
+#[derive(Debug, Clone)]
+pub struct CancellationSignal {
+    task_id: sys::mint,
+}
+
+assert_impl_all!(CancellationSignal: Send, Sync);
+
+/// Handle that can be used to wait for the background thread of an asynchronous task to
+/// finish.
+///
+/// Instances of this type are returned by
+/// [`AsyncTaskObject::spawn_with_thread_joinable()`].
+#[derive(Debug)]
+pub struct AsyncTaskJoinHandle {
+    task_id: sys::mint,
+    state: Arc<JoinState>,
+}
+
+#[derive(Debug, Default)]
+struct JoinState {
+    result: Mutex<Option<thread::Result<()>>>,
+    finished: Condvar,
+}
+
 
 //======================================
 // Impls
@@ -64,6 +146,143 @@ impl AsyncTaskObject {
         spawn_async_task_with_thread(f)
     }
 
+    /// Spawn a new Wolfram Language asynchronous task, and return a handle that can be
+    /// used to wait for its background thread to finish.
+    ///
+    /// This is like [`spawn_with_thread()`][AsyncTaskObject::spawn_with_thread], except
+    /// that `f` is also given a [`CancellationSignal`] that is set when the task is
+    /// stopped or removed. `f` should return promptly once the signal is set.
+    ///
+    /// [`AsyncTaskJoinHandle::join()`] can be used to wait until `f` has returned, for
+    /// example to ensure that any resources used by `f` have been released before a
+    /// library is unloaded.
+    ///
+    /// # Example
+    ///
+    /// Raise a `"tick"` event every 100 milliseconds until the task is stopped, and then
+    /// wait for the background thread to finish:
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use wolfram_library_link::{AsyncTaskObject, CancellationSignal, DataStore};
+    ///
+    /// let (task, handle) = AsyncTaskObject::spawn_with_thread_joinable(
+    ///     |task: AsyncTaskObject, cancelled: CancellationSignal| {
+    ///         while !cancelled.wait_timeout(Duration::from_millis(100)) {
+    ///             task.raise_async_event("tick", DataStore::new());
+    ///         }
+    ///     },
+    /// );
+    ///
+    /// // ...
+    ///
+    /// task.remove();
+    /// handle.join().expect("task thread panicked");
+    /// ```
+    pub fn spawn_with_thread_joinable<F>(f: F) -> (AsyncTaskObject, AsyncTaskJoinHandle)
+    where
+        F: FnOnce(AsyncTaskObject, CancellationSignal)
+            + Send
+            + panic::UnwindSafe
+            + 'static,
+    {
+        let state = Arc::new(JoinState::default());
+        let thread_state = Arc::clone(&state);
+
+        let task = spawn_async_task_with_thread(move |task: AsyncTaskObject| {
+            let cancelled = task.cancellation_signal();
+
+            let result = panic::catch_unwind(|| f(task, cancelled));
+
+            let JoinState {
+                result: ref result_slot,
+                ref finished,
+            } = *thread_state;
+
+            *result_slot.lock().unwrap() = Some(result);
+            finished.notify_all();
+        });
+
+        let handle = AsyncTaskJoinHandle {
+            task_id: task.id(),
+            state,
+        };
+
+        (task, handle)
+    }
+
+    /// Create a new Wolfram Language asynchronous task that does not have an associated
+    /// background thread.
+    ///
+    /// Events for the returned task can be raised using the returned
+    /// [`AsyncEventSender`], which can be cloned and sent to other threads. This is
+    /// useful when events are generated by callbacks owned by another library (for
+    /// example, a C library's event loop or a network client), or by a single worker
+    /// thread that services many tasks.
+    ///
+    /// Like [`spawn_with_thread()`][AsyncTaskObject::spawn_with_thread], this method
+    /// should be called from within a LibraryLink function that was called via
+    /// `` Internal`CreateAsynchronousTask ``. The task remains alive until it is
+    /// stopped or removed from the Wolfram Language.
+    ///
+    /// *LibraryLink C Function:* [`createAsynchronousTaskWithoutThread`][sys::st_WolframIOLibrary_Functions::createAsynchronousTaskWithoutThread].
+    ///
+    /// # Example
+    ///
+    /// Raise an event from a callback invoked by another library:
+    ///
+    /// ```no_run
+    /// # mod scope {
+    /// use wolfram_library_link::{self as wll, sys::mint, AsyncTaskObject, DataStore};
+    ///
+    /// # fn on_message(callback: impl Fn(&str) + Send + 'static) {}
+    /// #[wll::export]
+    /// fn start_listener() -> mint {
+    ///     let (task, sender) = AsyncTaskObject::new_without_thread();
+    ///
+    ///     on_message(move |message: &str| {
+    ///         let mut data = DataStore::new();
+    ///         data.add_str(message);
+    ///
+    ///         sender.raise_async_event("message", data);
+    ///     });
+    ///
+    ///     task.id()
+    /// }
+    /// # }
+    /// ```
+    pub fn new_without_thread() -> (AsyncTaskObject, AsyncEventSender) {
+        let task_id: sys::mint = unsafe { rtl::createAsynchronousTaskWithoutThread() };
+
+        (AsyncTaskObject(task_id), AsyncEventSender { task_id })
+    }
+
+    /// Get a [`CancellationSignal`] that is set when this task is stopped or removed.
+    pub fn cancellation_signal(&self) -> CancellationSignal {
+        CancellationSignal { task_id: self.id() }
+    }
+
+    /// Remove this asynchronous task.
+    ///
+    /// After the task is removed, [`is_alive()`][AsyncTaskObject::is_alive] returns
+    /// `false` and any [`CancellationSignal`] for this task is set. No further events
+    /// raised for this task will be processed.
+    ///
+    /// *LibraryLink C Function:* [`removeAsynchronousTask`][sys::st_WolframIOLibrary_Functions::removeAsynchronousTask].
+    pub fn remove(self) {
+        let AsyncTaskObject(id) = self;
+
+        unsafe {
+            rtl::removeAsynchronousTask(id);
+        }
+    }
+
+    /// Get an [`AsyncEventSender`] that can be used to raise events associated with this
+    /// task.
+    pub fn event_sender(&self) -> AsyncEventSender {
+        AsyncEventSender { task_id: self.id() }
+    }
+
     /// Returns the numeric ID which identifies this async object.
     pub fn id(&self) -> sys::mint {
         let AsyncTaskObject(id) = *self;
@@ -109,23 +328,124 @@ impl AsyncTaskObject {
     pub fn raise_async_event(&self, name: &str, data: DataStore) {
         let AsyncTaskObject(id) = *self;
 
-        let name = CString::new(name)
-            .expect("unable to convert raised async event name to CString");
+        raise_async_event(id, name, data)
+    }
+}
 
-        unsafe {
-            // raise_async_event(id, name.as_ptr() as *mut c_char, data.into_ptr());
-            rtl::raiseAsyncEvent(id, name.into_raw(), data.into_raw());
+impl AsyncEventSender {
+    /// Returns the numeric ID of the async task that events are raised for.
+    pub fn task_id(&self) -> sys::mint {
+        self.task_id
+    }
+
+    /// Returns whether the async task associated with this sender is still alive.
+    ///
+    /// *LibraryLink C Function:* [`asynchronousTaskAliveQ`][sys::st_WolframIOLibrary_Functions::asynchronousTaskAliveQ].
+    pub fn is_alive(&self) -> bool {
+        let is_alive: sys::mbool = unsafe { rtl::asynchronousTaskAliveQ(self.task_id) };
+
+        crate::bool_from_mbool(is_alive)
+    }
+
+    /// Raise a new named asynchronous event associated with the async task of this
+    /// sender.
+    ///
+    /// See also [`AsyncTaskObject::raise_async_event()`].
+    ///
+    /// *LibraryLink C Function:* [`raiseAsyncEvent`][sys::st_WolframIOLibrary_Functions::raiseAsyncEvent].
+    pub fn raise_async_event(&self, name: &str, data: DataStore) {
+        raise_async_event(self.task_id, name, data)
+    }
+}
+
+impl CancellationSignal {
+    /// Returns `true` if the task associated with this signal has been stopped or
+    /// removed.
+    ///
+    /// *LibraryLink C Function:* [`asynchronousTaskAliveQ`][sys::st_WolframIOLibrary_Functions::asynchronousTaskAliveQ].
+    pub fn is_cancelled(&self) -> bool {
+        let is_alive: sys::mbool = unsafe { rtl::asynchronousTaskAliveQ(self.task_id) };
+
+        !crate::bool_from_mbool(is_alive)
+    }
+
+    /// Block the current thread until this signal is set or `timeout` has elapsed.
+    ///
+    /// Returns `true` if the signal is set.
+    ///
+    /// This can be used in place of [`std::thread::sleep()`] in a task loop, so that the
+    /// loop exits promptly when the task is stopped.
+    ///
+    /// The signal is checked every 10 milliseconds, so this can return up to that long
+    /// after the task is stopped or removed.
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        /// How often to check whether the task is still alive.
+        const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            if self.is_cancelled() {
+                return true;
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+
+            thread::sleep(POLL_INTERVAL.min(deadline - now));
         }
     }
 }
 
-fn spawn_async_task_with_thread<F>(task: F) -> AsyncTaskObject
+impl AsyncTaskJoinHandle {
+    /// Returns the numeric ID of the async task this handle is associated with.
+    pub fn task_id(&self) -> sys::mint {
+        self.task_id
+    }
+
+    /// Returns `true` if the background thread of the task has finished running the
+    /// task closure.
+    pub fn is_finished(&self) -> bool {
+        self.state.result.lock().unwrap().is_some()
+    }
+
+    /// Wait for the background thread of the task to finish running the task closure.
+    ///
+    /// If the task closure panicked, the panic payload is returned as an error.
+    pub fn join(self) -> thread::Result<()> {
+        let JoinState {
+            ref result,
+            ref finished,
+        } = *self.state;
+
+        let mut result = finished
+            .wait_while(result.lock().unwrap(), |result| result.is_none())
+            .unwrap();
+
+        result.take().unwrap()
+    }
+}
+
+fn raise_async_event(id: sys::mint, name: &str, data: DataStore) {
+    let name =
+        CString::new(name).expect("unable to convert raised async event name to CString");
+
+    unsafe {
+        // raise_async_event(id, name.as_ptr() as *mut c_char, data.into_ptr());
+        rtl::raiseAsyncEvent(id, name.into_raw(), data.into_raw());
+    }
+}
+
+pub(crate) fn spawn_async_task_with_thread<F>(task: F) -> AsyncTaskObject
 where
     // Note: Ensure that the bound on async_task_thread_trampoline() is kept up-to-date
     //       with this bound.
-    F: FnMut(AsyncTaskObject) + Send + 'static + panic::UnwindSafe,
+    F: FnOnce(AsyncTaskObject) + Send + 'static + panic::UnwindSafe,
 {
-    // FIXME: This box is being leaked. Where is an appropriate place to drop it?
+    // Ownership of this box is taken back by async_task_thread_trampoline(), which is
+    // called exactly once, on the new background thread.
     let boxed_closure = Box::into_raw(Box::new(task));
 
     // Spawn a background thread using the user closure.
@@ -143,9 +463,9 @@ unsafe extern "C" fn async_task_thread_trampoline<F>(
     async_object_id: sys::mint,
     boxed_closure: *mut c_void,
 ) where
-    F: FnMut(AsyncTaskObject) + Send + 'static + panic::UnwindSafe,
+    F: FnOnce(AsyncTaskObject) + Send + 'static + panic::UnwindSafe,
 {
-    let boxed_closure: &mut F = &mut *(boxed_closure as *mut F);
+    let boxed_closure: Box<F> = Box::from_raw(boxed_closure as *mut F);
 
     // static_assertions::assert_impl_all!(F: panic::UnwindSafe);
 
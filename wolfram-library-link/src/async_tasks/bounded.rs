@@ -0,0 +1,363 @@
+//! Bounded delivery of asynchronous events, with acknowledgement from the Wolfram
+//! Language event handler.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Condvar, Mutex},
+    time::Duration,
+};
+
+use once_cell::sync::Lazy;
+use static_assertions::assert_impl_all;
+
+use super::{AsyncEventSender, AsyncTaskObject};
+use crate::{sys, DataStore};
+
+/// Bounded event channels, keyed by task ID.
+///
+/// Used by [`BoundedEventSender::acknowledge()`] to find the channel associated with a
+/// task.
+///
+/// A channel is kept here for as long as its task is alive, even after every
+/// [`BoundedEventSender`] for it has been dropped, so that acknowledgements arriving
+/// after the producer has finished still raise any deferred event. The channels of tasks
+/// that are no longer alive are removed when a new channel is created, or when an
+/// acknowledgement for that task arrives.
+static CHANNELS: Lazy<Mutex<HashMap<sys::mint, Arc<Channel>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// How often a blocked [`BoundedEventSender::raise_async_event()`] call checks whether
+/// the task is still alive.
+const ALIVE_CHECK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Raises asynchronous events for a task, limiting the number of events that have been
+/// raised but not yet acknowledged by the Wolfram Language event handler.
+///
+/// [`AsyncTaskObject::raise_async_event()`] does not wait for the raised event to be
+/// processed. If events are raised faster than the Wolfram Language can handle them, the
+/// queue of unprocessed events will grow without bound.
+///
+/// A `BoundedEventSender` tracks the number of *outstanding* events: events that have
+/// been raised, but whose handler has not yet called
+/// [`BoundedEventSender::acknowledge()`]. Once `capacity` events are outstanding, new
+/// events are handled according to the [`BackpressurePolicy`] of the sender.
+///
+/// Use [`AsyncTaskObject::bounded_event_sender()`] to create a `BoundedEventSender`.
+///
+/// # Acknowledging events
+///
+/// The Wolfram Language event handler acknowledges each event by calling a library
+/// function that calls [`BoundedEventSender::acknowledge()`] with the ID of the task:
+///
+/// ```no_run
+/// # mod scope {
+/// use wolfram_library_link::{self as wll, sys::mint, BoundedEventSender};
+///
+/// #[wll::export]
+/// fn acknowledge_event(task_id: mint) -> bool {
+///     BoundedEventSender::acknowledge(task_id)
+/// }
+/// # }
+/// ```
+///
+/// ```wolfram
+/// acknowledge = LibraryFunctionLoad["...", "acknowledge_event", {Integer}, "Boolean"];
+///
+/// handler[taskObject_, "item", data_] := (
+///     process[data];
+///     acknowledge[taskObject[[2]]]
+/// )
+/// ```
+#[derive(Debug, Clone)]
+pub struct BoundedEventSender {
+    channel: Arc<Channel>,
+}
+
+assert_impl_all!(BoundedEventSender: Send, Sync);
+
+/// What a [`BoundedEventSender`] does with a new event when the maximum number of
+/// unacknowledged events are outstanding.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BackpressurePolicy {
+    /// Block the thread raising the event until an outstanding event is acknowledged,
+    /// or the task is stopped.
+    Block,
+    /// Discard the new event.
+    DropNewest,
+    /// Hold on to the new event, and raise it as soon as an outstanding event is
+    /// acknowledged. If an event is already being held, it is replaced by the new event.
+    ///
+    /// This is useful for events that report the latest state of a computation, where
+    /// intermediate states can be skipped.
+    CoalesceLatest,
+}
+
+/// The outcome of [`BoundedEventSender::raise_async_event()`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EventDelivery {
+    /// The event was raised.
+    Raised,
+    /// The event will be raised when an outstanding event is acknowledged, unless it is
+    /// replaced by a newer event first.
+    ///
+    /// Only returned by senders using [`BackpressurePolicy::CoalesceLatest`].
+    Deferred,
+    /// The event was discarded, either because of the [`BackpressurePolicy`] of the
+    /// sender, or because the task is no longer alive.
+    Dropped,
+}
+
+/// Statistics about the events raised by a [`BoundedEventSender`].
+///
+/// Returned by [`BoundedEventSender::stats()`].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct EventQueueStats {
+    /// The maximum number of outstanding events.
+    pub capacity: usize,
+    /// The number of events that have been raised, but not yet acknowledged.
+    pub outstanding: usize,
+    /// The largest value of `outstanding` seen so far.
+    pub max_outstanding: usize,
+    /// The total number of events that have been raised.
+    pub raised: u64,
+    /// The total number of events that have been acknowledged.
+    pub acknowledged: u64,
+    /// The total number of events that were discarded.
+    pub dropped: u64,
+    /// The total number of deferred events that were replaced by a newer event before
+    /// they could be raised.
+    pub coalesced: u64,
+}
+
+#[derive(Debug)]
+struct Channel {
+    events: AsyncEventSender,
+    policy: BackpressurePolicy,
+    state: Mutex<ChannelState>,
+    /// Notified when an outstanding event is acknowledged.
+    acknowledged: Condvar,
+}
+
+#[derive(Debug)]
+struct ChannelState {
+    stats: EventQueueStats,
+    /// Event waiting to be raised, if the policy is
+    /// [`BackpressurePolicy::CoalesceLatest`].
+    deferred: Option<DeferredEvent>,
+}
+
+#[derive(Debug)]
+struct DeferredEvent {
+    name: String,
+    data: DataStore,
+}
+
+// Safety: A DataStore is an owned heap allocation that is not associated with any
+//         particular thread. DataStores are routinely constructed on background threads
+//         and passed to raiseAsyncEvent(), which is what happens to deferred events.
+unsafe impl Send for DeferredEvent {}
+
+impl AsyncTaskObject {
+    /// Create a [`BoundedEventSender`] that raises events for this task, allowing at most
+    /// `capacity` unacknowledged events to be outstanding at once.
+    ///
+    /// A task has at most one bounded event channel. If a bounded event sender was
+    /// already created for this task, the returned sender shares its channel, so that
+    /// every sender for the task sees every acknowledgement.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `capacity` is 0, or if a bounded event sender with a
+    /// different `capacity` or `policy` was already created for this task.
+    pub fn bounded_event_sender(
+        &self,
+        capacity: usize,
+        policy: BackpressurePolicy,
+    ) -> BoundedEventSender {
+        assert!(
+            capacity > 0,
+            "bounded event sender capacity must be non-zero"
+        );
+
+        let mut channels = CHANNELS.lock().unwrap();
+
+        remove_dead_channels(&mut channels);
+
+        if let Some(channel) = channels.get(&self.id()) {
+            let existing_capacity = channel.state.lock().unwrap().stats.capacity;
+
+            assert!(
+                existing_capacity == capacity && channel.policy == policy,
+                "bounded event sender for task {} already exists with capacity {} and \
+                policy {:?}",
+                self.id(),
+                existing_capacity,
+                channel.policy
+            );
+
+            return BoundedEventSender {
+                channel: Arc::clone(channel),
+            };
+        }
+
+        let channel = Arc::new(Channel {
+            events: self.event_sender(),
+            policy,
+            state: Mutex::new(ChannelState {
+                stats: EventQueueStats {
+                    capacity,
+                    ..EventQueueStats::default()
+                },
+                deferred: None,
+            }),
+            acknowledged: Condvar::new(),
+        });
+
+        channels.insert(self.id(), Arc::clone(&channel));
+
+        BoundedEventSender { channel }
+    }
+}
+
+impl BoundedEventSender {
+    /// Returns the numeric ID of the async task this sender raises events for.
+    pub fn task_id(&self) -> sys::mint {
+        self.channel.events.task_id()
+    }
+
+    /// Returns whether the async task this sender raises events for is still alive.
+    pub fn is_alive(&self) -> bool {
+        self.channel.events.is_alive()
+    }
+
+    /// Raise a new named asynchronous event, subject to the [`BackpressurePolicy`] of
+    /// this sender.
+    ///
+    /// See also [`AsyncTaskObject::raise_async_event()`].
+    pub fn raise_async_event(&self, name: &str, data: DataStore) -> EventDelivery {
+        let Channel {
+            ref events,
+            policy,
+            ref state,
+            ref acknowledged,
+        } = *self.channel;
+
+        let mut state = state.lock().unwrap();
+
+        loop {
+            if !events.is_alive() {
+                state.stats.dropped += 1;
+                return EventDelivery::Dropped;
+            }
+
+            if state.stats.outstanding < state.stats.capacity {
+                state.raise(events, name, data);
+                return EventDelivery::Raised;
+            }
+
+            match policy {
+                BackpressurePolicy::Block => {
+                    // Wake up regularly to check if the task has been stopped, in which
+                    // case no more acknowledgements will arrive.
+                    state = acknowledged
+                        .wait_timeout(state, ALIVE_CHECK_INTERVAL)
+                        .unwrap()
+                        .0;
+                },
+                BackpressurePolicy::DropNewest => {
+                    state.stats.dropped += 1;
+                    return EventDelivery::Dropped;
+                },
+                BackpressurePolicy::CoalesceLatest => {
+                    let event = DeferredEvent {
+                        name: name.to_owned(),
+                        data,
+                    };
+
+                    if state.deferred.replace(event).is_some() {
+                        state.stats.coalesced += 1;
+                    }
+
+                    return EventDelivery::Deferred;
+                },
+            }
+        }
+    }
+
+    /// Get statistics about the events raised by this sender.
+    pub fn stats(&self) -> EventQueueStats {
+        self.channel.state.lock().unwrap().stats
+    }
+
+    /// Acknowledge that the Wolfram Language event handler has finished processing an
+    /// event raised by the bounded event sender for the task with ID `task_id`.
+    ///
+    /// This is intended to be called from a library function that is called by the
+    /// event handler. See [`BoundedEventSender`] for an example.
+    ///
+    /// If an event was deferred by [`BackpressurePolicy::CoalesceLatest`], it is raised.
+    /// This happens even if every sender for the task has already been dropped, as long
+    /// as the task is still alive.
+    ///
+    /// Returns `false` if there is no bounded event sender for `task_id`, if the task is
+    /// no longer alive, or if it has no outstanding events.
+    pub fn acknowledge(task_id: sys::mint) -> bool {
+        let channel: Arc<Channel> = {
+            let mut channels = CHANNELS.lock().unwrap();
+
+            let channel = match channels.get(&task_id) {
+                Some(channel) => Arc::clone(channel),
+                None => return false,
+            };
+
+            // Only this entry is checked, so that acknowledging an event does not
+            // depend on the number of channels.
+            if !channel.events.is_alive() {
+                channels.remove(&task_id);
+                return false;
+            }
+
+            channel
+        };
+
+        let mut state = channel.state.lock().unwrap();
+
+        if state.stats.outstanding == 0 {
+            return false;
+        }
+
+        state.stats.outstanding -= 1;
+        state.stats.acknowledged += 1;
+
+        if let Some(DeferredEvent { name, data }) = state.deferred.take() {
+            state.raise(&channel.events, &name, data);
+        }
+
+        channel.acknowledged.notify_all();
+
+        true
+    }
+}
+
+/// Remove the channels of tasks that are no longer alive, which will not raise any more
+/// events.
+fn remove_dead_channels(channels: &mut HashMap<sys::mint, Arc<Channel>>) {
+    channels.retain(|_, channel| channel.events.is_alive());
+}
+
+impl ChannelState {
+    fn raise(&mut self, events: &AsyncEventSender, name: &str, data: DataStore) {
+        let EventQueueStats {
+            ref mut outstanding,
+            ref mut max_outstanding,
+            ref mut raised,
+            ..
+        } = self.stats;
+
+        *outstanding += 1;
+        *max_outstanding = (*max_outstanding).max(*outstanding);
+        *raised += 1;
+
+        events.raise_async_event(name, data);
+    }
+}
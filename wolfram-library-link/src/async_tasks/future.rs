@@ -0,0 +1,485 @@
+//! Asynchronous tasks driven by a [`Future`] or [`Stream`] on a shared executor.
+
+use std::{
+    any::Any,
+    collections::HashMap,
+    future::Future,
+    panic,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    task::{Context, Poll, Waker},
+    thread,
+    time::Duration,
+};
+
+use futures::{
+    executor::ThreadPool,
+    future::{self, Either, FutureExt},
+    stream::{Stream, StreamExt},
+};
+use once_cell::sync::Lazy;
+
+use super::{AsyncEventSender, AsyncTaskObject, CancellationSignal};
+use crate::{rtl, sys, DataStore};
+
+/// Executor shared by all tasks spawned using [`AsyncTaskObject::spawn_future()`] and
+/// [`AsyncTaskObject::spawn_stream()`], and by the futures returned by exported
+/// `async fn` functions.
+static EXECUTOR: Lazy<ThreadPool> = Lazy::new(|| {
+    ThreadPool::builder()
+        .name_prefix("wolfram-library-link-async-")
+        .create()
+        .expect("failed to create async task executor thread pool")
+});
+
+/// How often [`TaskRemoved`] futures check whether their task is still alive.
+const ALIVE_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// How often [`block_on_abortable()`] checks whether the current evaluation has been
+/// aborted while waiting for a future to complete.
+const ABORT_CHECK_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Task ID and waker of each pending [`TaskRemoved`] future, keyed by the
+/// [`TaskRemoved::key`] of the future.
+///
+/// Several futures can wait for the same task, so each future has its own entry, which
+/// is removed when the future completes or is dropped.
+///
+/// A background thread is started the first time a waker is added, which polls
+/// `asynchronousTaskAliveQ` for each task every [`ALIVE_POLL_INTERVAL`], and wakes the
+/// futures whose task is no longer alive.
+static WATCHED_TASKS: Lazy<(Mutex<Watchers>, Condvar)> = Lazy::new(|| {
+    thread::Builder::new()
+        .name("wolfram-library-link-async-watcher".to_owned())
+        .spawn(watch_tasks)
+        .expect("failed to spawn async task watcher thread");
+
+    (Mutex::new(HashMap::new()), Condvar::new())
+});
+
+type Watchers = HashMap<u64, (sys::mint, Waker)>;
+
+/// Source of [`TaskRemoved::key`] values.
+static NEXT_KEY: AtomicU64 = AtomicU64::new(0);
+
+/// Output of a future run by [`block_on_until()`], set when the future completes.
+struct Completion<T> {
+    output: Mutex<Option<thread::Result<T>>>,
+    ready: Condvar,
+}
+
+/// Future that completes when an asynchronous task is stopped or removed.
+struct TaskRemoved {
+    task_id: sys::mint,
+    /// Unique key of this future in [`WATCHED_TASKS`].
+    key: u64,
+}
+
+impl AsyncTaskObject {
+    /// Spawn a new Wolfram Language asynchronous task that runs a [`Future`] on a
+    /// shared executor.
+    ///
+    /// Unlike [`spawn_with_thread()`][AsyncTaskObject::spawn_with_thread], this does
+    /// not create a new thread for each task. All futures spawned using this function
+    /// are run on a single thread pool that is shared by every task created by this
+    /// library. Futures should therefore not block for long periods of time.
+    ///
+    /// `f` is called immediately with an `AsyncTaskObject` that can be used to raise
+    /// events, and the returned future is spawned on the executor. The task is removed
+    /// when the future completes.
+    ///
+    /// The `AsyncTaskObject` passed to `f` is the only owning handle to the task. The
+    /// returned [`AsyncEventSender`] can be used to get the
+    /// [`task_id()`][AsyncEventSender::task_id] of the new task, or to raise events for
+    /// it from elsewhere.
+    ///
+    /// If the task is stopped or removed from the Wolfram Language before the future
+    /// completes, the future is dropped without being polled again. Whether the task is
+    /// still alive is checked every 10 milliseconds while the future is waiting, so it
+    /// can take up to that long for the future to be dropped.
+    ///
+    /// If the future panics, an `"error"` event is raised with data `{message}`, where
+    /// `message` is the panic message, and then the task is removed.
+    ///
+    /// Like [`spawn_with_thread()`][AsyncTaskObject::spawn_with_thread], this method
+    /// should be called from within a LibraryLink function that was called via
+    /// `` Internal`CreateAsynchronousTask ``.
+    ///
+    /// *LibraryLink C Function:* [`createAsynchronousTaskWithoutThread`][crate::sys::st_WolframIOLibrary_Functions::createAsynchronousTaskWithoutThread].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # mod scope {
+    /// use wolfram_library_link::{self as wll, sys::mint, AsyncTaskObject, DataStore};
+    ///
+    /// #[wll::export]
+    /// fn start_task() -> mint {
+    ///     let sender = AsyncTaskObject::spawn_future(|task| async move {
+    ///         let mut data = DataStore::new();
+    ///         data.add_str("finished");
+    ///
+    ///         task.raise_async_event("status", data);
+    ///     });
+    ///
+    ///     sender.task_id()
+    /// }
+    /// # }
+    /// ```
+    pub fn spawn_future<F, Fut>(f: F) -> AsyncEventSender
+    where
+        F: FnOnce(AsyncTaskObject) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let (task, sender) = AsyncTaskObject::new_without_thread();
+        let task_id = sender.task_id();
+        let returned_sender = sender.clone();
+
+        let removed = TaskRemoved::new(&task);
+        let future = f(task);
+
+        EXECUTOR.spawn_ok(async move {
+            // Catch any panics which occur, so that they don't terminate the executor
+            // thread, and so the task is still removed.
+            let future = panic::AssertUnwindSafe(future).catch_unwind();
+            futures::pin_mut!(future);
+
+            // Drop the future if the task is removed before it completes. There is then
+            // no task to raise events for or to remove.
+            let result = match future::select(future, removed).await {
+                Either::Left((result, _)) => result,
+                Either::Right(((), _)) => return,
+            };
+
+            if let Err(payload) = result {
+                let mut data = DataStore::new();
+                data.add_str(&panic_message(&*payload));
+
+                sender.raise_async_event("error", data);
+            }
+
+            unsafe {
+                rtl::removeAsynchronousTask(task_id);
+            }
+        });
+
+        returned_sender
+    }
+
+    /// Spawn a new Wolfram Language asynchronous task that raises an event for each
+    /// item produced by `stream`.
+    ///
+    /// Each `(name, data)` item results in a call to
+    /// [`raise_async_event()`][AsyncTaskObject::raise_async_event]. The task is removed
+    /// when `stream` finishes.
+    ///
+    /// If the task is stopped or removed from the Wolfram Language, `stream` is dropped,
+    /// even if it is waiting for its next item. Whether the task is still alive is
+    /// checked every 10 milliseconds while the stream is waiting, so it can take up to
+    /// that long for the stream to be dropped.
+    ///
+    /// The stream is driven on the same shared executor used by
+    /// [`spawn_future()`][AsyncTaskObject::spawn_future].
+    ///
+    /// # Example
+    ///
+    /// Raise a `"count"` event for each of the numbers 1 through `n`:
+    ///
+    /// ```no_run
+    /// # mod scope {
+    /// use futures::stream::{self, StreamExt};
+    /// use wolfram_library_link::{self as wll, sys::mint, AsyncTaskObject, DataStore};
+    ///
+    /// #[wll::export]
+    /// fn start_counter(n: mint) -> mint {
+    ///     let stream = stream::iter(1..=n).map(|i| {
+    ///         let mut data = DataStore::new();
+    ///         data.add_i64(i);
+    ///
+    ///         ("count".to_owned(), data)
+    ///     });
+    ///
+    ///     AsyncTaskObject::spawn_stream(stream).task_id()
+    /// }
+    /// # }
+    /// ```
+    pub fn spawn_stream<S>(stream: S) -> AsyncEventSender
+    where
+        S: Stream<Item = (String, DataStore)> + Send + 'static,
+    {
+        AsyncTaskObject::spawn_future(|task| async move {
+            futures::pin_mut!(stream);
+
+            // If the task is removed while waiting for the next item, this future (and
+            // the stream) is dropped by `spawn_future()`.
+            while let Some((name, data)) = stream.next().await {
+                if !task.is_alive() {
+                    break;
+                }
+
+                task.raise_async_event(&name, data);
+            }
+        })
+    }
+}
+
+impl CancellationSignal {
+    /// Returns a future that completes when this signal is set.
+    ///
+    /// Like [`wait_timeout()`][CancellationSignal::wait_timeout], the signal is checked
+    /// every 10 milliseconds, so the future can complete up to that long after the task
+    /// is stopped or removed. Any number of these futures can wait for the same task.
+    ///
+    /// *This method is only available when the `futures` feature is enabled.*
+    pub fn cancelled(&self) -> impl Future<Output = ()> + Send + 'static {
+        TaskRemoved::from_task_id(self.task_id)
+    }
+}
+
+impl TaskRemoved {
+    fn new(task: &AsyncTaskObject) -> Self {
+        TaskRemoved::from_task_id(task.id())
+    }
+
+    fn from_task_id(task_id: sys::mint) -> Self {
+        TaskRemoved {
+            task_id,
+            key: NEXT_KEY.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+}
+
+impl Future for TaskRemoved {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        let (wakers, watch) = &*WATCHED_TASKS;
+
+        let mut wakers = wakers.lock().unwrap();
+
+        if !is_alive(self.task_id) {
+            wakers.remove(&self.key);
+            return Poll::Ready(());
+        }
+
+        wakers.insert(self.key, (self.task_id, cx.waker().clone()));
+        watch.notify_one();
+
+        Poll::Pending
+    }
+}
+
+impl Drop for TaskRemoved {
+    fn drop(&mut self) {
+        let (wakers, _) = &*WATCHED_TASKS;
+
+        wakers.lock().unwrap().remove(&self.key);
+    }
+}
+
+/// Body of the background thread started by [`WATCHED_TASKS`].
+fn watch_tasks() {
+    let (wakers, watch) = &*WATCHED_TASKS;
+
+    loop {
+        {
+            let mut wakers = watch
+                .wait_while(wakers.lock().unwrap(), |wakers| wakers.is_empty())
+                .unwrap();
+
+            // Check each task once, even if several futures are waiting for it.
+            let mut alive: HashMap<sys::mint, bool> = HashMap::new();
+
+            wakers.retain(|_, (task_id, waker)| {
+                let task_id = *task_id;
+
+                if *alive.entry(task_id).or_insert_with(|| is_alive(task_id)) {
+                    return true;
+                }
+
+                waker.wake_by_ref();
+                false
+            });
+        }
+
+        thread::sleep(ALIVE_POLL_INTERVAL);
+    }
+}
+
+/// Run `future` to completion on the [`EXECUTOR`], blocking the current thread and
+/// periodically checking if the current evaluation has been
+/// [`aborted()`][crate::aborted].
+///
+/// Returns `None` if the evaluation was aborted before `future` completed. In that case,
+/// `future` is dropped without being polled again, cancelling any work it has not yet
+/// performed. The future is dropped before this function returns.
+///
+/// If `future` panics, the panic is resumed on the current thread.
+///
+/// This must be called from the thread that the current LibraryLink function was
+/// called on.
+pub(crate) fn block_on_abortable<F>(future: F) -> Option<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    block_on_until(future, crate::aborted)
+}
+
+/// Run `future` to completion on the [`EXECUTOR`], or until `is_aborted` returns `true`.
+///
+/// `is_aborted` is called on the current thread every [`ABORT_CHECK_INTERVAL`] while
+/// the future has not completed.
+fn block_on_until<F>(future: F, mut is_aborted: impl FnMut() -> bool) -> Option<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let completion = Arc::new(Completion {
+        output: Mutex::new(None),
+        ready: Condvar::new(),
+    });
+
+    // The future is shared with the spawned task instead of being moved into it, so
+    // that it can be dropped from this thread if the evaluation is aborted.
+    //
+    // Catch any panics which occur, so that they don't terminate the executor thread.
+    let slot = Arc::new(Mutex::new(Some(
+        panic::AssertUnwindSafe(future).catch_unwind().boxed(),
+    )));
+
+    {
+        let completion = Arc::clone(&completion);
+        let slot = Arc::clone(&slot);
+
+        EXECUTOR.spawn_ok(future::poll_fn(move |cx| {
+            let mut slot = slot.lock().unwrap();
+
+            let future = match *slot {
+                Some(ref mut future) => future,
+                // The evaluation was aborted and the future has been dropped.
+                None => return Poll::Ready(()),
+            };
+
+            let output = match future.as_mut().poll(cx) {
+                Poll::Ready(output) => output,
+                Poll::Pending => return Poll::Pending,
+            };
+
+            *slot = None;
+
+            *completion.output.lock().unwrap() = Some(output);
+            completion.ready.notify_one();
+
+            Poll::Ready(())
+        }));
+    }
+
+    let mut output = completion.output.lock().unwrap();
+
+    loop {
+        match output.take() {
+            Some(Ok(output)) => return Some(output),
+            Some(Err(payload)) => panic::resume_unwind(payload),
+            None => (),
+        }
+
+        if is_aborted() {
+            drop(output);
+
+            // Drop the future, waiting for it to finish being polled if it is currently
+            // being polled by an executor thread. The future may have completed before
+            // it was dropped, but its output is discarded, because the evaluation was
+            // aborted.
+            let future = slot.lock().unwrap().take();
+            drop(future);
+
+            return None;
+        }
+
+        // Wait until the future completes, waking up regularly to check for aborts.
+        output = completion
+            .ready
+            .wait_timeout(output, ABORT_CHECK_INTERVAL)
+            .unwrap()
+            .0;
+    }
+}
+
+fn is_alive(task_id: sys::mint) -> bool {
+    let is_alive: sys::mbool = unsafe { rtl::asynchronousTaskAliveQ(task_id) };
+
+    crate::bool_from_mbool(is_alive)
+}
+
+/// Get the message of a panic from its payload.
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Rust panic (no message)".to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn test_block_on_until_completes() {
+        let output = block_on_until(async { 2 + 3 }, || false);
+
+        assert_eq!(output, Some(5));
+    }
+
+    #[test]
+    fn test_block_on_until_aborted() {
+        static DROPPED: AtomicBool = AtomicBool::new(false);
+
+        struct OnDrop;
+
+        impl Drop for OnDrop {
+            fn drop(&mut self) {
+                DROPPED.store(true, Ordering::SeqCst);
+            }
+        }
+
+        let checks = AtomicUsize::new(0);
+
+        let output = block_on_until(
+            async {
+                let _on_drop = OnDrop;
+
+                future::pending::<i64>().await
+            },
+            || checks.fetch_add(1, Ordering::SeqCst) == 3,
+        );
+
+        assert_eq!(output, None);
+        assert_eq!(checks.load(Ordering::SeqCst), 4);
+
+        // The future is dropped before `block_on_until()` returns.
+        assert!(DROPPED.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_block_on_until_panic() {
+        let result = panic::catch_unwind(|| {
+            block_on_until(async { panic!("future failed") }, || false)
+        });
+
+        let payload = result.unwrap_err();
+
+        assert_eq!(payload.downcast_ref::<&str>(), Some(&"future failed"));
+
+        // The executor threads are still usable after a panic.
+        assert_eq!(block_on_until(async { 1 }, || false), Some(1));
+    }
+}
@@ -0,0 +1,244 @@
+//! Progress reporting for long-running computations run in an asynchronous task.
+
+use std::{
+    fmt::Display,
+    panic,
+    time::{Duration, Instant},
+};
+
+use super::{spawn_async_task_with_thread, AsyncEventSender, AsyncTaskObject};
+use crate::{DataStore, IntoDataStoreNode};
+
+/// Reports the progress of a long-running computation using asynchronous task events.
+///
+/// A `Progress` raises three kinds of events for its task:
+///
+/// * `"progress"`, with data `{fraction, eta, message}`, where `fraction` is a real
+///   number between 0 and 1, `eta` is the estimated number of seconds remaining (or
+///   `-1.` if no estimate is available yet), and `message` is a string.
+/// * `"result"`, with data `{result}`, raised by [`finish()`][Progress::finish].
+/// * `"error"`, with data `{message}`, raised by [`fail()`][Progress::fail], or if the
+///   `Progress` is dropped without a result being reported.
+///
+/// `"progress"` events are rate-limited, so [`update()`][Progress::update] can be called
+/// as often as is convenient without flooding the Wolfram Language with events.
+///
+/// [`Progress::wolfram_handler_code()`] returns Wolfram Language code that runs a task
+/// which reports progress, displays a [`ProgressIndicator`][ref/ProgressIndicator]<sub>WL</sub>
+/// while it runs, and returns the final result.
+///
+/// # Example
+///
+/// ```no_run
+/// # mod scope {
+/// use wolfram_library_link::{self as wll, sys::mint, AsyncTaskObject, Progress};
+///
+/// #[wll::export]
+/// fn start_sum(n: mint) -> mint {
+///     let task = AsyncTaskObject::spawn_with_progress(move |progress: &mut Progress| {
+///         let mut sum: i64 = 0;
+///
+///         for i in 1..=n {
+///             if progress.is_cancelled() {
+///                 return Err("cancelled");
+///             }
+///
+///             sum += i;
+///             progress.update(i as f64 / n as f64);
+///         }
+///
+///         Ok(sum)
+///     });
+///
+///     task.id()
+/// }
+/// # }
+/// ```
+///
+/// [ref/ProgressIndicator]: https://reference.wolfram.com/language/ref/ProgressIndicator.html
+#[derive(Debug)]
+pub struct Progress {
+    events: AsyncEventSender,
+    min_interval: Duration,
+    started: Instant,
+    last_update: Option<Instant>,
+    /// Whether a `"result"` or `"error"` event has been raised.
+    finished: bool,
+}
+
+impl Progress {
+    /// The default minimum time between `"progress"` events.
+    pub const DEFAULT_MIN_INTERVAL: Duration = Duration::from_millis(100);
+
+    /// Construct a new `Progress` that reports progress for `task`.
+    pub fn new(task: &AsyncTaskObject) -> Self {
+        Progress::from_event_sender(task.event_sender())
+    }
+
+    /// Construct a new `Progress` that raises events using `events`.
+    pub fn from_event_sender(events: AsyncEventSender) -> Self {
+        Progress {
+            events,
+            min_interval: Progress::DEFAULT_MIN_INTERVAL,
+            started: Instant::now(),
+            last_update: None,
+            finished: false,
+        }
+    }
+
+    /// Set the minimum time between `"progress"` events.
+    ///
+    /// Calls to [`update()`][Progress::update] made sooner than `interval` after the
+    /// previous `"progress"` event was raised are ignored.
+    pub fn with_min_interval(mut self, interval: Duration) -> Self {
+        self.min_interval = interval;
+        self
+    }
+
+    /// Returns `true` if the task has been stopped or removed.
+    ///
+    /// Long-running computations should check this periodically, and stop early if it
+    /// returns `true`.
+    pub fn is_cancelled(&self) -> bool {
+        !self.events.is_alive()
+    }
+
+    /// Report that `fraction` of the computation has been completed.
+    ///
+    /// Returns `true` if a `"progress"` event was raised, or `false` if this update was
+    /// skipped due to rate-limiting.
+    pub fn update(&mut self, fraction: f64) -> bool {
+        self.update_with_message(fraction, "")
+    }
+
+    /// Report that `fraction` of the computation has been completed, along with a
+    /// message describing the current stage of the computation.
+    ///
+    /// Returns `true` if a `"progress"` event was raised, or `false` if this update was
+    /// skipped due to rate-limiting.
+    pub fn update_with_message(&mut self, fraction: f64, message: &str) -> bool {
+        let now = Instant::now();
+
+        if let Some(last_update) = self.last_update {
+            if now.duration_since(last_update) < self.min_interval {
+                return false;
+            }
+        }
+
+        self.last_update = Some(now);
+
+        let fraction = if fraction.is_nan() {
+            0.0
+        } else {
+            fraction.clamp(0.0, 1.0)
+        };
+
+        let eta = if fraction > 0.0 {
+            let elapsed = now.duration_since(self.started).as_secs_f64();
+
+            elapsed * (1.0 - fraction) / fraction
+        } else {
+            -1.0
+        };
+
+        let mut data = DataStore::new();
+        data.add_f64(fraction);
+        data.add_f64(eta);
+        data.add_str(message);
+
+        self.events.raise_async_event("progress", data);
+
+        true
+    }
+
+    /// Report the final result of the computation.
+    pub fn finish<T: IntoDataStoreNode>(mut self, result: T) {
+        let mut data = DataStore::new();
+        result.add_to_data_store(&mut data, None);
+
+        self.events.raise_async_event("result", data);
+        self.finished = true;
+    }
+
+    /// Report that the computation failed.
+    pub fn fail<E: Display>(mut self, error: E) {
+        self.raise_error(&error.to_string());
+    }
+
+    fn raise_error(&mut self, message: &str) {
+        let mut data = DataStore::new();
+        data.add_str(message);
+
+        self.events.raise_async_event("error", data);
+        self.finished = true;
+    }
+
+    /// Wolfram Language code for a function that runs an asynchronous task which reports
+    /// progress using [`Progress`].
+    ///
+    /// The code evaluates to a pure function that is called as `run[start, args]`, where
+    /// `start` is a library function that starts the task, such as one that calls
+    /// [`AsyncTaskObject::spawn_with_progress()`], and `args` is the list of arguments
+    /// to pass to `start`.
+    ///
+    /// `run` displays a [`ProgressIndicator`][ref/ProgressIndicator]<sub>WL</sub>, along
+    /// with the latest progress message and estimated time remaining, until the task
+    /// finishes. It returns the result of the computation, or a
+    /// [`Failure`][ref/Failure]<sub>WL</sub> if the computation failed. If the
+    /// evaluation is aborted, the task is removed.
+    ///
+    /// ```wolfram
+    /// run = ToExpression[LibraryFunctionLoad["libmy_lib", "progress_code", {}, "UTF8String"][]];
+    ///
+    /// run[LibraryFunctionLoad["libmy_lib", "start_sum", {Integer}, Integer], {10^6}]
+    /// ```
+    ///
+    /// [ref/ProgressIndicator]: https://reference.wolfram.com/language/ref/ProgressIndicator.html
+    /// [ref/Failure]: https://reference.wolfram.com/language/ref/Failure.html
+    pub fn wolfram_handler_code() -> &'static str {
+        include_str!("progress.wl")
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+
+        // Ensure the Wolfram Language side isn't left waiting for a result that will
+        // never arrive.
+        if std::thread::panicking() {
+            self.raise_error("computation panicked");
+        } else {
+            self.raise_error("computation finished without reporting a result");
+        }
+    }
+}
+
+impl AsyncTaskObject {
+    /// Spawn a new Wolfram Language asynchronous task that runs a computation which
+    /// reports its progress using [`Progress`].
+    ///
+    /// `f` is called on a new background thread. If `f` returns `Ok(result)`, a
+    /// `"result"` event is raised. If `f` returns `Err(error)` or panics, an `"error"`
+    /// event is raised.
+    ///
+    /// See [`Progress`] for a description of the events raised by this task, and an
+    /// example.
+    pub fn spawn_with_progress<F, T, E>(f: F) -> AsyncTaskObject
+    where
+        F: FnOnce(&mut Progress) -> Result<T, E> + Send + panic::UnwindSafe + 'static,
+        T: IntoDataStoreNode,
+        E: Display,
+    {
+        spawn_async_task_with_thread(move |task: AsyncTaskObject| {
+            let mut progress = Progress::new(&task);
+
+            match f(&mut progress) {
+                Ok(result) => progress.finish(result),
+                Err(error) => progress.fail(error),
+            }
+        })
+    }
+}
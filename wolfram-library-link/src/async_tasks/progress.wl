@@ -0,0 +1,48 @@
+Function[{start, args},
+    Module[{
+        fraction = 0.,
+        eta = Missing["NotAvailable"],
+        message = "",
+        done = False,
+        result,
+        task
+    },
+        task = Internal`CreateAsynchronousTask[
+            start,
+            args,
+            Function[{taskObject, event, data},
+                Switch[event,
+                    "progress",
+                        fraction = data[[1]];
+                        eta = If[data[[2]] < 0,
+                            Missing["NotAvailable"],
+                            Quantity[Round[data[[2]]], "Seconds"]
+                        ];
+                        message = data[[3]],
+                    "result",
+                        result = data[[1]];
+                        done = True,
+                    "error",
+                        result = Failure["NativeComputationError", <|
+                            "MessageTemplate" -> "`1`",
+                            "MessageParameters" -> {data[[1]]}
+                        |>];
+                        done = True
+                ]
+            ]
+        ];
+
+        CheckAbort[
+            Monitor[
+                While[!done, Pause[0.05]],
+                Row[{ProgressIndicator[fraction], message, eta}, "  "]
+            ],
+            RemoveAsynchronousTask[task];
+            Abort[]
+        ];
+
+        RemoveAsynchronousTask[task];
+
+        result
+    ]
+]
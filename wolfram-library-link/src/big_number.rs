@@ -0,0 +1,463 @@
+//! Arbitrary-precision numbers in WSTP functions.
+//!
+//! See [`BigReal`], and the `num-bigint` and `num-rational` features.
+
+use std::{
+    ffi::{CStr, CString},
+    fmt,
+    os::raw::{c_char, c_int},
+    ptr,
+    str::FromStr,
+};
+
+use wstp::{sys, Link, TokenType};
+
+use crate::{
+    expr::Expr,
+    wstp_args::{skip_elements, ArgsError, FromLink, IntoLink},
+};
+
+#[cfg(feature = "num-bigint")]
+use num_bigint::BigInt;
+
+#[cfg(feature = "num-rational")]
+use {crate::wstp_args::test_system_head, num_bigint::Sign, num_rational::BigRational};
+
+/// Arbitrary-precision real number, including its [precision][ref/Precision] or
+/// [accuracy][ref/Accuracy].
+///
+/// Reals read from a WSTP link using [`FromLink`] are converted to `f64` by WSTP, which
+/// loses any digits beyond machine precision. A `BigReal` instead stores the exact
+/// decimal digits that were sent on the link, so that a value like
+/// `N[Pi, 50]` can be read and written back without any loss of digits or precision
+/// information.
+///
+/// A `BigReal` is represented as a decimal significand multiplied by a power of 10. The
+/// [`Display`][fmt::Display] and [`FromStr`] implementations use Wolfram Language
+/// [`InputForm`][ref/InputForm] number syntax.
+///
+/// # Example
+///
+/// ```
+/// use wolfram_library_link::{BigReal, RealPrecision};
+///
+/// let real: BigReal = "3.14159265358979323846264338328`30.*^-2".parse().unwrap();
+///
+/// assert_eq!(real.significand(), "3.14159265358979323846264338328");
+/// assert_eq!(real.exponent(), -2);
+/// assert_eq!(real.precision(), RealPrecision::Precision(30.0));
+/// assert_eq!(real.to_f64(), 0.031415926535897934);
+/// ```
+///
+/// [ref/Precision]: https://reference.wolfram.com/language/ref/Precision.html
+/// [ref/Accuracy]: https://reference.wolfram.com/language/ref/Accuracy.html
+/// [ref/InputForm]: https://reference.wolfram.com/language/ref/InputForm.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct BigReal {
+    /// Decimal digits of the significand, with an optional leading `-`. Always contains
+    /// a decimal point.
+    significand: String,
+    /// Power of 10 that `significand` is multiplied by.
+    exponent: i64,
+    precision: RealPrecision,
+}
+
+/// The precision of a [`BigReal`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum RealPrecision {
+    /// Machine-precision real, as would be stored in an `f64`.
+    Machine,
+    /// Arbitrary-precision real with the specified number of significant decimal digits,
+    /// as returned by [`Precision`][ref/Precision].
+    ///
+    /// [ref/Precision]: https://reference.wolfram.com/language/ref/Precision.html
+    Precision(f64),
+    /// Arbitrary-precision real with the specified number of significant decimal digits
+    /// to the right of the decimal point, as returned by [`Accuracy`][ref/Accuracy].
+    ///
+    /// This is used by values like `0``20.` whose precision is not meaningful.
+    ///
+    /// [ref/Accuracy]: https://reference.wolfram.com/language/ref/Accuracy.html
+    Accuracy(f64),
+}
+
+/// Error returned when a string is not a valid [`BigReal`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseBigRealError {
+    input: String,
+}
+
+//======================================
+// Impls
+//======================================
+
+impl BigReal {
+    /// Construct a new real number equal to `significand * 10^exponent`.
+    ///
+    /// `significand` must be a decimal number with an optional leading `-` and an
+    /// optional decimal point, e.g. `"-12.5"`.
+    pub fn new(
+        significand: &str,
+        exponent: i64,
+        precision: RealPrecision,
+    ) -> Result<BigReal, ParseBigRealError> {
+        let digits = significand.strip_prefix('-').unwrap_or(significand);
+
+        let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+
+        let is_decimal = |part: &str| part.bytes().all(|byte: u8| byte.is_ascii_digit());
+
+        if integer.len() + fraction.len() == 0
+            || !is_decimal(integer)
+            || !is_decimal(fraction)
+        {
+            return Err(ParseBigRealError {
+                input: significand.to_owned(),
+            });
+        }
+
+        let mut significand = significand.to_owned();
+        if fraction.is_empty() && !significand.ends_with('.') {
+            significand.push('.');
+        }
+
+        Ok(BigReal {
+            significand,
+            exponent,
+            precision,
+        })
+    }
+
+    /// Decimal digits of the significand of this number, with an optional leading `-`.
+    pub fn significand(&self) -> &str {
+        &self.significand
+    }
+
+    /// Power of 10 that the [`significand()`][BigReal::significand] is multiplied by.
+    pub fn exponent(&self) -> i64 {
+        self.exponent
+    }
+
+    #[allow(missing_docs)]
+    pub fn precision(&self) -> RealPrecision {
+        self.precision
+    }
+
+    /// Convert this number to the nearest `f64`.
+    pub fn to_f64(&self) -> f64 {
+        format!("{}0e{}", self.significand, self.exponent)
+            .parse()
+            .expect("BigReal::to_f64(): invalid significand")
+    }
+}
+
+/// Parses a number in Wolfram Language [`InputForm`][ref/InputForm] syntax, e.g.
+/// `-1.5`, ``1.5`20.``, ``0``10.``, or ``2.5`20.*^-30``.
+///
+/// [ref/InputForm]: https://reference.wolfram.com/language/ref/InputForm.html
+impl FromStr for BigReal {
+    type Err = ParseBigRealError;
+
+    fn from_str(input: &str) -> Result<BigReal, ParseBigRealError> {
+        let invalid = || ParseBigRealError {
+            input: input.to_owned(),
+        };
+
+        let (number, exponent) = match input.split_once("*^") {
+            Some((number, exponent)) => (number, Some(exponent)),
+            // Also accept C-style exponents, e.g. "1.5e-20".
+            None => match input.split_once(['e', 'E']) {
+                Some((number, exponent)) => (number, Some(exponent)),
+                None => (input, None),
+            },
+        };
+
+        let exponent = match exponent {
+            Some(exponent) => exponent.parse().map_err(|_| invalid())?,
+            None => 0,
+        };
+
+        let parse_digits = |digits: &str| match digits.trim_end_matches('.') {
+            "" => Err(invalid()),
+            digits => digits.parse::<f64>().map_err(|_| invalid()),
+        };
+
+        let (significand, precision) = match number.split_once('`') {
+            None => (number, RealPrecision::Machine),
+            Some((significand, "")) => (significand, RealPrecision::Machine),
+            Some((significand, marker)) => match marker.strip_prefix('`') {
+                Some(accuracy) => (
+                    significand,
+                    RealPrecision::Accuracy(parse_digits(accuracy)?),
+                ),
+                None => (significand, RealPrecision::Precision(parse_digits(marker)?)),
+            },
+        };
+
+        BigReal::new(significand, exponent, precision).map_err(|_| invalid())
+    }
+}
+
+/// Formats this number using Wolfram Language [`InputForm`][ref/InputForm] syntax.
+///
+/// [ref/InputForm]: https://reference.wolfram.com/language/ref/InputForm.html
+impl fmt::Display for BigReal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let BigReal {
+            significand,
+            exponent,
+            precision,
+        } = self;
+
+        write!(f, "{}", significand)?;
+
+        match precision {
+            RealPrecision::Machine => (),
+            RealPrecision::Precision(precision) => write!(f, "`{:?}", precision)?,
+            RealPrecision::Accuracy(accuracy) => write!(f, "``{:?}", accuracy)?,
+        }
+
+        if *exponent != 0 {
+            write!(f, "*^{}", exponent)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for ParseBigRealError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid real number: {:?}", self.input)
+    }
+}
+
+impl std::error::Error for ParseBigRealError {}
+
+//======================================
+// FromLink and IntoLink Impls
+//======================================
+
+/// Reads a real number without converting it to `f64`.
+impl FromLink for BigReal {
+    fn from_link(link: &mut Link) -> Result<Self, ArgsError> {
+        let (token_type, number) = get_number_string(link, "Real")?;
+
+        if token_type != TokenType::Real {
+            return Err(ArgsError::unexpected_type("Real", &number));
+        }
+
+        BigReal::from_str(&number)
+            .map_err(|_| ArgsError::unexpected_type("Real", &number))
+    }
+}
+
+/// Writes a real number without converting it to `f64`.
+impl IntoLink for &BigReal {
+    fn into_link(self, link: &mut Link) -> Result<(), wstp::Error> {
+        if self.precision == RealPrecision::Machine {
+            return link.put_f64(self.to_f64());
+        }
+
+        let number = CString::new(self.to_string()).expect("BigReal contains a nul byte");
+
+        unsafe {
+            if sys::WSPutRealNumberAsString(link.raw_link(), number.as_ptr()) == 0 {
+                return Err(link_error(link));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes a real number without converting it to `f64`.
+impl IntoLink for BigReal {
+    fn into_link(self, link: &mut Link) -> Result<(), wstp::Error> {
+        (&self).into_link(link)
+    }
+}
+
+/// Reads an integer of any size.
+///
+/// This impl is only available when the `"num-bigint"` feature of `wolfram-library-link`
+/// is enabled.
+#[cfg(feature = "num-bigint")]
+impl FromLink for BigInt {
+    fn from_link(link: &mut Link) -> Result<Self, ArgsError> {
+        let (token_type, number) = get_number_string(link, "Integer")?;
+
+        match token_type {
+            TokenType::Integer => BigInt::from_str(&number)
+                .map_err(|_| ArgsError::unexpected_type("Integer", &number)),
+            _ => Err(ArgsError::unexpected_type("Integer", &number)),
+        }
+    }
+}
+
+/// Writes an integer of any size.
+///
+/// This impl is only available when the `"num-bigint"` feature of `wolfram-library-link`
+/// is enabled.
+#[cfg(feature = "num-bigint")]
+impl IntoLink for &BigInt {
+    fn into_link(self, link: &mut Link) -> Result<(), wstp::Error> {
+        if let Ok(value) = i64::try_from(self) {
+            return link.put_i64(value);
+        }
+
+        put_integer_digits(link, &self.to_string())
+    }
+}
+
+/// Writes an integer of any size.
+///
+/// This impl is only available when the `"num-bigint"` feature of `wolfram-library-link`
+/// is enabled.
+#[cfg(feature = "num-bigint")]
+impl IntoLink for BigInt {
+    fn into_link(self, link: &mut Link) -> Result<(), wstp::Error> {
+        (&self).into_link(link)
+    }
+}
+
+/// Reads a `Rational[numerator, denominator]` expression, or an integer, of any size.
+///
+/// This impl is only available when the `"num-rational"` feature of
+/// `wolfram-library-link` is enabled.
+#[cfg(feature = "num-rational")]
+impl FromLink for BigRational {
+    fn from_link(link: &mut Link) -> Result<Self, ArgsError> {
+        match test_system_head(link, "Rational")? {
+            Some(2) => {
+                let numerator = BigInt::from_link(link)?;
+                let denominator = BigInt::from_link(link)?;
+
+                if denominator.sign() == Sign::NoSign {
+                    return Err(ArgsError::unexpected_type(
+                        "Rational",
+                        &format!("Rational[{}, 0]", numerator),
+                    ));
+                }
+
+                Ok(BigRational::new(numerator, denominator))
+            },
+            Some(length) => {
+                skip_elements(link, length)?;
+
+                Err(ArgsError::unexpected_type(
+                    "Rational",
+                    &format!("Rational with {} elements", length),
+                ))
+            },
+            None => Ok(BigRational::from_integer(BigInt::from_link(link)?)),
+        }
+    }
+}
+
+/// Writes a `Rational[numerator, denominator]` expression, or an integer if the
+/// denominator is 1.
+///
+/// This impl is only available when the `"num-rational"` feature of
+/// `wolfram-library-link` is enabled.
+#[cfg(feature = "num-rational")]
+impl IntoLink for &BigRational {
+    fn into_link(self, link: &mut Link) -> Result<(), wstp::Error> {
+        if self.is_integer() {
+            return self.numer().into_link(link);
+        }
+
+        link.put_function("System`Rational", 2)?;
+        self.numer().into_link(link)?;
+        self.denom().into_link(link)
+    }
+}
+
+/// Writes a `Rational[numerator, denominator]` expression, or an integer if the
+/// denominator is 1.
+///
+/// This impl is only available when the `"num-rational"` feature of
+/// `wolfram-library-link` is enabled.
+#[cfg(feature = "num-rational")]
+impl IntoLink for BigRational {
+    fn into_link(self, link: &mut Link) -> Result<(), wstp::Error> {
+        (&self).into_link(link)
+    }
+}
+
+//======================================
+// Helpers
+//======================================
+
+/// Read the next expression on `link`, which must be an integer or real, as a string in
+/// Wolfram Language [`InputForm`][ref/InputForm] syntax.
+///
+/// This preserves all of the digits of the number, unlike reading it as an `i64` or
+/// `f64`.
+///
+/// [ref/InputForm]: https://reference.wolfram.com/language/ref/InputForm.html
+fn get_number_string(
+    link: &mut Link,
+    expected: &str,
+) -> Result<(TokenType, String), ArgsError> {
+    let token_type = link.get_type()?;
+
+    let found = match token_type {
+        TokenType::Integer | TokenType::Real => {
+            let mut number: *const c_char = ptr::null();
+
+            unsafe {
+                let raw_link = link.raw_link();
+
+                if sys::WSGetNumberAsString(raw_link, &mut number) == 0 {
+                    return Err(ArgsError::from(link_error(link)));
+                }
+
+                let string = CStr::from_ptr(number).to_string_lossy().into_owned();
+
+                sys::WSReleaseString(raw_link, number);
+
+                return Ok((token_type, string));
+            }
+        },
+        TokenType::String => Expr::string(link.get_string_ref()?.as_str()).to_string(),
+        TokenType::Symbol => link.get_symbol_ref()?.as_str().to_owned(),
+        TokenType::Function => {
+            let length = link.get_arg_count()?;
+            let head = link.get_expr()?;
+
+            skip_elements(link, length)?;
+
+            format!("{}[...]", head)
+        },
+    };
+
+    Err(ArgsError::unexpected_type(expected, &found))
+}
+
+/// Write an integer of any size from its decimal digits, with an optional leading `-`.
+pub(crate) fn put_integer_digits(
+    link: &mut Link,
+    digits: &str,
+) -> Result<(), wstp::Error> {
+    let length = c_int::try_from(digits.len()).expect("integer has too many digits");
+
+    link.put_raw_type(i32::from(sys::WSTKINT))?;
+
+    unsafe {
+        let raw_link = link.raw_link();
+
+        if sys::WSPutSize(raw_link, length) == 0
+            || sys::WSPutData(raw_link, digits.as_ptr() as *const c_char, length) == 0
+        {
+            return Err(link_error(link));
+        }
+    }
+
+    Ok(())
+}
+
+/// Get the error set on `link` by a WSTP C API function that returned a failure code.
+fn link_error(link: &Link) -> wstp::Error {
+    link.error()
+        .expect("WSTP function failed without setting an error on the link")
+}
@@ -1,4 +1,5 @@
 use std::{
+    collections::HashMap,
     ffi::{CStr, CString},
     fmt,
     marker::PhantomData,
@@ -7,12 +8,23 @@ use std::{
 
 
 use once_cell::sync::OnceCell;
+use ref_cast::RefCast;
 use static_assertions::assert_not_impl_any;
 
 use crate::{
     rtl,
     sys::{self, mcomplex, mint, mreal},
-    FromArg, Image, NumericArray,
+    Image, NumericArray,
+};
+
+mod convert;
+pub(crate) mod expr;
+
+pub use self::expr::DataStoreFromExprError;
+
+pub use self::convert::{
+    FromDataStore, FromDataStoreError, FromDataStoreErrorKind, FromDataStoreNode,
+    IntoDataStore, IntoDataStoreNode,
 };
 
 
@@ -45,8 +57,10 @@ use crate::{
 /// data.add_str("hello");
 /// data.add_bool(false);
 /// ```
-// TODO: Provide better Debug formatting for this type.
-#[derive(Debug)]
+///
+/// The `Display` implementation of `DataStore` formats it using Wolfram Language syntax.
+/// Use [`DataStore::to_expr()`] and [`DataStore::from_expr()`] to convert between
+/// `DataStore` and [`Expr`][crate::expr::Expr].
 #[derive(ref_cast::RefCast)]
 #[repr(transparent)]
 pub struct DataStore(sys::DataStore);
@@ -96,19 +110,44 @@ pub struct DataStoreNode<'store> {
 /// [`DataStoreNode`]s can contain any value that can be stored in an
 /// [`MArgument`][sys::MArgument].
 ///
+/// New variants may be added to this enum in the future, so matches on it must include
+/// a wildcard arm.
+///
 // TODO: Rename this to `ArgValue`, as this is based on `MArgument`?
 #[allow(missing_docs)]
+#[derive(Copy, Clone)]
+#[non_exhaustive]
 pub enum DataStoreNodeValue<'node> {
     Boolean(bool),
     Integer(mint),
     Real(mreal),
     Complex(mcomplex),
     Str(&'node str),
+    Tensor(TensorRef<'node>),
+    SparseArray(SparseArrayRef<'node>),
     NumericArray(&'node NumericArray),
     Image(&'node Image),
     DataStore(&'node DataStore),
 }
 
+/// `MTensor` value borrowed from a [`DataStoreNode`].
+///
+/// Instances of this type are contained in [`DataStoreNodeValue::Tensor`].
+#[derive(Copy, Clone)]
+pub struct TensorRef<'node> {
+    raw: sys::MTensor,
+    marker: PhantomData<&'node DataStore>,
+}
+
+/// `MSparseArray` value borrowed from a [`DataStoreNode`].
+///
+/// Instances of this type are contained in [`DataStoreNodeValue::SparseArray`].
+#[derive(Copy, Clone)]
+pub struct SparseArrayRef<'node> {
+    raw: sys::MSparseArray,
+    marker: PhantomData<&'node DataStore>,
+}
+
 /// Iterator over the [`DataStoreNode`]s stored in a [`DataStore`].
 ///
 /// Instances of this type are returned by [`DataStore::nodes()`].
@@ -302,6 +341,61 @@ impl DataStore {
         unsafe { rtl::DataStore_addMNumericArray(ds, array) }
     }
 
+    /// Add an [`Image`] value to this `DataStore`.
+    ///
+    /// *LibraryLink C Function:* [`DataStore_addMImage`][rtl::DataStore_addMImage].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use wolfram_library_link::{ColorSpace, DataStore, Image, UninitImage};
+    ///
+    /// let mut image: UninitImage<u8> =
+    ///     UninitImage::new_2d(640, 480, 3, ColorSpace::RGB, true);
+    /// image.zero();
+    /// let image: Image<u8> = unsafe { image.assume_init() };
+    ///
+    /// let mut store = DataStore::new();
+    ///
+    /// store.add_image(image);
+    /// ```
+    pub fn add_image<T>(&mut self, image: Image<T>) {
+        let DataStore(ds) = *self;
+        let image = unsafe { image.into_raw() };
+
+        unsafe { rtl::DataStore_addMImage(ds, image) }
+    }
+
+    /// Add a raw [`MTensor`][sys::MTensor] value to this `DataStore`.
+    ///
+    /// *LibraryLink C Function:* [`DataStore_addMTensor`][rtl::DataStore_addMTensor].
+    ///
+    /// # Safety
+    ///
+    /// `tensor` must be a valid `MTensor` instance. Ownership of `tensor` is transferred
+    /// to this `DataStore`; `tensor` must not be used or freed after calling this
+    /// function.
+    pub unsafe fn add_tensor_raw(&mut self, tensor: sys::MTensor) {
+        let DataStore(ds) = *self;
+
+        rtl::DataStore_addMTensor(ds, tensor)
+    }
+
+    /// Add a raw [`MSparseArray`][sys::MSparseArray] value to this `DataStore`.
+    ///
+    /// *LibraryLink C Function:* [`DataStore_addMSparseArray`][rtl::DataStore_addMSparseArray].
+    ///
+    /// # Safety
+    ///
+    /// `array` must be a valid `MSparseArray` instance. Ownership of `array` is
+    /// transferred to this `DataStore`; `array` must not be used or freed after calling
+    /// this function.
+    pub unsafe fn add_sparse_array_raw(&mut self, array: sys::MSparseArray) {
+        let DataStore(ds) = *self;
+
+        rtl::DataStore_addMSparseArray(ds, array)
+    }
+
     //==================================
     // Named data
     //==================================
@@ -449,6 +543,222 @@ impl DataStore {
         }
     }
 
+    /// Add an [`Image`] value to this `DataStore`.
+    ///
+    /// See also [`DataStore::add_image()`].
+    ///
+    /// *LibraryLink C Function:* [`DataStore_addNamedMImage`][rtl::DataStore_addNamedMImage].
+    pub fn add_named_image<T>(&mut self, name: &str, image: Image<T>) {
+        let DataStore(ds) = *self;
+        let image = unsafe { image.into_raw() };
+
+        let name = CString::new(name).expect("could not convert &str to CString");
+
+        unsafe { rtl::DataStore_addNamedMImage(ds, name.as_ptr() as *mut c_char, image) }
+    }
+
+    /// Add a raw [`MTensor`][sys::MTensor] value to this `DataStore`.
+    ///
+    /// See also [`DataStore::add_tensor_raw()`].
+    ///
+    /// *LibraryLink C Function:* [`DataStore_addNamedMTensor`][rtl::DataStore_addNamedMTensor].
+    ///
+    /// # Safety
+    ///
+    /// `tensor` must be a valid `MTensor` instance. Ownership of `tensor` is transferred
+    /// to this `DataStore`; `tensor` must not be used or freed after calling this
+    /// function.
+    pub unsafe fn add_named_tensor_raw(&mut self, name: &str, tensor: sys::MTensor) {
+        let DataStore(ds) = *self;
+
+        let name = CString::new(name).expect("could not convert &str to CString");
+
+        rtl::DataStore_addNamedMTensor(ds, name.as_ptr() as *mut c_char, tensor)
+    }
+
+    /// Add a raw [`MSparseArray`][sys::MSparseArray] value to this `DataStore`.
+    ///
+    /// See also [`DataStore::add_sparse_array_raw()`].
+    ///
+    /// *LibraryLink C Function:* [`DataStore_addNamedMSparseArray`][rtl::DataStore_addNamedMSparseArray].
+    ///
+    /// # Safety
+    ///
+    /// `array` must be a valid `MSparseArray` instance. Ownership of `array` is
+    /// transferred to this `DataStore`; `array` must not be used or freed after calling
+    /// this function.
+    pub unsafe fn add_named_sparse_array_raw(
+        &mut self,
+        name: &str,
+        array: sys::MSparseArray,
+    ) {
+        let DataStore(ds) = *self;
+
+        let name = CString::new(name).expect("could not convert &str to CString");
+
+        rtl::DataStore_addNamedMSparseArray(ds, name.as_ptr() as *mut c_char, array)
+    }
+
+    //==================================
+    // Keyed access
+    //==================================
+
+    /// Get the value of the first node in this `DataStore` named `name`.
+    ///
+    /// Returns `None` if this `DataStore` does not contain a node named `name`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use wolfram_library_link::{DataStore, DataStoreNodeValue};
+    ///
+    /// let mut store = DataStore::new();
+    /// store.add_named_i64("count", 5);
+    ///
+    /// assert!(matches!(store.get("count"), Some(DataStoreNodeValue::Integer(5))));
+    /// assert!(store.get("missing").is_none());
+    /// ```
+    pub fn get<'s>(&'s self, name: &str) -> Option<DataStoreNodeValue<'s>> {
+        self.get_node(name).map(DataStoreNode::into_value)
+    }
+
+    /// Get the first node in this `DataStore` named `name`.
+    pub fn get_node<'s>(&'s self, name: &str) -> Option<DataStoreNode<'s>> {
+        self.nodes().find(|node| node.name_str() == Some(name))
+    }
+
+    /// Returns `true` if this `DataStore` contains a node named `name`.
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.get_node(name).is_some()
+    }
+
+    /// Get the `bool` value of the node named `name`.
+    ///
+    /// Returns `None` if there is no node named `name`, or if its value is not a
+    /// `Boolean`.
+    pub fn get_bool(&self, name: &str) -> Option<bool> {
+        match self.get(name)? {
+            DataStoreNodeValue::Boolean(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Get the `i64` value of the node named `name`.
+    ///
+    /// Returns `None` if there is no node named `name`, or if its value is not an
+    /// `Integer`.
+    pub fn get_i64(&self, name: &str) -> Option<i64> {
+        match self.get(name)? {
+            DataStoreNodeValue::Integer(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Get the `f64` value of the node named `name`.
+    ///
+    /// Returns `None` if there is no node named `name`, or if its value is not a
+    /// `Real`.
+    pub fn get_f64(&self, name: &str) -> Option<f64> {
+        match self.get(name)? {
+            DataStoreNodeValue::Real(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Get the [`mcomplex`][sys::mcomplex] value of the node named `name`.
+    ///
+    /// Returns `None` if there is no node named `name`, or if its value is not a
+    /// `Complex`.
+    pub fn get_complex_f64(&self, name: &str) -> Option<sys::mcomplex> {
+        match self.get(name)? {
+            DataStoreNodeValue::Complex(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Get the [`str`] value of the node named `name`.
+    ///
+    /// Returns `None` if there is no node named `name`, or if its value is not a
+    /// `String`.
+    pub fn get_str<'s>(&'s self, name: &str) -> Option<&'s str> {
+        match self.get(name)? {
+            DataStoreNodeValue::Str(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Get the [`NumericArray`] value of the node named `name`.
+    ///
+    /// Returns `None` if there is no node named `name`, or if its value is not a
+    /// `NumericArray`.
+    pub fn get_numeric_array<'s>(&'s self, name: &str) -> Option<&'s NumericArray> {
+        match self.get(name)? {
+            DataStoreNodeValue::NumericArray(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Get the [`Image`] value of the node named `name`.
+    ///
+    /// Returns `None` if there is no node named `name`, or if its value is not an
+    /// `Image`.
+    pub fn get_image<'s>(&'s self, name: &str) -> Option<&'s Image> {
+        match self.get(name)? {
+            DataStoreNodeValue::Image(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Get the `DataStore` value of the node named `name`.
+    ///
+    /// Returns `None` if there is no node named `name`, or if its value is not a
+    /// `DataStore`.
+    pub fn get_data_store<'s>(&'s self, name: &str) -> Option<&'s DataStore> {
+        match self.get(name)? {
+            DataStoreNodeValue::DataStore(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Construct a map from the names of the named nodes in this `DataStore` to their
+    /// values.
+    ///
+    /// Unnamed nodes are skipped. If several nodes have the same name, the map contains
+    /// the value of the first one, matching the behavior of [`DataStore::get()`].
+    ///
+    /// The names and values in the returned map are borrowed from this `DataStore`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use wolfram_library_link::DataStore;
+    ///
+    /// let mut store = DataStore::new();
+    /// store.add_named_i64("count", 5);
+    /// store.add_named_str("path", "/tmp/data.csv");
+    /// store.add_bool(true);
+    ///
+    /// let map = store.to_map();
+    ///
+    /// assert_eq!(map.len(), 2);
+    /// assert!(map.contains_key("path"));
+    /// ```
+    pub fn to_map<'s>(&'s self) -> HashMap<&'s str, DataStoreNodeValue<'s>> {
+        let mut map = HashMap::new();
+
+        for node in self.nodes() {
+            if let Some(name) = node.name_str() {
+                map.entry(name).or_insert_with(|| node.into_value());
+            }
+        }
+
+        map
+    }
+
+    //==================================
+    // Nodes
+    //==================================
+
     /// Returns an iterator over the [`DataStoreNode`]s of this `DataStore`.
     ///
     /// A [`DataStore`] is made up of a linked list of [`DataStoreNode`]s. The [`Nodes`]
@@ -490,10 +800,22 @@ impl DataStore {
 impl<'store> DataStoreNode<'store> {
     /// Get the name associated with this node, if any.
     ///
+    /// See also: [`DataStoreNode::name_str()`], which does not allocate.
+    ///
     /// *LibraryLink C Function:* [`DataStoreNode_getName`][rtl::DataStoreNode_getName].
     pub fn name(&self) -> Option<String> {
-        // TODO: Do we need to free this string, or does getName() just return a
-        //       borrwed reference?
+        self.name_str().map(str::to_owned)
+    }
+
+    /// Get the name associated with this node, if any, borrowed from the [`DataStore`]
+    /// containing this node.
+    ///
+    /// Returns `None` if this node is unnamed, or if its name is not valid UTF-8.
+    ///
+    /// *LibraryLink C Function:* [`DataStoreNode_getName`][rtl::DataStoreNode_getName].
+    pub fn name_str(&self) -> Option<&'store str> {
+        // The returned string is owned by the node, and is freed along with the
+        // `DataStore` that contains it.
         let mut raw_c_str: *mut c_char = std::ptr::null_mut();
 
         let err_code: sys::errcode_t =
@@ -503,45 +825,111 @@ impl<'store> DataStoreNode<'store> {
             return None;
         }
 
-        let c_str = unsafe { CStr::from_ptr(raw_c_str) };
-
-        let str: &str = c_str.to_str().ok()?;
+        let c_str: &'store CStr = unsafe { CStr::from_ptr(raw_c_str) };
 
-        Some(str.to_owned())
+        c_str.to_str().ok()
     }
 
     /// Get the value stored in this `DataStoreNode`.
     ///
     /// This is a safe wrapper around [`DataStoreNode::data_raw()`].
     pub fn value<'node>(&'node self) -> DataStoreNodeValue<'node> {
-        use DataStoreNodeValue as V;
+        self.store_value()
+    }
 
-        let data_raw: &'node sys::MArgument = unsafe { self.data_raw() };
+    /// Get the value stored in this `DataStoreNode`, borrowed from the [`DataStore`]
+    /// containing this node.
+    ///
+    /// Unlike [`DataStoreNode::value()`], the returned value can outlive this node.
+    pub fn into_value(self) -> DataStoreNodeValue<'store> {
+        self.store_value()
+    }
 
-        unsafe {
+    fn store_value(&self) -> DataStoreNodeValue<'store> {
+        match self.try_store_value() {
+            Ok(value) => value,
+            Err(message) => panic!("{}", message),
+        }
+    }
+
+    /// Get the value stored in this node, or a message describing why the value could
+    /// not be read.
+    fn try_store_value(&self) -> Result<DataStoreNodeValue<'store>, String> {
+        use DataStoreNodeValue as V;
+
+        // Copy the `MArgument` out of the `data` cache, so that none of the values
+        // constructed below borrow from `self`.
+        let data: sys::MArgument = match unsafe { self.try_data_raw() } {
+            Ok(data) => *data,
+            Err(code) => {
+                return Err(format!(
+                    "DataStoreNode::data_raw: failed to get data (error code: {})",
+                    code
+                ))
+            },
+        };
+
+        // Safety:
+        //   Each field of the `MArgument` returned by `DataStoreNode_getData()` points to
+        //   the value stored in this node, which is owned by the `DataStore` this node
+        //   was borrowed from. That `DataStore` is borrowed immutably for `'store`
+        //   (see `marker`), so it cannot be modified or dropped while any of the
+        //   references created below from those pointers are alive.
+        let value = unsafe {
             match self.data_type_raw() as u32 {
-                sys::MType_Undef => panic!("unexpected DataStoreNode Undef data type"),
-                sys::MType_Boolean => V::Boolean(bool::from_arg(data_raw)),
-                sys::MType_Integer => V::Integer(mint::from_arg(data_raw)),
-                sys::MType_Real => V::Real(mreal::from_arg(data_raw)),
-                sys::MType_Complex => V::Complex(mcomplex::from_arg(data_raw)),
-                sys::MType_UTF8String => V::Str(<&str>::from_arg(data_raw)),
-                sys::MType_Tensor => {
-                    unimplemented!("unhandled DataStoreNode Tensor data type")
+                sys::MType_Undef => {
+                    return Err("unexpected DataStoreNode Undef data type".to_owned())
                 },
-                sys::MType_SparseArray => {
-                    unimplemented!("unhandled DataStoreNode SparseArray data type")
+                sys::MType_Boolean => V::Boolean(crate::bool_from_mbool(*data.boolean)),
+                sys::MType_Integer => V::Integer(*data.integer),
+                sys::MType_Real => V::Real(*data.real),
+                sys::MType_Complex => V::Complex(*data.cmplex),
+                sys::MType_UTF8String => {
+                    let cstr: &'store CStr = CStr::from_ptr(*data.utf8string);
+
+                    match cstr.to_str() {
+                        Ok(str) => V::Str(str),
+                        Err(_) => {
+                            return Err(
+                                "DataStoreNode: string was not valid UTF-8".to_owned()
+                            )
+                        },
+                    }
                 },
+                sys::MType_Tensor => V::Tensor(TensorRef {
+                    raw: *data.tensor,
+                    marker: PhantomData,
+                }),
+                sys::MType_SparseArray => V::SparseArray(SparseArrayRef {
+                    raw: *data.sparse,
+                    marker: PhantomData,
+                }),
                 sys::MType_NumericArray => {
-                    V::NumericArray(<&NumericArray>::from_arg(data_raw))
+                    let array: &'store sys::MNumericArray = &*data.numeric;
+
+                    V::NumericArray(NumericArray::ref_cast(array))
+                },
+                sys::MType_Image => {
+                    let image: &'store sys::MImage = &*data.image;
+
+                    V::Image(Image::ref_cast(image))
+                },
+                sys::MType_DataStore => {
+                    let store: &'store sys::DataStore =
+                        &*(data.tensor as *mut sys::DataStore);
+
+                    V::DataStore(DataStore::ref_cast(store))
                 },
-                sys::MType_Image => V::Image(<&Image>::from_arg(data_raw)),
-                sys::MType_DataStore => V::DataStore(<&DataStore>::from_arg(data_raw)),
                 type_ => {
-                    panic!("unexpected DataStoreNode::data_type_raw() value: {}", type_)
+                    return Err(format!(
+                        "unexpected DataStoreNode::data_type_raw() value: {}",
+                        type_
+                    ))
                 },
             }
-        }
+        };
+
+        Ok(value)
     }
 
     /// Get the next node in this linked list of `DataStoreNode`'s.
@@ -641,18 +1029,104 @@ impl Drop for DataStore {
     }
 }
 
+impl<'node> TensorRef<'node> {
+    /// Get the rank of this tensor.
+    ///
+    /// *LibraryLink C Function:* [`MTensor_getRank`][rtl::MTensor_getRank].
+    pub fn rank(&self) -> usize {
+        let rank = unsafe { rtl::MTensor_getRank(self.raw) };
+
+        usize::try_from(rank).expect("MTensor rank overflows usize")
+    }
+
+    /// Get the dimensions of this tensor.
+    ///
+    /// *LibraryLink C Function:* [`MTensor_getDimensions`][rtl::MTensor_getDimensions].
+    pub fn dimensions(&self) -> &'node [mint] {
+        let rank = self.rank();
+
+        if rank == 0 {
+            return &[];
+        }
+
+        // Safety: The dimensions array is owned by the tensor, which is owned by the
+        //         `DataStore` this value was borrowed from.
+        unsafe { std::slice::from_raw_parts(rtl::MTensor_getDimensions(self.raw), rank) }
+    }
+
+    /// Get the raw `MTensor` pointer.
+    ///
+    /// The tensor is owned by the [`DataStore`] this value was borrowed from. It must not
+    /// be freed, and must not be used after that `DataStore` is dropped.
+    pub fn as_raw(&self) -> sys::MTensor {
+        self.raw
+    }
+}
+
+impl<'node> SparseArrayRef<'node> {
+    /// Get the rank of this sparse array.
+    ///
+    /// *LibraryLink C Function:* [`MSparseArray_getRank`][rtl::MSparseArray_getRank].
+    pub fn rank(&self) -> usize {
+        let rank = unsafe { rtl::MSparseArray_getRank(self.raw) };
+
+        usize::try_from(rank).expect("MSparseArray rank overflows usize")
+    }
+
+    /// Get the dimensions of this sparse array.
+    ///
+    /// *LibraryLink C Function:* [`MSparseArray_getDimensions`][rtl::MSparseArray_getDimensions].
+    pub fn dimensions(&self) -> &'node [mint] {
+        let rank = self.rank();
+
+        if rank == 0 {
+            return &[];
+        }
+
+        // Safety: The dimensions array is owned by the sparse array, which is owned by
+        //         the `DataStore` this value was borrowed from.
+        unsafe {
+            std::slice::from_raw_parts(rtl::MSparseArray_getDimensions(self.raw), rank)
+        }
+    }
+
+    /// Get the raw `MSparseArray` pointer.
+    ///
+    /// The sparse array is owned by the [`DataStore`] this value was borrowed from. It
+    /// must not be freed, and must not be used after that `DataStore` is dropped.
+    pub fn as_raw(&self) -> sys::MSparseArray {
+        self.raw
+    }
+}
+
 //======================================
 // Formatting Impls
 //======================================
 
 impl<'store> fmt::Debug for DataStoreNode<'store> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.debug_struct("DataStoreNode")
-            .field("name", &self.name())
-            .field("value", &self.value())
+        let mut debug = f.debug_struct("DataStoreNode");
+        debug.field("name", &self.name_str());
+
+        // Don't panic if the value of this node cannot be read.
+        match self.try_store_value() {
+            Ok(value) => debug.field("value", &value),
             // TODO: Add an enum to wrap the raw data type and use that here instead.
-            // .field("data_type_raw", &self.data_type_raw())
-            .finish()
+            Err(_) => debug.field("data_type_raw", &self.data_type_raw()),
+        };
+
+        debug.finish()
+    }
+}
+
+impl fmt::Debug for DataStore {
+    /// Formats the nodes of this `DataStore`.
+    ///
+    /// Unlike the [`Display`][fmt::Display] implementation, this reads the node values
+    /// directly, and does not construct an [`Expr`][crate::expr::Expr].
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("DataStore")?;
+        f.debug_list().entries(self.nodes()).finish()
     }
 }
 
@@ -666,9 +1140,27 @@ impl<'node> fmt::Debug for DataStoreNodeValue<'node> {
             V::Real(val) => val.fmt(f),
             V::Complex(val) => val.fmt(f),
             V::Str(val) => val.fmt(f),
+            V::Tensor(val) => val.fmt(f),
+            V::SparseArray(val) => val.fmt(f),
             V::NumericArray(val) => val.fmt(f),
             V::Image(val) => val.fmt(f),
             V::DataStore(val) => val.fmt(f),
         }
     }
 }
+
+impl<'node> fmt::Debug for TensorRef<'node> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Tensor")
+            .field("dimensions", &self.dimensions())
+            .finish()
+    }
+}
+
+impl<'node> fmt::Debug for SparseArrayRef<'node> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SparseArray")
+            .field("dimensions", &self.dimensions())
+            .finish()
+    }
+}
@@ -0,0 +1,417 @@
+//! Conversions between Rust types and [`DataStore`] values.
+//!
+//! See [`IntoDataStore`] and [`FromDataStore`].
+
+use std::fmt;
+
+use crate::{
+    sys::mcomplex, DataStore, DataStoreNode, DataStoreNodeValue, Image, NumericArray,
+    NumericArrayType,
+};
+
+/// Types that can be converted into a [`DataStore`].
+///
+/// This trait can be derived for structs using
+/// [`#[derive(IntoDataStore)]`][derive@crate::IntoDataStore]. It is implemented for
+/// tuples of up to 12 elements, whose elements are stored as unnamed nodes.
+pub trait IntoDataStore {
+    /// Convert `self` into a new [`DataStore`].
+    fn into_data_store(self) -> DataStore;
+}
+
+/// Types that can be constructed from the contents of a [`DataStore`].
+///
+/// This trait can be derived for structs using
+/// [`#[derive(FromDataStore)]`][derive@crate::FromDataStore]. It is implemented for
+/// tuples of up to 12 elements, which are constructed from the nodes of a data store
+/// in order.
+pub trait FromDataStore: Sized {
+    /// Construct a new value of this type from the nodes of `store`.
+    fn from_data_store(store: &DataStore) -> Result<Self, FromDataStoreError>;
+}
+
+/// Types that can be stored as the value of a single [`DataStoreNode`].
+///
+/// This trait is implemented for every type that can be used as a field of a struct
+/// that derives [`IntoDataStore`].
+pub trait IntoDataStoreNode {
+    /// Add `self` as a new node at the end of `store`, with optional `name`.
+    fn add_to_data_store(self, store: &mut DataStore, name: Option<&str>);
+}
+
+/// Types that can be constructed from the value of a single [`DataStoreNode`].
+///
+/// This trait is implemented for every type that can be used as a field of a struct
+/// that derives [`FromDataStore`].
+pub trait FromDataStoreNode: Sized {
+    /// Construct a new value of this type from the value of `node`.
+    fn from_data_store_node(node: &DataStoreNode) -> Result<Self, FromDataStoreError>;
+}
+
+/// Error returned when a [`FromDataStore`] or [`FromDataStoreNode`] conversion fails.
+///
+/// # Example
+///
+/// ```no_run
+/// use wolfram_library_link::{DataStore, FromDataStore};
+///
+/// #[derive(FromDataStore)]
+/// struct Point {
+///     x: f64,
+///     y: f64,
+/// }
+///
+/// let mut store = DataStore::new();
+/// store.add_named_f64("x", 1.0);
+/// store.add_named_str("y", "two");
+///
+/// let error = Point::from_data_store(&store).err().unwrap();
+///
+/// assert_eq!(
+///     error.to_string(),
+///     "DataStore node \"y\": expected Real, found String"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct FromDataStoreError {
+    /// Path to the node that caused this error, from outermost to innermost.
+    path: Vec<NodeKey>,
+    kind: FromDataStoreErrorKind,
+}
+
+/// The kind of error represented by a [`FromDataStoreError`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum FromDataStoreErrorKind {
+    /// A node with the expected name was not found.
+    MissingNode,
+    /// A node value did not have the expected type.
+    UnexpectedType {
+        /// Description of the expected type.
+        expected: String,
+        /// Description of the type that was found.
+        found: String,
+    },
+    /// A data store did not contain the expected number of nodes.
+    UnexpectedLength {
+        /// Expected number of nodes.
+        expected: usize,
+        /// Number of nodes that were found.
+        found: usize,
+    },
+}
+
+/// Identifies a node in a [`FromDataStoreError`] path.
+#[derive(Debug, Clone, PartialEq)]
+enum NodeKey {
+    Name(String),
+    /// 1-based position of an unnamed node.
+    Position(usize),
+}
+
+//======================================
+// Impls
+//======================================
+
+impl FromDataStoreError {
+    /// Construct a new error of the specified kind.
+    ///
+    /// Use [`FromDataStoreError::in_node()`] and
+    /// [`FromDataStoreError::at_position()`] to record the location of the node that
+    /// caused the error.
+    pub fn new(kind: FromDataStoreErrorKind) -> Self {
+        FromDataStoreError {
+            path: Vec::new(),
+            kind,
+        }
+    }
+
+    /// Construct an [`UnexpectedType`][FromDataStoreErrorKind::UnexpectedType] error
+    /// for a node with value `found`.
+    pub fn unexpected_type(expected: &str, found: &DataStoreNodeValue) -> Self {
+        FromDataStoreError::new(FromDataStoreErrorKind::UnexpectedType {
+            expected: expected.to_owned(),
+            found: found.type_name().to_owned(),
+        })
+    }
+
+    /// Record that this error occurred in the node named `name`.
+    pub fn in_node(mut self, name: &str) -> Self {
+        self.path.insert(0, NodeKey::Name(name.to_owned()));
+        self
+    }
+
+    /// Record that this error occurred in the unnamed node at 1-based `position`.
+    pub fn at_position(mut self, position: usize) -> Self {
+        self.path.insert(0, NodeKey::Position(position));
+        self
+    }
+
+    /// Get the kind of this error.
+    pub fn kind(&self) -> &FromDataStoreErrorKind {
+        &self.kind
+    }
+}
+
+impl<'node> DataStoreNodeValue<'node> {
+    /// Get the Wolfram Language name of the type of this value.
+    ///
+    /// ```
+    /// use wolfram_library_link::DataStoreNodeValue;
+    ///
+    /// assert_eq!(DataStoreNodeValue::Integer(5).type_name(), "Integer");
+    /// ```
+    pub fn type_name(&self) -> &'static str {
+        use DataStoreNodeValue as V;
+
+        match self {
+            V::Boolean(_) => "Boolean",
+            V::Integer(_) => "Integer",
+            V::Real(_) => "Real",
+            V::Complex(_) => "Complex",
+            V::Str(_) => "String",
+            V::Tensor(_) => "Tensor",
+            V::SparseArray(_) => "SparseArray",
+            V::NumericArray(_) => "NumericArray",
+            V::Image(_) => "Image",
+            V::DataStore(_) => "DataStore",
+        }
+    }
+}
+
+//======================================
+// IntoDataStoreNode Impls
+//======================================
+
+macro_rules! impl_IntoDataStoreNode {
+    ($type:ty, $add:ident, $add_named:ident) => {
+        impl IntoDataStoreNode for $type {
+            fn add_to_data_store(self, store: &mut DataStore, name: Option<&str>) {
+                match name {
+                    Some(name) => store.$add_named(name, self),
+                    None => store.$add(self),
+                }
+            }
+        }
+    };
+}
+
+impl_IntoDataStoreNode!(bool, add_bool, add_named_bool);
+impl_IntoDataStoreNode!(i64, add_i64, add_named_i64);
+impl_IntoDataStoreNode!(f64, add_f64, add_named_f64);
+impl_IntoDataStoreNode!(mcomplex, add_complex_f64, add_named_complex_f64);
+impl_IntoDataStoreNode!(&str, add_str, add_named_str);
+impl_IntoDataStoreNode!(NumericArray, add_numeric_array, add_named_numeric_array);
+impl_IntoDataStoreNode!(DataStore, add_data_store, add_named_data_store);
+
+impl IntoDataStoreNode for String {
+    fn add_to_data_store(self, store: &mut DataStore, name: Option<&str>) {
+        self.as_str().add_to_data_store(store, name)
+    }
+}
+
+impl<T: NumericArrayType> IntoDataStoreNode for NumericArray<T> {
+    fn add_to_data_store(self, store: &mut DataStore, name: Option<&str>) {
+        self.into_generic().add_to_data_store(store, name)
+    }
+}
+
+impl<T> IntoDataStoreNode for Image<T> {
+    fn add_to_data_store(self, store: &mut DataStore, name: Option<&str>) {
+        match name {
+            Some(name) => store.add_named_image(name, self),
+            None => store.add_image(self),
+        }
+    }
+}
+
+//======================================
+// FromDataStoreNode Impls
+//======================================
+
+macro_rules! impl_FromDataStoreNode {
+    ($type:ty, $expected:literal, |$value:ident| $pattern:pat => $result:expr) => {
+        impl FromDataStoreNode for $type {
+            fn from_data_store_node(
+                node: &DataStoreNode,
+            ) -> Result<Self, FromDataStoreError> {
+                match node.value() {
+                    $pattern => Ok($result),
+                    $value => {
+                        Err(FromDataStoreError::unexpected_type($expected, &$value))
+                    },
+                }
+            }
+        }
+    };
+}
+
+impl_FromDataStoreNode!(bool, "Boolean", |other| DataStoreNodeValue::Boolean(value) => value);
+impl_FromDataStoreNode!(i64, "Integer", |other| DataStoreNodeValue::Integer(value) => value);
+impl_FromDataStoreNode!(f64, "Real", |other| DataStoreNodeValue::Real(value) => value);
+impl_FromDataStoreNode!(mcomplex, "Complex", |other| DataStoreNodeValue::Complex(value) => value);
+impl_FromDataStoreNode!(String, "String", |other| DataStoreNodeValue::Str(value) => value.to_owned());
+impl_FromDataStoreNode!(NumericArray, "NumericArray", |other| DataStoreNodeValue::NumericArray(value) => value.clone());
+impl_FromDataStoreNode!(DataStore, "DataStore", |other| DataStoreNodeValue::DataStore(value) => value.clone());
+
+impl<T: NumericArrayType> FromDataStoreNode for NumericArray<T> {
+    fn from_data_store_node(node: &DataStoreNode) -> Result<Self, FromDataStoreError> {
+        let expected = || format!("NumericArray of type \"{}\"", T::TYPE.name());
+
+        match node.value() {
+            DataStoreNodeValue::NumericArray(array) => match array.try_kind::<T>() {
+                Ok(array) => Ok(array.clone()),
+                Err(()) => Err(FromDataStoreError::new(
+                    FromDataStoreErrorKind::UnexpectedType {
+                        expected: expected(),
+                        found: format!(
+                            "NumericArray of type \"{}\"",
+                            array.data_type().name()
+                        ),
+                    },
+                )),
+            },
+            other => Err(FromDataStoreError::unexpected_type(&expected(), &other)),
+        }
+    }
+}
+
+//======================================
+// Tuple Impls
+//======================================
+
+macro_rules! impl_tuples {
+    ($($type:ident),*) => {
+        impl<$($type: IntoDataStoreNode),*> IntoDataStore for ($($type,)*) {
+            #[allow(non_snake_case)]
+            fn into_data_store(self) -> DataStore {
+                let ($($type,)*) = self;
+
+                let mut store = DataStore::new();
+
+                $( $type.add_to_data_store(&mut store, None); )*
+
+                store
+            }
+        }
+
+        impl<$($type: IntoDataStoreNode),*> IntoDataStoreNode for ($($type,)*) {
+            fn add_to_data_store(self, store: &mut DataStore, name: Option<&str>) {
+                self.into_data_store().add_to_data_store(store, name)
+            }
+        }
+
+        impl<$($type: FromDataStoreNode),*> FromDataStore for ($($type,)*) {
+            fn from_data_store(store: &DataStore) -> Result<Self, FromDataStoreError> {
+                let nodes = crate::macro_utils::data_store_nodes_exact(
+                    store,
+                    [$(stringify!($type)),*].len(),
+                )?;
+
+                let mut nodes = nodes.iter().enumerate();
+
+                Ok(($(
+                    {
+                        let (index, node) = nodes.next().unwrap();
+                        crate::macro_utils::data_store_positional_node::<$type>(index, node)?
+                    },
+                )*))
+            }
+        }
+
+        impl<$($type: FromDataStoreNode),*> FromDataStoreNode for ($($type,)*) {
+            fn from_data_store_node(
+                node: &DataStoreNode,
+            ) -> Result<Self, FromDataStoreError> {
+                let store = crate::macro_utils::data_store_node_as_data_store(node)?;
+
+                FromDataStore::from_data_store(store)
+            }
+        }
+    };
+}
+
+impl_tuples!(A);
+impl_tuples!(A, B);
+impl_tuples!(A, B, C);
+impl_tuples!(A, B, C, D);
+impl_tuples!(A, B, C, D, E);
+impl_tuples!(A, B, C, D, E, F);
+impl_tuples!(A, B, C, D, E, F, G);
+impl_tuples!(A, B, C, D, E, F, G, H);
+impl_tuples!(A, B, C, D, E, F, G, H, I);
+impl_tuples!(A, B, C, D, E, F, G, H, I, J);
+impl_tuples!(A, B, C, D, E, F, G, H, I, J, K);
+impl_tuples!(A, B, C, D, E, F, G, H, I, J, K, L);
+
+//======================================
+// FromIterator and Extend Impls
+//======================================
+
+/// Construct a `DataStore` from an iterator of named values.
+///
+/// # Example
+///
+/// ```no_run
+/// use wolfram_library_link::DataStore;
+///
+/// let store: DataStore = [("x", 1.5), ("y", -2.0)].into_iter().collect();
+///
+/// assert_eq!(store.get_f64("y"), Some(-2.0));
+/// ```
+impl<K: AsRef<str>, V: IntoDataStoreNode> FromIterator<(K, V)> for DataStore {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut store = DataStore::new();
+        store.extend(iter);
+        store
+    }
+}
+
+/// Add named values to the end of a `DataStore`.
+impl<K: AsRef<str>, V: IntoDataStoreNode> Extend<(K, V)> for DataStore {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (name, value) in iter {
+            value.add_to_data_store(self, Some(name.as_ref()));
+        }
+    }
+}
+
+//======================================
+// Formatting Impls
+//======================================
+
+impl fmt::Display for FromDataStoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let FromDataStoreError { path, kind } = self;
+
+        if !path.is_empty() {
+            write!(f, "DataStore node ")?;
+
+            for (index, key) in path.iter().enumerate() {
+                if index > 0 {
+                    write!(f, ".")?;
+                }
+
+                match key {
+                    NodeKey::Name(name) => write!(f, "{:?}", name)?,
+                    NodeKey::Position(position) => write!(f, "#{}", position)?,
+                }
+            }
+
+            write!(f, ": ")?;
+        }
+
+        match kind {
+            FromDataStoreErrorKind::MissingNode => write!(f, "missing node"),
+            FromDataStoreErrorKind::UnexpectedType { expected, found } => {
+                write!(f, "expected {}, found {}", expected, found)
+            },
+            FromDataStoreErrorKind::UnexpectedLength { expected, found } => write!(
+                f,
+                "expected DataStore with {} nodes, found {}",
+                expected, found
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FromDataStoreError {}
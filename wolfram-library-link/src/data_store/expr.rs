@@ -0,0 +1,1231 @@
+//! Conversions between [`DataStore`] values and [`Expr`]s.
+//!
+//! See [`DataStore::to_expr()`] and [`DataStore::from_expr()`].
+
+use std::{
+    fmt::{self, Write},
+    os::raw::c_int,
+};
+
+use crate::{
+    expr::{Expr, ExprKind, Normal, Symbol},
+    rtl,
+    sys::{self, mcomplex, mint},
+    ColorSpace, DataStore, DataStoreNodeValue, Image, ImageData, ImageType,
+    IntoDataStoreNode, NumericArray, NumericArrayDataType, NumericArrayKind,
+    NumericArrayType, ToExprError, UninitImage,
+};
+
+/// Error returned by [`DataStore::from_expr()`].
+#[derive(Debug, Clone)]
+pub struct DataStoreFromExprError {
+    /// The expression that could not be converted.
+    expr: Expr,
+    message: &'static str,
+}
+
+//======================================
+// Impls
+//======================================
+
+impl DataStore {
+    /// Convert this `DataStore` into an equivalent
+    /// ``Developer`DataStore[...]`` expression.
+    ///
+    /// Named nodes are represented as `"name" -> value` rules. [`NumericArray`] values
+    /// are represented as `NumericArray[data, "type"]` expressions, and [`Image`] values
+    /// as `Image[data, "type", ColorSpace -> ..., Interleaving -> ...]` expressions.
+    ///
+    /// Returns an error if this store contains a value that cannot be represented as an
+    /// [`Expr`], e.g. an `"UnsignedInteger64"` [`NumericArray`] element that does not
+    /// fit in an `i64`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use wolfram_library_link::{
+    ///     expr::{Expr, Symbol},
+    ///     DataStore,
+    /// };
+    ///
+    /// let mut store = DataStore::new();
+    /// store.add_i64(1);
+    /// store.add_named_str("key", "value");
+    ///
+    /// assert_eq!(
+    ///     store.to_expr().unwrap(),
+    ///     Expr::normal(Symbol::new("Developer`DataStore"), vec![
+    ///         Expr::from(1),
+    ///         Expr::rule("key", Expr::string("value")),
+    ///     ])
+    /// );
+    /// ```
+    pub fn to_expr(&self) -> Result<Expr, ToExprError> {
+        let elements = self
+            .nodes()
+            .map(|node| {
+                let value = node_value_to_expr(node.value())?;
+
+                Ok(match node.name_str() {
+                    Some(name) => Expr::rule(name, value),
+                    None => value,
+                })
+            })
+            .collect::<Result<_, ToExprError>>()?;
+
+        Ok(Expr::normal(Symbol::new("Developer`DataStore"), elements))
+    }
+
+    /// Construct a `DataStore` from a ``Developer`DataStore[...]`` expression.
+    ///
+    /// This is the inverse of [`DataStore::to_expr()`]. Elements of the form
+    /// `"name" -> value` are added as named nodes. Supported values are:
+    ///
+    /// * `True` and `False`
+    /// * machine integers and reals, including `Indeterminate` and
+    ///   `DirectedInfinity[1]` or `DirectedInfinity[-1]`
+    /// * `Complex[re, im]`
+    /// * strings
+    /// * rectangular lists of numbers, which are added as `MTensor` values
+    /// * `SparseArray[list]`, where `list` is a rectangular list of numbers
+    /// * `NumericArray[data, "type"]`
+    /// * `Image[data, "type", opts]` and `Image3D[data, "type", opts]`
+    /// * nested ``Developer`DataStore[...]`` expressions
+    pub fn from_expr(expr: &Expr) -> Result<DataStore, DataStoreFromExprError> {
+        let elements = match expr.kind() {
+            ExprKind::Normal(normal) if has_head(normal, "Developer`DataStore") => {
+                normal.elements()
+            },
+            _ => {
+                return Err(DataStoreFromExprError::new(
+                    expr,
+                    "expected Developer`DataStore expression",
+                ))
+            },
+        };
+
+        let mut store = DataStore::new();
+
+        for element in elements {
+            match element.kind() {
+                ExprKind::Normal(rule)
+                    if has_head(rule, "System`Rule") && rule.elements().len() == 2 =>
+                {
+                    let name = match rule.elements()[0].kind() {
+                        ExprKind::String(name) => name,
+                        _ => {
+                            return Err(DataStoreFromExprError::new(
+                                element,
+                                "DataStore node name must be a string",
+                            ))
+                        },
+                    };
+
+                    add_expr_node(&mut store, Some(name), &rule.elements()[1])?
+                },
+                _ => add_expr_node(&mut store, None, element)?,
+            }
+        }
+
+        Ok(store)
+    }
+}
+
+impl DataStoreFromExprError {
+    fn new(expr: &Expr, message: &'static str) -> Self {
+        DataStoreFromExprError {
+            expr: expr.clone(),
+            message,
+        }
+    }
+
+    /// The expression that could not be converted.
+    pub fn expr(&self) -> &Expr {
+        &self.expr
+    }
+}
+
+impl TryFrom<&DataStore> for Expr {
+    type Error = ToExprError;
+
+    fn try_from(store: &DataStore) -> Result<Expr, ToExprError> {
+        store.to_expr()
+    }
+}
+
+impl TryFrom<&Expr> for DataStore {
+    type Error = DataStoreFromExprError;
+
+    fn try_from(expr: &Expr) -> Result<DataStore, DataStoreFromExprError> {
+        DataStore::from_expr(expr)
+    }
+}
+
+//======================================
+// DataStore -> Expr
+//======================================
+
+fn node_value_to_expr(value: DataStoreNodeValue) -> Result<Expr, ToExprError> {
+    use DataStoreNodeValue as V;
+
+    // Safety: The tensor and sparse array handles in `value` are borrowed from a
+    //         `DataStore`, so the raw pointers they contain are valid.
+    let expr = match value {
+        V::Boolean(value) => Expr::from(value),
+        V::Integer(value) => Expr::from(value),
+        V::Real(value) => real_expr(value),
+        V::Complex(value) => complex_expr(value.ri),
+        V::Str(value) => Expr::string(value),
+        V::Tensor(tensor) => unsafe { tensor_to_expr(tensor.as_raw())? },
+        V::SparseArray(array) => unsafe { sparse_array_to_expr(array.as_raw())? },
+        V::NumericArray(array) => numeric_array_to_expr(array)?,
+        V::Image(image) => image_to_expr(image),
+        V::DataStore(store) => store.to_expr()?,
+    };
+
+    Ok(expr)
+}
+
+/// Convert a machine real into an expression, using `Indeterminate` and
+/// `DirectedInfinity[..]` for the values that have no literal representation.
+pub(crate) fn real_expr(value: f64) -> Expr {
+    if value.is_nan() {
+        Expr::symbol(Symbol::new("System`Indeterminate"))
+    } else if value.is_infinite() {
+        let direction = if value > 0.0 { 1 } else { -1 };
+
+        Expr::normal(Symbol::new("System`DirectedInfinity"), vec![Expr::from(
+            direction,
+        )])
+    } else {
+        Expr::real(value)
+    }
+}
+
+pub(crate) fn complex_expr([re, im]: [f64; 2]) -> Expr {
+    Expr::normal(Symbol::new("System`Complex"), vec![
+        real_expr(re),
+        real_expr(im),
+    ])
+}
+
+/// Expression for a `UnsignedInteger64` value.
+///
+/// [`Expr`] has no arbitrary-precision integer representation, so this returns an error
+/// if `value` does not fit in an `i64`.
+pub(crate) fn u64_expr(value: u64) -> Result<Expr, ToExprError> {
+    match i64::try_from(value) {
+        Ok(value) => Ok(Expr::from(value)),
+        Err(_) => Err(ToExprError::integer_overflow(value)),
+    }
+}
+
+/// Construct a nested `List` with the specified dimensions from a flat list of elements.
+pub(crate) fn nested_list(dimensions: &[usize], elements: &[Expr]) -> Expr {
+    let (&len, rest) = match dimensions.split_first() {
+        Some(split) => split,
+        None => return elements[0].clone(),
+    };
+
+    let stride: usize = rest.iter().product();
+
+    let parts = (0..len)
+        .map(|index| nested_list(rest, &elements[index * stride..(index + 1) * stride]))
+        .collect();
+
+    Expr::list(parts)
+}
+
+/// Returns an error if `array` is an `"UnsignedInteger64"` array containing a value
+/// that does not fit in an `i64`.
+pub(crate) fn numeric_array_to_expr(array: &NumericArray) -> Result<Expr, ToExprError> {
+    fn elements<T: NumericArrayType + Copy>(
+        array: &NumericArray<T>,
+        to_expr: impl Fn(T) -> Expr,
+    ) -> Vec<Expr> {
+        array
+            .as_slice()
+            .iter()
+            .map(|&value| to_expr(value))
+            .collect()
+    }
+
+    let elements = if array.data_type() == NumericArrayDataType::ComplexReal32 {
+        // NumericArray::kind() does not support ComplexReal32 arrays, so read the
+        // elements directly.
+        let data: &[[f32; 2]] = unsafe {
+            std::slice::from_raw_parts(
+                array.data_ptr() as *const [f32; 2],
+                array.flattened_length(),
+            )
+        };
+
+        data.iter()
+            .map(|&[re, im]| complex_expr([f64::from(re), f64::from(im)]))
+            .collect()
+    } else {
+        match array.kind() {
+            NumericArrayKind::Bit8(array) => elements(array, Expr::from),
+            NumericArrayKind::Bit16(array) => elements(array, Expr::from),
+            NumericArrayKind::Bit32(array) => elements(array, Expr::from),
+            NumericArrayKind::Bit64(array) => elements(array, Expr::from),
+            NumericArrayKind::UBit8(array) => elements(array, Expr::from),
+            NumericArrayKind::UBit16(array) => elements(array, Expr::from),
+            NumericArrayKind::UBit32(array) => elements(array, Expr::from),
+            NumericArrayKind::UBit64(array) => array
+                .as_slice()
+                .iter()
+                .map(|&value| u64_expr(value))
+                .collect::<Result<_, _>>()?,
+            NumericArrayKind::Real32(array) => {
+                elements(array, |value| real_expr(f64::from(value)))
+            },
+            NumericArrayKind::Real64(array) => elements(array, real_expr),
+            NumericArrayKind::ComplexReal64(array) => {
+                elements(array, |value| complex_expr(value.ri))
+            },
+        }
+    };
+
+    Ok(Expr::normal(Symbol::new("System`NumericArray"), vec![
+        nested_list(array.dimensions(), &elements),
+        Expr::string(array.data_type().name()),
+    ]))
+}
+
+pub(crate) fn image_to_expr(image: &Image) -> Expr {
+    /// # Safety
+    ///
+    /// `S` must be the storage type of the data in `image`.
+    unsafe fn samples<S: Copy>(image: &Image, to_expr: impl Fn(S) -> Expr) -> Vec<Expr> {
+        let data: &[S] = std::slice::from_raw_parts(
+            image.raw_data() as *const S,
+            image.flattened_length(),
+        );
+
+        data.iter().map(|&value| to_expr(value)).collect()
+    }
+
+    let elements = unsafe {
+        match image.data_type() {
+            ImageType::Bit => samples(image, |value: i8| Expr::from(value)),
+            ImageType::Bit8 => samples(image, |value: u8| Expr::from(value)),
+            ImageType::Bit16 => samples(image, |value: u16| Expr::from(value)),
+            ImageType::Real32 => samples(image, |value: f32| real_expr(f64::from(value))),
+            ImageType::Real64 => samples(image, real_expr),
+        }
+    };
+
+    let mut elements = vec![nested_list(&image_dimensions(image), &elements)];
+    elements.push(Expr::string(image.data_type().name()));
+    elements.extend(image_options(image));
+
+    Expr::normal(Symbol::new(image_head(image)), elements)
+}
+
+/// Dimensions of the data of `image`, as used in `Image[data, ...]`.
+pub(crate) fn image_dimensions(image: &Image) -> Vec<usize> {
+    let mut dimensions = Vec::with_capacity(4);
+    if image.rank() == 3 {
+        dimensions.push(image.slice_count());
+    }
+    dimensions.push(image.row_count());
+    dimensions.push(image.column_count());
+
+    if image.is_interleaved() {
+        dimensions.push(image.channels());
+    } else {
+        dimensions.insert(0, image.channels());
+    }
+
+    dimensions
+}
+
+pub(crate) fn image_head(image: &Image) -> &'static str {
+    match image.rank() {
+        3 => "System`Image3D",
+        _ => "System`Image",
+    }
+}
+
+/// The `ColorSpace` and `Interleaving` option rules describing `image`.
+pub(crate) fn image_options(image: &Image) -> [Expr; 2] {
+    let color_space = match image.color_space() {
+        ColorSpace::Automatic => Expr::symbol(Symbol::new("System`Automatic")),
+        ColorSpace::Gray => Expr::string("Grayscale"),
+        ColorSpace::RGB => Expr::string("RGB"),
+        ColorSpace::HSB => Expr::string("HSB"),
+        ColorSpace::CMYK => Expr::string("CMYK"),
+        ColorSpace::XYZ => Expr::string("XYZ"),
+        ColorSpace::LUV => Expr::string("LUV"),
+        ColorSpace::LAB => Expr::string("LAB"),
+        ColorSpace::LCH => Expr::string("LCH"),
+    };
+
+    [
+        Expr::rule(Symbol::new("System`ColorSpace"), color_space),
+        Expr::rule(
+            Symbol::new("System`Interleaving"),
+            Expr::from(image.is_interleaved()),
+        ),
+    ]
+}
+
+/// # Safety
+///
+/// `tensor` must be a valid `MTensor` instance.
+unsafe fn tensor_to_expr(tensor: sys::MTensor) -> Result<Expr, ToExprError> {
+    unsafe fn data<T: Copy>(data: *const T, len: usize) -> &'static [T] {
+        std::slice::from_raw_parts(data, len)
+    }
+
+    let rank = usize::try_from(rtl::MTensor_getRank(tensor))
+        .expect("MTensor rank overflows usize");
+    let len = usize::try_from(rtl::MTensor_getFlattenedLength(tensor))
+        .expect("MTensor flattened length overflows usize");
+
+    let dimensions: Vec<usize> = data(rtl::MTensor_getDimensions(tensor), rank)
+        .iter()
+        .map(|&dim: &mint| {
+            usize::try_from(dim).expect("MTensor dimension overflows usize")
+        })
+        .collect();
+
+    let elements: Vec<Expr> = match rtl::MTensor_getType(tensor) as u32 {
+        sys::MType_Integer => data(rtl::MTensor_getIntegerData(tensor), len)
+            .iter()
+            .map(|&value| Expr::from(value))
+            .collect(),
+        sys::MType_Real => data(rtl::MTensor_getRealData(tensor), len)
+            .iter()
+            .map(|&value| real_expr(value))
+            .collect(),
+        sys::MType_Complex => data(rtl::MTensor_getComplexData(tensor), len)
+            .iter()
+            .map(|value: &mcomplex| complex_expr(value.ri))
+            .collect(),
+        type_ => {
+            return Err(ToExprError::new(format!(
+                "unexpected MTensor data type: {}",
+                type_
+            )))
+        },
+    };
+
+    Ok(nested_list(&dimensions, &elements))
+}
+
+/// # Safety
+///
+/// `array` must be a valid `MSparseArray` instance.
+unsafe fn sparse_array_to_expr(array: sys::MSparseArray) -> Result<Expr, ToExprError> {
+    let mut dense: sys::MTensor = std::ptr::null_mut();
+
+    let err_code: c_int = rtl::MSparseArray_toMTensor(array, &mut dense);
+
+    if err_code != 0 || dense.is_null() {
+        return Err(ToExprError::new(format!(
+            "MSparseArray_toMTensor: failed to convert sparse array (error code: {})",
+            err_code
+        )));
+    }
+
+    let dense_expr = tensor_to_expr(dense);
+
+    rtl::MTensor_free(dense);
+
+    Ok(Expr::normal(Symbol::new("System`SparseArray"), vec![
+        dense_expr?,
+    ]))
+}
+
+//======================================
+// Expr -> DataStore
+//======================================
+
+pub(crate) fn has_head(normal: &Normal, head: &str) -> bool {
+    match normal.head().kind() {
+        ExprKind::Symbol(symbol) => symbol.as_str() == head,
+        _ => false,
+    }
+}
+
+fn add_expr_node(
+    store: &mut DataStore,
+    name: Option<&str>,
+    expr: &Expr,
+) -> Result<(), DataStoreFromExprError> {
+    match expr.kind() {
+        ExprKind::Integer(value) => value.add_to_data_store(store, name),
+        ExprKind::Real(value) => (**value).add_to_data_store(store, name),
+        ExprKind::String(value) => value.as_str().add_to_data_store(store, name),
+        ExprKind::Symbol(symbol) => match symbol.as_str() {
+            "System`True" => true.add_to_data_store(store, name),
+            "System`False" => false.add_to_data_store(store, name),
+            "System`Indeterminate" => f64::NAN.add_to_data_store(store, name),
+            _ => {
+                return Err(DataStoreFromExprError::new(
+                    expr,
+                    "unsupported DataStore element",
+                ))
+            },
+        },
+        ExprKind::Normal(normal) => {
+            if has_head(normal, "Developer`DataStore") {
+                DataStore::from_expr(expr)?.add_to_data_store(store, name)
+            } else if has_head(normal, "System`NumericArray") {
+                numeric_array_from_expr(expr, normal)?.add_to_data_store(store, name)
+            } else if has_head(normal, "System`Image")
+                || has_head(normal, "System`Image3D")
+            {
+                image_from_expr(expr)?.add_to_data_store(store, name)
+            } else if has_head(normal, "System`List") {
+                let tensor = tensor_from_expr(expr)?;
+
+                // Safety: `tensor` is a new `MTensor` instance, and ownership of it is
+                //         transferred to `store`.
+                unsafe {
+                    match name {
+                        Some(name) => store.add_named_tensor_raw(name, tensor),
+                        None => store.add_tensor_raw(tensor),
+                    }
+                }
+            } else if has_head(normal, "System`SparseArray") {
+                let array = sparse_array_from_expr(expr, normal)?;
+
+                // Safety: `array` is a new `MSparseArray` instance, and ownership of it
+                //         is transferred to `store`.
+                unsafe {
+                    match name {
+                        Some(name) => store.add_named_sparse_array_raw(name, array),
+                        None => store.add_sparse_array_raw(array),
+                    }
+                }
+            } else if let Some(value) = real_value(expr) {
+                value.add_to_data_store(store, name)
+            } else if let Some([re, im]) = complex_parts(expr) {
+                mcomplex { ri: [re, im] }.add_to_data_store(store, name)
+            } else {
+                return Err(DataStoreFromExprError::new(
+                    expr,
+                    "unsupported DataStore element",
+                ));
+            }
+        },
+    }
+
+    Ok(())
+}
+
+/// Get the value of a real number expression, including the non-finite values written
+/// by [`real_expr()`].
+pub(crate) fn real_value(expr: &Expr) -> Option<f64> {
+    match expr.kind() {
+        ExprKind::Integer(value) => Some(*value as f64),
+        ExprKind::Real(value) => Some(**value),
+        ExprKind::Symbol(symbol) if symbol.as_str() == "System`Indeterminate" => {
+            Some(f64::NAN)
+        },
+        ExprKind::Normal(normal)
+            if has_head(normal, "System`DirectedInfinity")
+                && normal.elements().len() == 1 =>
+        {
+            match normal.elements()[0].kind() {
+                ExprKind::Integer(1) => Some(f64::INFINITY),
+                ExprKind::Integer(-1) => Some(f64::NEG_INFINITY),
+                _ => None,
+            }
+        },
+        _ => None,
+    }
+}
+
+/// Get the real and imaginary parts of a `Complex[re, im]` expression.
+pub(crate) fn complex_parts(expr: &Expr) -> Option<[f64; 2]> {
+    match expr.kind() {
+        ExprKind::Normal(normal)
+            if has_head(normal, "System`Complex") && normal.elements().len() == 2 =>
+        {
+            Some([
+                real_value(&normal.elements()[0])?,
+                real_value(&normal.elements()[1])?,
+            ])
+        },
+        _ => None,
+    }
+}
+
+
+/// Construct a new `MTensor` from a rectangular list of integers, reals, or complex
+/// numbers, as produced by [`tensor_to_expr()`].
+///
+/// The tensor has the most specific type that can represent every element.
+fn tensor_from_expr(expr: &Expr) -> Result<sys::MTensor, DataStoreFromExprError> {
+    /// # Safety
+    ///
+    /// `data` must return the data of a tensor of type `type_`, which must have
+    /// elements of type `T`.
+    unsafe fn new_tensor<T: Copy>(
+        type_: u32,
+        dimensions: &[mint],
+        values: &[T],
+        data: impl Fn(sys::MTensor) -> *mut T,
+    ) -> Option<sys::MTensor> {
+        let rank = mint::try_from(dimensions.len()).ok()?;
+        let mut tensor: sys::MTensor = std::ptr::null_mut();
+
+        let err_code: c_int =
+            rtl::MTensor_new(mint::from(type_), rank, dimensions.as_ptr(), &mut tensor);
+
+        if err_code != 0 || tensor.is_null() {
+            return None;
+        }
+
+        std::ptr::copy_nonoverlapping(values.as_ptr(), data(tensor), values.len());
+
+        Some(tensor)
+    }
+
+    let invalid = || {
+        DataStoreFromExprError::new(
+            expr,
+            "expected rectangular List of integers, reals, or complex numbers",
+        )
+    };
+
+    let dimensions = array_dimensions(expr);
+
+    let mut elements = Vec::new();
+    if !flatten_array(expr, &dimensions, &mut elements) {
+        return Err(invalid());
+    }
+
+    let dimensions = dimensions
+        .iter()
+        .map(|&dim| mint::try_from(dim).map_err(|_| invalid()))
+        .collect::<Result<Vec<mint>, _>>()?;
+
+    let integers: Option<Vec<mint>> = elements
+        .iter()
+        .map(|element| match element.kind() {
+            ExprKind::Integer(value) => Some(*value),
+            _ => None,
+        })
+        .collect();
+    let reals = || -> Option<Vec<f64>> {
+        elements
+            .iter()
+            .map(|&element| real_value(element))
+            .collect()
+    };
+    let complexes = || -> Option<Vec<mcomplex>> {
+        elements
+            .iter()
+            .map(|&element| {
+                let ri = match real_value(element) {
+                    Some(re) => [re, 0.0],
+                    None => complex_parts(element)?,
+                };
+
+                Some(mcomplex { ri })
+            })
+            .collect()
+    };
+
+    let tensor = unsafe {
+        if let Some(values) = integers {
+            new_tensor(sys::MType_Integer, &dimensions, &values, |tensor| {
+                rtl::MTensor_getIntegerData(tensor)
+            })
+        } else if let Some(values) = reals() {
+            new_tensor(sys::MType_Real, &dimensions, &values, |tensor| {
+                rtl::MTensor_getRealData(tensor)
+            })
+        } else if let Some(values) = complexes() {
+            new_tensor(sys::MType_Complex, &dimensions, &values, |tensor| {
+                rtl::MTensor_getComplexData(tensor)
+            })
+        } else {
+            return Err(invalid());
+        }
+    };
+
+    tensor.ok_or_else(|| DataStoreFromExprError::new(expr, "unable to construct MTensor"))
+}
+
+/// Construct a new `MSparseArray` from a `SparseArray[list]` expression, as produced by
+/// [`sparse_array_to_expr()`].
+fn sparse_array_from_expr(
+    expr: &Expr,
+    normal: &Normal,
+) -> Result<sys::MSparseArray, DataStoreFromExprError> {
+    let dense = match normal.elements() {
+        [dense] => tensor_from_expr(dense)?,
+        _ => {
+            return Err(DataStoreFromExprError::new(
+                expr,
+                "expected SparseArray[list] with rectangular array data",
+            ))
+        },
+    };
+
+    let mut array: sys::MSparseArray = std::ptr::null_mut();
+
+    // Safety: `dense` is a valid `MTensor` instance. A null implicit value tensor
+    //         makes the implicit value of the array 0.
+    let err_code: c_int = unsafe {
+        let err_code =
+            rtl::MSparseArray_fromMTensor(dense, std::ptr::null_mut(), &mut array);
+        rtl::MTensor_free(dense);
+        err_code
+    };
+
+    if err_code != 0 || array.is_null() {
+        return Err(DataStoreFromExprError::new(
+            expr,
+            "unable to construct MSparseArray",
+        ));
+    }
+
+    Ok(array)
+}
+
+pub(crate) fn numeric_array_from_expr(
+    expr: &Expr,
+    normal: &Normal,
+) -> Result<NumericArray, DataStoreFromExprError> {
+    let invalid = || {
+        DataStoreFromExprError::new(
+            expr,
+            "expected NumericArray[data, \"type\"] with rectangular array data",
+        )
+    };
+
+    let (data, type_name) = match normal.elements() {
+        [data, type_name] => match type_name.kind() {
+            ExprKind::String(type_name) => (data, type_name.as_str()),
+            _ => return Err(invalid()),
+        },
+        _ => return Err(invalid()),
+    };
+
+    let dimensions = array_dimensions(data);
+
+    let mut elements = Vec::new();
+    if dimensions.is_empty() || !flatten_array(data, &dimensions, &mut elements) {
+        return Err(invalid());
+    }
+
+    fn convert<T: NumericArrayType>(
+        expr: &Expr,
+        dimensions: &[usize],
+        elements: &[&Expr],
+        from_expr: impl Fn(&Expr) -> Option<T>,
+    ) -> Result<NumericArray, DataStoreFromExprError> {
+        let data = elements
+            .iter()
+            .map(|&element| {
+                from_expr(element).ok_or_else(|| {
+                    DataStoreFromExprError::new(element, "invalid NumericArray element")
+                })
+            })
+            .collect::<Result<Vec<T>, _>>()?;
+
+        match NumericArray::try_from_array(dimensions, &data) {
+            Ok(array) => Ok(array.into_generic()),
+            Err(_) => Err(DataStoreFromExprError::new(
+                expr,
+                "unable to construct NumericArray",
+            )),
+        }
+    }
+
+    /// `NumericArrayType` is not implemented for 32-bit complex reals, so construct the
+    /// array from `[re, im]` pairs directly.
+    fn convert_complex32(
+        expr: &Expr,
+        dimensions: &[usize],
+        elements: &[&Expr],
+    ) -> Result<NumericArray, DataStoreFromExprError> {
+        let data = elements
+            .iter()
+            .map(|&element| {
+                let mcomplex { ri: [re, im] } = complex(element).ok_or_else(|| {
+                    DataStoreFromExprError::new(element, "invalid NumericArray element")
+                })?;
+
+                Ok([re as f32, im as f32])
+            })
+            .collect::<Result<Vec<[f32; 2]>, _>>()?;
+
+        let error =
+            || DataStoreFromExprError::new(expr, "unable to construct NumericArray");
+
+        let rank = mint::try_from(dimensions.len()).map_err(|_| error())?;
+        let mut array: sys::MNumericArray = std::ptr::null_mut();
+
+        unsafe {
+            let err_code: c_int = rtl::MNumericArray_new(
+                NumericArrayDataType::ComplexReal32.as_raw(),
+                rank,
+                dimensions.as_ptr() as *mut mint,
+                &mut array,
+            );
+
+            if err_code != 0 || array.is_null() {
+                return Err(error());
+            }
+
+            let array: NumericArray = NumericArray::from_raw(array);
+
+            std::ptr::copy_nonoverlapping(
+                data.as_ptr(),
+                array.data_ptr() as *mut [f32; 2],
+                data.len(),
+            );
+
+            Ok(array)
+        }
+    }
+
+    fn integer<T: TryFrom<i64>>(expr: &Expr) -> Option<T> {
+        match expr.kind() {
+            ExprKind::Integer(value) => T::try_from(*value).ok(),
+            _ => None,
+        }
+    }
+
+    fn complex(expr: &Expr) -> Option<mcomplex> {
+        let ri = match real_value(expr) {
+            Some(re) => [re, 0.0],
+            None => complex_parts(expr)?,
+        };
+
+        Some(mcomplex { ri })
+    }
+
+    let d = &dimensions;
+    let e = &elements;
+
+    match type_name {
+        "Integer8" => convert::<i8>(expr, d, e, integer),
+        "Integer16" => convert::<i16>(expr, d, e, integer),
+        "Integer32" => convert::<i32>(expr, d, e, integer),
+        "Integer64" => convert::<i64>(expr, d, e, integer),
+        "UnsignedInteger8" => convert::<u8>(expr, d, e, integer),
+        "UnsignedInteger16" => convert::<u16>(expr, d, e, integer),
+        "UnsignedInteger32" => convert::<u32>(expr, d, e, integer),
+        "UnsignedInteger64" => convert::<u64>(expr, d, e, integer),
+        "Real32" => convert::<f32>(expr, d, e, |elem| real_value(elem).map(|v| v as f32)),
+        "Real64" => convert::<f64>(expr, d, e, real_value),
+        "ComplexReal32" => convert_complex32(expr, d, e),
+        "ComplexReal64" => convert::<mcomplex>(expr, d, e, complex),
+        _ => Err(DataStoreFromExprError::new(
+            expr,
+            "unsupported NumericArray type",
+        )),
+    }
+}
+
+/// Construct an [`Image`] from an `Image[data, "type", opts]` or
+/// `Image3D[data, "type", opts]` expression, as produced by [`image_to_expr()`].
+///
+/// `data` can be a rectangular list or a `NumericArray[list, "type"]` expression. The
+/// `ColorSpace` and `Interleaving` options are supported.
+pub(crate) fn image_from_expr(expr: &Expr) -> Result<Image, DataStoreFromExprError> {
+    let invalid = || {
+        DataStoreFromExprError::new(
+            expr,
+            "expected Image[data, \"type\", opts] with rectangular array data",
+        )
+    };
+
+    let (normal, rank) = match expr.kind() {
+        ExprKind::Normal(normal) if has_head(normal, "System`Image") => (normal, 2),
+        ExprKind::Normal(normal) if has_head(normal, "System`Image3D") => (normal, 3),
+        _ => return Err(invalid()),
+    };
+
+    let (data, type_name, options) = match normal.elements() {
+        [data, type_name, options @ ..] => match type_name.kind() {
+            ExprKind::String(type_name) => (data, type_name.as_str(), options),
+            _ => return Err(invalid()),
+        },
+        _ => return Err(invalid()),
+    };
+
+    let (color_space, interleaving) = image_options_from_exprs(options)?;
+
+    // The image data may be wrapped in a NumericArray[list, "type"] expression.
+    let data = match data.kind() {
+        ExprKind::Normal(array)
+            if has_head(array, "System`NumericArray") && array.elements().len() == 2 =>
+        {
+            &array.elements()[0]
+        },
+        _ => data,
+    };
+
+    let dimensions = array_dimensions(data);
+
+    let mut elements = Vec::new();
+    if !flatten_array(data, &dimensions, &mut elements) {
+        return Err(invalid());
+    }
+
+    let (pixels, channels) = match image_shape(rank, &dimensions, interleaving) {
+        Some(shape) => shape,
+        None => return Err(invalid()),
+    };
+
+    fn convert<T: ImageData>(
+        expr: &Expr,
+        (pixels, channels): (&[usize], usize),
+        color_space: ColorSpace,
+        interleaving: bool,
+        elements: &[&Expr],
+        from_expr: impl Fn(&Expr) -> Option<T::STORAGE>,
+    ) -> Result<Image, DataStoreFromExprError> {
+        let data = elements
+            .iter()
+            .map(|&element| {
+                from_expr(element).ok_or_else(|| {
+                    DataStoreFromExprError::new(element, "invalid Image element")
+                })
+            })
+            .collect::<Result<Vec<T::STORAGE>, _>>()?;
+
+        match new_image::<T>(pixels, channels, color_space, interleaving, &data) {
+            Some(image) => Ok(unsafe { Image::from_raw(image.into_raw()) }),
+            None => Err(DataStoreFromExprError::new(
+                expr,
+                "unable to construct Image",
+            )),
+        }
+    }
+
+    fn integer<T: TryFrom<i64>>(expr: &Expr) -> Option<T> {
+        match expr.kind() {
+            ExprKind::Integer(value) => T::try_from(*value).ok(),
+            _ => None,
+        }
+    }
+
+    let shape = (pixels, channels);
+    let (cs, i) = (color_space, interleaving);
+    let e = &elements;
+
+    match type_name {
+        "Bit" => convert::<bool>(expr, shape, cs, i, e, |elem| match integer(elem)? {
+            value @ (0 | 1) => Some(value),
+            _ => None,
+        }),
+        "Byte" => convert::<u8>(expr, shape, cs, i, e, integer),
+        "Bit16" => convert::<u16>(expr, shape, cs, i, e, integer),
+        "Real32" => convert::<f32>(expr, shape, cs, i, e, |elem| {
+            real_value(elem).map(|value| value as f32)
+        }),
+        "Real64" | "Real" => convert::<f64>(expr, shape, cs, i, e, real_value),
+        _ => Err(DataStoreFromExprError::new(expr, "unsupported Image type")),
+    }
+}
+
+/// Get the `ColorSpace` and `Interleaving` values from the option rules of an
+/// `Image[data, "type", opts]` expression.
+pub(crate) fn image_options_from_exprs(
+    options: &[Expr],
+) -> Result<(ColorSpace, bool), DataStoreFromExprError> {
+    let mut color_space = ColorSpace::Automatic;
+    let mut interleaving = true;
+
+    for option in options {
+        let (name, value) = match option.kind() {
+            ExprKind::Normal(rule)
+                if has_head(rule, "System`Rule") && rule.elements().len() == 2 =>
+            {
+                (&rule.elements()[0], &rule.elements()[1])
+            },
+            _ => return Err(DataStoreFromExprError::new(option, "invalid Image option")),
+        };
+
+        match name.try_as_symbol().map(Symbol::as_str) {
+            Some("System`ColorSpace") => {
+                color_space = color_space_from_expr(value).ok_or_else(|| {
+                    DataStoreFromExprError::new(value, "unsupported Image color space")
+                })?
+            },
+            Some("System`Interleaving") => {
+                interleaving = value.try_as_bool().ok_or_else(|| {
+                    DataStoreFromExprError::new(value, "expected True or False")
+                })?
+            },
+            _ => {
+                return Err(DataStoreFromExprError::new(
+                    option,
+                    "unsupported Image option",
+                ))
+            },
+        }
+    }
+
+    Ok((color_space, interleaving))
+}
+
+/// Separate the `dimensions` of the data of an image with the specified `rank` into the
+/// pixel dimensions and the channel count.
+///
+/// Returns `None` if `dimensions` is not valid for an image of that rank.
+pub(crate) fn image_shape(
+    rank: usize,
+    dimensions: &[usize],
+    interleaving: bool,
+) -> Option<(&[usize], usize)> {
+    match dimensions.len().checked_sub(rank)? {
+        0 => Some((dimensions, 1)),
+        1 if interleaving => Some((&dimensions[..rank], dimensions[rank])),
+        1 => Some((&dimensions[1..], dimensions[0])),
+        _ => None,
+    }
+}
+
+/// Construct a new 2D or 3D image with the specified `pixels` dimensions, and copy
+/// `data` into it.
+///
+/// Returns `None` if the image could not be created.
+pub(crate) fn new_image<T: ImageData>(
+    pixels: &[usize],
+    channels: usize,
+    color_space: ColorSpace,
+    interleaving: bool,
+    data: &[T::STORAGE],
+) -> Option<Image<T>> {
+    let image = match *pixels {
+        [rows, columns] => UninitImage::<T>::try_new_2d(
+            columns,
+            rows,
+            channels,
+            color_space,
+            interleaving,
+        ),
+        [slices, rows, columns] => UninitImage::<T>::try_new_3d(
+            slices,
+            columns,
+            rows,
+            channels,
+            color_space,
+            interleaving,
+        ),
+        _ => return None,
+    };
+
+    Some(image.ok()?.init_from_storage(data))
+}
+
+/// Inverse of the `ColorSpace` option value written by [`image_to_expr()`].
+fn color_space_from_expr(expr: &Expr) -> Option<ColorSpace> {
+    let color_space = match expr.kind() {
+        ExprKind::Symbol(symbol) if symbol.as_str() == "System`Automatic" => {
+            ColorSpace::Automatic
+        },
+        ExprKind::String(name) => match name.as_str() {
+            "Grayscale" => ColorSpace::Gray,
+            "RGB" => ColorSpace::RGB,
+            "HSB" => ColorSpace::HSB,
+            "CMYK" => ColorSpace::CMYK,
+            "XYZ" => ColorSpace::XYZ,
+            "LUV" => ColorSpace::LUV,
+            "LAB" => ColorSpace::LAB,
+            "LCH" => ColorSpace::LCH,
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    Some(color_space)
+}
+
+/// Determine the dimensions of the array `data` from the first element at each level.
+///
+/// Use [`flatten_array()`] to check that `data` is rectangular.
+fn array_dimensions(data: &Expr) -> Vec<usize> {
+    let mut dimensions = Vec::new();
+    let mut current = data;
+    while let ExprKind::Normal(list) = current.kind() {
+        if !has_head(list, "System`List") {
+            break;
+        }
+
+        dimensions.push(list.elements().len());
+
+        match list.elements().first() {
+            Some(first) => current = first,
+            None => break,
+        }
+    }
+
+    dimensions
+}
+
+/// Append the elements of the array `expr`, which must have the specified dimensions,
+/// to `elements`.
+///
+/// Returns `false` if `expr` is not a rectangular array with the specified dimensions.
+pub(crate) fn flatten_array<'e>(
+    expr: &'e Expr,
+    dimensions: &[usize],
+    elements: &mut Vec<&'e Expr>,
+) -> bool {
+    let (&len, rest) = match dimensions.split_first() {
+        Some(split) => split,
+        None => {
+            elements.push(expr);
+            return true;
+        },
+    };
+
+    match expr.kind() {
+        ExprKind::Normal(list)
+            if has_head(list, "System`List") && list.elements().len() == len =>
+        {
+            list.elements()
+                .iter()
+                .all(|element| flatten_array(element, rest, elements))
+        },
+        _ => false,
+    }
+}
+
+//======================================
+// Formatting Impls
+//======================================
+
+/// Formats an [`Expr`] using Wolfram Language `InputForm`-like syntax, omitting the
+/// ``System` `` context from symbols.
+struct WolframSyntax<'e>(&'e Expr);
+
+impl fmt::Display for DataStore {
+    /// Formats this `DataStore` as a ``Developer`DataStore[...]`` expression in Wolfram
+    /// Language syntax.
+    ///
+    /// ```no_run
+    /// use wolfram_library_link::DataStore;
+    ///
+    /// let mut store = DataStore::new();
+    /// store.add_i64(1);
+    /// store.add_named_bool("flag", false);
+    ///
+    /// assert_eq!(store.to_string(), "Developer`DataStore[1, \"flag\" -> False]");
+    /// ```
+    ///
+    /// If this store cannot be converted into an [`Expr`] (see
+    /// [`DataStore::to_expr()`]), it is formatted using its [`Debug`][fmt::Debug]
+    /// implementation instead.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.to_expr() {
+            Ok(expr) => WolframSyntax(&expr).fmt(f),
+            Err(_) => fmt::Debug::fmt(self, f),
+        }
+    }
+}
+
+impl fmt::Display for DataStoreFromExprError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let DataStoreFromExprError { expr, message } = self;
+
+        write!(f, "{}: {}", message, WolframSyntax(expr))
+    }
+}
+
+impl std::error::Error for DataStoreFromExprError {}
+
+impl<'e> fmt::Display for WolframSyntax<'e> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let WolframSyntax(expr) = *self;
+
+        match expr.kind() {
+            ExprKind::Integer(value) => write!(f, "{}", value),
+            ExprKind::Real(value) if value.is_nan() => write!(f, "Indeterminate"),
+            ExprKind::Real(value) if value.is_infinite() => {
+                let direction = if **value > 0.0 { 1 } else { -1 };
+
+                write!(f, "DirectedInfinity[{}]", direction)
+            },
+            ExprKind::Real(value) => {
+                // Use the `*^` exponent notation, and make sure the mantissa is a real
+                // and not an integer.
+                let real = format!("{:?}", **value);
+
+                match real.split_once('e') {
+                    Some((mantissa, exponent)) if mantissa.contains('.') => {
+                        write!(f, "{}*^{}", mantissa, exponent)
+                    },
+                    Some((mantissa, exponent)) => {
+                        write!(f, "{}.*^{}", mantissa, exponent)
+                    },
+                    None => write!(f, "{}", real),
+                }
+            },
+            ExprKind::String(value) => write_string(f, value),
+            ExprKind::Symbol(symbol) => {
+                let name = symbol.as_str();
+
+                match name.strip_prefix("System`") {
+                    Some(short) if !short.contains('`') => write!(f, "{}", short),
+                    _ => write!(f, "{}", name),
+                }
+            },
+            ExprKind::Normal(normal) => {
+                let elements = normal.elements();
+
+                if has_head(normal, "System`Rule") && elements.len() == 2 {
+                    return write!(
+                        f,
+                        "{} -> {}",
+                        WolframSyntax(&elements[0]),
+                        WolframSyntax(&elements[1])
+                    );
+                }
+
+                let (open, close) = if has_head(normal, "System`List") {
+                    ("{", "}")
+                } else {
+                    write!(f, "{}", WolframSyntax(normal.head()))?;
+                    ("[", "]")
+                };
+
+                write!(f, "{}", open)?;
+                for (index, element) in elements.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", WolframSyntax(element))?;
+                }
+                write!(f, "{}", close)
+            },
+        }
+    }
+}
+
+/// Write `value` as a Wolfram Language string literal.
+///
+/// Control characters are written using the `\.xx` hexadecimal escape, and all other
+/// characters, including non-ASCII characters, are written as-is.
+fn write_string(f: &mut fmt::Formatter, value: &str) -> fmt::Result {
+    f.write_char('"')?;
+
+    for char in value.chars() {
+        match char {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            // All control characters are in the range 0x00 - 0x9F, so they can be
+            // written using the two digit `\.xx` escape.
+            _ if char.is_control() => write!(f, "\\.{:02x}", u32::from(char))?,
+            _ => f.write_char(char)?,
+        }
+    }
+
+    f.write_char('"')
+}
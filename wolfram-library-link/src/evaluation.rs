@@ -0,0 +1,478 @@
+//! Evaluation of Wolfram Language code with captured output and structured results.
+
+use std::fmt;
+
+use wstp::{Link, Token};
+
+use crate::{
+    expr::{Expr, ExprKind, Symbol},
+    from_expr::{FromExpr, FromExprError},
+    wl,
+};
+
+/// Wolfram Language function used to evaluate an expression while capturing its output.
+///
+/// See the comment at the top of `evaluation.wl` for the format of the value it returns.
+const EVALUATION_CODE: &str = include_str!("evaluation.wl");
+
+/// The result of evaluating an expression using [`Evaluation::run()`].
+///
+/// An `Evaluation` records how the evaluation ended, and any messages and `Print[..]`
+/// output that were generated, in the order they occurred.
+///
+/// # Example
+///
+/// ```no_run
+/// use wolfram_library_link::{
+///     expr::{Expr, Symbol},
+///     Evaluation, EvaluationOutcome,
+/// };
+///
+/// // Evaluate `Print["Hello"]; 1/0`
+/// let expr = Expr::normal(Symbol::new("System`CompoundExpression"), vec![
+///     Expr::normal(Symbol::new("System`Print"), vec![Expr::string("Hello")]),
+///     Expr::normal(Symbol::new("System`Times"), vec![
+///         Expr::from(1),
+///         Expr::normal(Symbol::new("System`Power"), vec![Expr::from(0), Expr::from(-1)]),
+///     ]),
+/// ]);
+///
+/// let evaluation = Evaluation::run(&expr).unwrap();
+///
+/// assert!(matches!(evaluation.outcome(), EvaluationOutcome::Returned(_)));
+///
+/// // Prints "Hello" followed by the Power::infy message.
+/// for output in evaluation.output() {
+///     println!("{}", output);
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evaluation {
+    outcome: EvaluationOutcome,
+    output: Vec<EvaluationOutput>,
+}
+
+/// How an [`Evaluation`] ended.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EvaluationOutcome {
+    /// Evaluation finished normally and returned a value.
+    Returned(Expr),
+    /// Evaluation was aborted, either by the user or by a call to `Abort[]`.
+    Aborted,
+    /// Evaluation ended in a `Throw[value]` or `Throw[value, tag]` that was not caught.
+    Thrown {
+        /// The value that was thrown.
+        value: Expr,
+        /// The tag the value was thrown with, if any.
+        tag: Option<Expr>,
+    },
+}
+
+/// Output generated during an [`Evaluation`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum EvaluationOutput {
+    /// Text printed using `Print[..]`.
+    Print(String),
+    /// A message that was issued.
+    Message {
+        /// Name of the message, e.g. `"Power::infy"`.
+        ///
+        /// This is empty if the message name could not be determined.
+        name: String,
+        /// Text of the message, with the message arguments filled in.
+        text: String,
+    },
+}
+
+/// Error returned by [`evaluate_as()`], [`symbol_value()`], [`set_symbol_value()`], and
+/// [`Evaluation::into_result()`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum EvaluationError {
+    /// A WSTP error occurred, or the Kernel returned an unexpected response.
+    Link(String),
+    /// Evaluation was aborted.
+    Aborted,
+    /// Evaluation ended in an uncaught `Throw[..]`.
+    Thrown {
+        /// The value that was thrown.
+        value: Expr,
+        /// The tag the value was thrown with, if any.
+        tag: Option<Expr>,
+    },
+    /// A message was issued by an evaluation that was expected to succeed silently.
+    Message {
+        /// Name of the message.
+        name: String,
+        /// Text of the message.
+        text: String,
+    },
+    /// The returned expression could not be converted into the requested type.
+    Conversion(FromExprError),
+}
+
+//======================================
+// Impls
+//======================================
+
+impl Evaluation {
+    /// Evaluate `expr` by calling back into the Wolfram Kernel, capturing any messages
+    /// and `Print[..]` output it generates.
+    ///
+    /// Unlike [`evaluate()`][crate::evaluate], aborts and uncaught `Throw[..]`s are
+    /// reported in the [`outcome()`][Evaluation::outcome] of the evaluation. An abort
+    /// that occurs during `expr` is caught and does not propagate to the code that
+    /// called the current LibraryLink function.
+    ///
+    /// An error is returned only if a WSTP error occurs.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if it is not called from the main Kernel thread.
+    pub fn run(expr: &Expr) -> Result<Evaluation, EvaluationError> {
+        // Parse the evaluation code with a fixed $Context and $ContextPath, since this
+        // may be called from a WSTP function that was invoked with $ContextPath = {}.
+        let wrapped = wl!(
+            Block[{$Context = "RustLink`Private`", $ContextPath = {"System`"}},
+                ToExpression[#EVALUATION_CODE]
+            ][#(expr.clone())]
+        );
+
+        let returned = crate::with_link(|link: &mut Link| {
+            link.put_expr(&wl!(EvaluatePacket[#wrapped]))
+                .map_err(link_error)?;
+
+            crate::process_wstp_link(link).map_err(EvaluationError::Link)?;
+
+            read_return_packet(link)
+        })?;
+
+        let result =
+            decode_symbols(&returned).and_then(|expr| parse_evaluation_result(&expr));
+
+        let (outcome, output) = result.ok_or_else(|| {
+            EvaluationError::Link(format!(
+                "unexpected result from captured evaluation: {}",
+                returned
+            ))
+        })?;
+
+        Ok(Evaluation { outcome, output })
+    }
+
+    /// How the evaluation ended.
+    pub fn outcome(&self) -> &EvaluationOutcome {
+        &self.outcome
+    }
+
+    /// Messages and `Print[..]` output generated by the evaluation, in the order they
+    /// occurred.
+    pub fn output(&self) -> &[EvaluationOutput] {
+        &self.output
+    }
+
+    /// Returns the messages issued during the evaluation.
+    pub fn messages(&self) -> impl Iterator<Item = &EvaluationOutput> {
+        self.output
+            .iter()
+            .filter(|output| matches!(output, EvaluationOutput::Message { .. }))
+    }
+
+    /// Returns the returned value, or `None` if the evaluation was aborted or ended in
+    /// an uncaught `Throw[..]`.
+    pub fn returned(&self) -> Option<&Expr> {
+        match self.outcome {
+            EvaluationOutcome::Returned(ref expr) => Some(expr),
+            EvaluationOutcome::Aborted | EvaluationOutcome::Thrown { .. } => None,
+        }
+    }
+
+    /// Convert this evaluation into its returned value, or an error if the evaluation
+    /// was aborted or ended in an uncaught `Throw[..]`.
+    ///
+    /// Any captured output is discarded.
+    pub fn into_result(self) -> Result<Expr, EvaluationError> {
+        match self.outcome {
+            EvaluationOutcome::Returned(expr) => Ok(expr),
+            EvaluationOutcome::Aborted => Err(EvaluationError::Aborted),
+            EvaluationOutcome::Thrown { value, tag } => {
+                Err(EvaluationError::Thrown { value, tag })
+            },
+        }
+    }
+
+    /// Split this evaluation into its outcome and captured output.
+    pub fn into_parts(self) -> (EvaluationOutcome, Vec<EvaluationOutput>) {
+        let Evaluation { outcome, output } = self;
+
+        (outcome, output)
+    }
+}
+
+impl fmt::Display for EvaluationOutput {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EvaluationOutput::Print(text) => write!(f, "{}", text),
+            EvaluationOutput::Message { name, text } if name.is_empty() => {
+                write!(f, "{}", text)
+            },
+            EvaluationOutput::Message { name, text } => write!(f, "{}: {}", name, text),
+        }
+    }
+}
+
+impl fmt::Display for EvaluationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EvaluationError::Link(message) => {
+                write!(f, "evaluation failed: {}", message)
+            },
+            EvaluationError::Aborted => write!(f, "evaluation was aborted"),
+            EvaluationError::Thrown { value, tag: None } => {
+                write!(f, "uncaught Throw: {}", value)
+            },
+            EvaluationError::Thrown {
+                value,
+                tag: Some(tag),
+            } => write!(f, "uncaught Throw with tag {}: {}", tag, value),
+            EvaluationError::Message { name, text } => {
+                write!(f, "evaluation issued message: {}: {}", name, text)
+            },
+            EvaluationError::Conversion(err) => {
+                write!(f, "unable to convert evaluation result: {}", err)
+            },
+        }
+    }
+}
+
+impl std::error::Error for EvaluationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EvaluationError::Conversion(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<FromExprError> for EvaluationError {
+    fn from(err: FromExprError) -> Self {
+        EvaluationError::Conversion(err)
+    }
+}
+
+//======================================
+// Functions
+//======================================
+
+/// Evaluate `expr` and convert the returned value into `T`.
+///
+/// Messages and `Print[..]` output generated by the evaluation are discarded. Use
+/// [`Evaluation::run()`] to inspect them.
+///
+/// # Example
+///
+/// ```no_run
+/// use wolfram_library_link::{
+///     self as wll,
+///     expr::{Expr, Symbol},
+/// };
+///
+/// // Evaluate `Range[5]`
+/// let expr = Expr::normal(Symbol::new("System`Range"), vec![Expr::from(5)]);
+///
+/// let values: Vec<i64> = wll::evaluate_as(&expr).unwrap();
+///
+/// assert_eq!(values, vec![1, 2, 3, 4, 5]);
+/// ```
+///
+/// # Panics
+///
+/// This function will panic if it is not called from the main Kernel thread.
+pub fn evaluate_as<T: FromExpr>(expr: &Expr) -> Result<T, EvaluationError> {
+    let returned = Evaluation::run(expr)?.into_result()?;
+
+    Ok(T::from_expr(&returned)?)
+}
+
+/// Get the value of the symbol `sym`, or `None` if `sym` has no value.
+///
+/// # Panics
+///
+/// This function will panic if it is not called from the main Kernel thread.
+pub fn symbol_value<T: FromExpr>(sym: &Symbol) -> Result<Option<T>, EvaluationError> {
+    let expr = wl!(If[ValueQ[#sym], {#sym}, {}]);
+
+    let returned = Evaluation::run(&expr)?.into_result()?;
+
+    match returned.kind() {
+        ExprKind::Normal(normal) if normal.has_head(&Symbol::new("System`List")) => {
+            match normal.elements() {
+                [] => Ok(None),
+                [value] => Ok(Some(T::from_expr(value)?)),
+                _ => Err(EvaluationError::Link(format!(
+                    "unexpected result from symbol_value(): {}",
+                    returned
+                ))),
+            }
+        },
+        _ => Err(EvaluationError::Link(format!(
+            "unexpected result from symbol_value(): {}",
+            returned
+        ))),
+    }
+}
+
+/// Set the value of the symbol `sym` to `value`.
+///
+/// `value` is assigned without being evaluated. An error is returned if the assignment
+/// issues a message, for example because `sym` is `Protected`.
+///
+/// # Panics
+///
+/// This function will panic if it is not called from the main Kernel thread.
+pub fn set_symbol_value(sym: &Symbol, value: Expr) -> Result<(), EvaluationError> {
+    let expr = wl!(#sym = Unevaluated[#value]);
+
+    let (outcome, output) = Evaluation::run(&expr)?.into_parts();
+
+    if let Some(EvaluationOutput::Message { name, text }) = output
+        .into_iter()
+        .find(|output| matches!(output, EvaluationOutput::Message { .. }))
+    {
+        return Err(EvaluationError::Message { name, text });
+    }
+
+    Evaluation {
+        outcome,
+        output: Vec::new(),
+    }
+    .into_result()?;
+
+    Ok(())
+}
+
+//======================================
+// Utilities
+//======================================
+
+/// Read packets from `link` until a `ReturnPacket` is received, and return its
+/// contents.
+///
+/// Messages and `Print[..]` output are captured by the code in `evaluation.wl`, which
+/// also stops them from being written to the link. Any other packets that precede the
+/// `ReturnPacket`, e.g. text written directly to `$Output`, are discarded.
+///
+/// The packet heads are read individually instead of using [`Link::get_expr()`] because
+/// the Kernel writes them without a context.
+fn read_return_packet(link: &mut Link) -> Result<Expr, EvaluationError> {
+    loop {
+        let length = match link.get_token().map_err(link_error)? {
+            Token::Function { length } => length,
+            _ => return Err(EvaluationError::Link("expected packet".to_owned())),
+        };
+
+        let head: String = link
+            .get_symbol_ref()
+            .map_err(link_error)?
+            .as_str()
+            .to_owned();
+
+        if head.trim_start_matches("System`") == "ReturnPacket" && length == 1 {
+            return link.get_expr().map_err(link_error);
+        }
+
+        // Skip the rest of this packet.
+        link.new_packet().map_err(link_error)?;
+    }
+}
+
+fn link_error(err: wstp::Error) -> EvaluationError {
+    EvaluationError::Link(err.to_string())
+}
+
+/// Replace each `` RustLink`Private`symbol["Context`Name"] `` in `expr` with the named
+/// symbol.
+fn decode_symbols(expr: &Expr) -> Option<Expr> {
+    let normal = match expr.kind() {
+        ExprKind::Normal(normal) => normal,
+        ExprKind::Symbol(_) => return None,
+        ExprKind::Integer(_) | ExprKind::Real(_) | ExprKind::String(_) => {
+            return Some(expr.clone())
+        },
+    };
+
+    if normal.has_head(&Symbol::new("RustLink`Private`symbol")) {
+        return match normal.elements() {
+            [name] => Some(Expr::from(Symbol::try_new(string(name)?)?)),
+            _ => None,
+        };
+    }
+
+    let head = decode_symbols(normal.head())?;
+    let elements = normal
+        .elements()
+        .iter()
+        .map(decode_symbols)
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(Expr::normal(head, elements))
+}
+
+/// Parse the `{outcome, output}` list returned by the code in `evaluation.wl`.
+fn parse_evaluation_result(
+    expr: &Expr,
+) -> Option<(EvaluationOutcome, Vec<EvaluationOutput>)> {
+    let (outcome, output) = match list_elements(expr)? {
+        [outcome, output] => (outcome, output),
+        _ => return None,
+    };
+
+    let outcome = match list_elements(outcome)? {
+        [kind, value] if string(kind)? == "Returned" => {
+            EvaluationOutcome::Returned(value.clone())
+        },
+        [kind] if string(kind)? == "Aborted" => EvaluationOutcome::Aborted,
+        [kind, value] if string(kind)? == "Thrown" => EvaluationOutcome::Thrown {
+            value: value.clone(),
+            tag: None,
+        },
+        [kind, value, tag] if string(kind)? == "Thrown" => EvaluationOutcome::Thrown {
+            value: value.clone(),
+            tag: Some(tag.clone()),
+        },
+        _ => return None,
+    };
+
+    let output = list_elements(output)?
+        .iter()
+        .map(|elem| match list_elements(elem)? {
+            [kind, text] if string(kind)? == "Print" => {
+                Some(EvaluationOutput::Print(string(text)?.to_owned()))
+            },
+            [kind, name, text] if string(kind)? == "Message" => {
+                Some(EvaluationOutput::Message {
+                    name: string(name)?.to_owned(),
+                    text: string(text)?.to_owned(),
+                })
+            },
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    Some((outcome, output))
+}
+
+fn list_elements(expr: &Expr) -> Option<&[Expr]> {
+    match expr.kind() {
+        ExprKind::Normal(normal) if normal.has_head(&Symbol::new("System`List")) => {
+            Some(normal.elements())
+        },
+        _ => None,
+    }
+}
+
+fn string(expr: &Expr) -> Option<&str> {
+    match expr.kind() {
+        ExprKind::String(string) => Some(string.as_str()),
+        _ => None,
+    }
+}
@@ -0,0 +1,133 @@
+(* Evaluates its held argument, capturing any messages and Print output that are
+   generated, and whether the evaluation was aborted or ended in an uncaught Throw.
+
+   Returns {outcome, output}, where `outcome` is one of:
+
+     {"Returned", result}
+     {"Aborted"}
+     {"Thrown", value}
+     {"Thrown", value, tag}
+
+   and `output` is a list of {"Print", text} and {"Message", name, text} elements.
+
+   This is the only place output is captured: messages and Print output are not written
+   to $Messages or $Output while the expression is evaluated, so no MessagePacket or
+   TextPacket is sent for them.
+
+   The Kernel writes symbols to the link without their context when the context is on
+   $ContextPath, so every symbol in the returned list (including List) is replaced by
+   RustLink`Private`symbol["Context`Name"], which is always written in full.
+
+   Symbols are written in the RustLink`Private` context so that they are not renamed
+   by Module if they also appear in the evaluated expression. *)
+Function[
+    RustLink`Private`expr,
+    Module[{
+        RustLink`Private`output = {},
+        RustLink`Private`outcome = None,
+        RustLink`Private`thrown,
+        (* When called from a WSTP function, $ContextPath is {} (see the
+           'Symbol contexts problem' section of the crate documentation). Format text
+           using the default context path in that case, so that e.g. Pi is not written
+           as System`Pi. *)
+        RustLink`Private`toText = Function[
+            If[$ContextPath === {},
+                Block[{$Context = "Global`", $ContextPath = {"System`", "Global`"}},
+                    ToString[#]
+                ],
+                ToString[#]
+            ]
+        ]
+    },
+        RustLink`Private`thrown = Catch[
+            RustLink`Private`outcome = Catch[
+                CheckAbort[
+                    Internal`HandlerBlock[
+                        {
+                            "Message",
+                            Function[
+                                RustLink`Private`message,
+                                (* Only record messages that are actually issued, not
+                                   those that are turned off or quieted. *)
+                                If[Last[RustLink`Private`message],
+                                    AppendTo[
+                                        RustLink`Private`output,
+                                        Replace[RustLink`Private`message, {
+                                            Hold[
+                                                Message[
+                                                    MessageName[
+                                                        RustLink`Private`sym_Symbol,
+                                                        RustLink`Private`tag_String
+                                                    ],
+                                                    RustLink`Private`args___
+                                                ],
+                                                _
+                                            ] :> With[{
+                                                RustLink`Private`template = MessageName[
+                                                    RustLink`Private`sym,
+                                                    RustLink`Private`tag
+                                                ]
+                                            },
+                                                {
+                                                    "Message",
+                                                    StringJoin[
+                                                        SymbolName[Unevaluated[RustLink`Private`sym]],
+                                                        "::",
+                                                        RustLink`Private`tag
+                                                    ],
+                                                    RustLink`Private`toText[StringForm[
+                                                        If[StringQ[RustLink`Private`template],
+                                                            RustLink`Private`template,
+                                                            MessageName[General, RustLink`Private`tag]
+                                                        ],
+                                                        RustLink`Private`args
+                                                    ]]
+                                                }
+                                            ],
+                                            RustLink`Private`other_ :> {
+                                                "Message",
+                                                "",
+                                                RustLink`Private`toText[RustLink`Private`other]
+                                            }
+                                        }]
+                                    ]
+                                ]
+                            ]
+                        },
+                        Block[{
+                            $Messages = {},
+                            Print = Function[
+                                Null,
+                                AppendTo[
+                                    RustLink`Private`output,
+                                    {"Print", RustLink`Private`toText[Row[{##}]]}
+                                ];
+                            ]
+                        },
+                            {"Returned", RustLink`Private`expr}
+                        ]
+                    ],
+                    {"Aborted"}
+                ],
+                _,
+                {"Thrown", #1, #2} &
+            ];
+        ];
+
+        (* If `outcome` was never set, the evaluation ended in an untagged Throw. *)
+        If[RustLink`Private`outcome === None,
+            RustLink`Private`outcome = {"Thrown", RustLink`Private`thrown}
+        ];
+
+        ReplaceAll[
+            {RustLink`Private`outcome, RustLink`Private`output},
+            RustLink`Private`s_Symbol :> RustLink`Private`symbol[
+                StringJoin[
+                    Context[Unevaluated[RustLink`Private`s]],
+                    SymbolName[Unevaluated[RustLink`Private`s]]
+                ]
+            ]
+        ]
+    ],
+    HoldAll
+]
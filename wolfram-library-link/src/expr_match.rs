@@ -0,0 +1,52 @@
+//! Runtime support for the [`expr_match!`][crate::expr_match] macro.
+
+use std::fmt;
+
+use crate::expr::Expr;
+
+/// Error returned by [`expr_match!`][crate::expr_match] when an expression does not
+/// match any of the patterns.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExprMatchError {
+    expr: Expr,
+    patterns: &'static [&'static str],
+}
+
+impl ExprMatchError {
+    pub(crate) fn new(expr: &Expr, patterns: &'static [&'static str]) -> Self {
+        ExprMatchError {
+            expr: expr.clone(),
+            patterns,
+        }
+    }
+
+    /// The expression that did not match.
+    pub fn expr(&self) -> &Expr {
+        &self.expr
+    }
+
+    /// The patterns that were tried, formatted using Wolfram Language syntax.
+    pub fn patterns(&self) -> &[&'static str] {
+        self.patterns
+    }
+}
+
+impl fmt::Display for ExprMatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ExprMatchError { expr, patterns } = self;
+
+        match patterns {
+            [pattern] => {
+                write!(f, "expected expression matching {}, got: {}", pattern, expr)
+            },
+            _ => write!(
+                f,
+                "expected expression matching one of {}, got: {}",
+                patterns.join(", "),
+                expr
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ExprMatchError {}
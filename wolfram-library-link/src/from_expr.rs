@@ -0,0 +1,277 @@
+//! Conversion of Wolfram Language expressions into Rust values.
+
+use std::fmt;
+
+use crate::{
+    data_store::expr::{has_head, image_from_expr, numeric_array_from_expr},
+    expr::{Expr, ExprKind, Symbol},
+    DataStore, Image, ImageData, NumericArray, NumericArrayType,
+};
+
+/// Trait implemented for types that can be constructed from an [`Expr`].
+///
+/// This is used by [`evaluate_as()`][crate::evaluate_as] and
+/// [`symbol_value()`][crate::symbol_value] to convert the result of an evaluation into a
+/// Rust value.
+///
+/// # Example
+///
+/// ```
+/// use wolfram_library_link::{expr::Expr, FromExpr};
+///
+/// let list = Expr::list(vec![Expr::from(1), Expr::from(2), Expr::from(3)]);
+///
+/// assert_eq!(Vec::<i64>::from_expr(&list), Ok(vec![1, 2, 3]));
+/// assert!(String::from_expr(&list).is_err());
+/// ```
+pub trait FromExpr: Sized {
+    /// Construct a value of this type from `expr`.
+    fn from_expr(expr: &Expr) -> Result<Self, FromExprError>;
+}
+
+/// Error returned by [`FromExpr::from_expr()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FromExprError {
+    expected: &'static str,
+    expr: Expr,
+    reason: Option<String>,
+}
+
+impl FromExprError {
+    /// Construct a new error for `expr`, which could not be converted into a value of
+    /// the type described by `expected`.
+    pub fn new(expected: &'static str, expr: &Expr) -> Self {
+        FromExprError {
+            expected,
+            expr: expr.clone(),
+            reason: None,
+        }
+    }
+
+    /// Attach a description of why `expr` could not be converted, e.g. the error
+    /// returned by a lower-level conversion.
+    pub(crate) fn with_reason(mut self, reason: impl fmt::Display) -> Self {
+        self.reason = Some(reason.to_string());
+        self
+    }
+
+    /// Description of the expected form of the expression.
+    pub fn expected(&self) -> &str {
+        self.expected
+    }
+
+    /// The expression that could not be converted.
+    pub fn expr(&self) -> &Expr {
+        &self.expr
+    }
+
+    /// Description of why the expression could not be converted, if more detail is
+    /// available than [`expected()`][FromExprError::expected].
+    pub fn reason(&self) -> Option<&str> {
+        self.reason.as_deref()
+    }
+}
+
+impl fmt::Display for FromExprError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let FromExprError {
+            expected,
+            expr,
+            reason,
+        } = self;
+
+        write!(f, "expected {}, got: {}", expected, expr)?;
+
+        if let Some(reason) = reason {
+            write!(f, " ({})", reason)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for FromExprError {}
+
+//======================================
+// Impls
+//======================================
+
+impl FromExpr for Expr {
+    fn from_expr(expr: &Expr) -> Result<Self, FromExprError> {
+        Ok(expr.clone())
+    }
+}
+
+impl FromExpr for bool {
+    fn from_expr(expr: &Expr) -> Result<Self, FromExprError> {
+        match expr.kind() {
+            ExprKind::Symbol(sym) if sym.as_str() == "System`True" => Ok(true),
+            ExprKind::Symbol(sym) if sym.as_str() == "System`False" => Ok(false),
+            _ => Err(FromExprError::new("True or False", expr)),
+        }
+    }
+}
+
+impl FromExpr for i64 {
+    fn from_expr(expr: &Expr) -> Result<Self, FromExprError> {
+        match *expr.kind() {
+            ExprKind::Integer(value) => Ok(value),
+            _ => Err(FromExprError::new("Integer", expr)),
+        }
+    }
+}
+
+macro_rules! impl_FromExpr_for_integer {
+    ($($type:ty),*) => {
+        $(
+            impl FromExpr for $type {
+                fn from_expr(expr: &Expr) -> Result<Self, FromExprError> {
+                    let value = i64::from_expr(expr)?;
+
+                    <$type>::try_from(value).map_err(|_| {
+                        FromExprError::new(
+                            concat!("Integer in the range of ", stringify!($type)),
+                            expr,
+                        )
+                    })
+                }
+            }
+        )*
+    };
+}
+
+impl_FromExpr_for_integer!(i8, i16, i32, u8, u16, u32, u64, isize, usize);
+
+impl FromExpr for f64 {
+    fn from_expr(expr: &Expr) -> Result<Self, FromExprError> {
+        match *expr.kind() {
+            ExprKind::Real(value) => Ok(*value),
+            ExprKind::Integer(value) => Ok(value as f64),
+            _ => Err(FromExprError::new("Real or Integer", expr)),
+        }
+    }
+}
+
+impl FromExpr for f32 {
+    fn from_expr(expr: &Expr) -> Result<Self, FromExprError> {
+        f64::from_expr(expr).map(|value| value as f32)
+    }
+}
+
+impl FromExpr for String {
+    fn from_expr(expr: &Expr) -> Result<Self, FromExprError> {
+        match expr.kind() {
+            ExprKind::String(string) => Ok(string.clone()),
+            _ => Err(FromExprError::new("String", expr)),
+        }
+    }
+}
+
+impl FromExpr for Symbol {
+    fn from_expr(expr: &Expr) -> Result<Self, FromExprError> {
+        match expr.kind() {
+            ExprKind::Symbol(sym) => Ok(sym.clone()),
+            _ => Err(FromExprError::new("Symbol", expr)),
+        }
+    }
+}
+
+/// Converts a `List[...]` expression, converting each element into `T`.
+impl<T: FromExpr> FromExpr for Vec<T> {
+    fn from_expr(expr: &Expr) -> Result<Self, FromExprError> {
+        match expr.kind() {
+            ExprKind::Normal(normal) if normal.has_head(&Symbol::new("System`List")) => {
+                normal.elements().iter().map(T::from_expr).collect()
+            },
+            _ => Err(FromExprError::new("List", expr)),
+        }
+    }
+}
+
+/// Converts `None` or `Missing[...]` into `None`, and any other expression into
+/// `Some(T)`.
+impl<T: FromExpr> FromExpr for Option<T> {
+    fn from_expr(expr: &Expr) -> Result<Self, FromExprError> {
+        match expr.kind() {
+            ExprKind::Symbol(sym) if sym.as_str() == "System`None" => Ok(None),
+            ExprKind::Normal(normal)
+                if normal.has_head(&Symbol::new("System`Missing")) =>
+            {
+                Ok(None)
+            },
+            _ => T::from_expr(expr).map(Some),
+        }
+    }
+}
+
+/// Converts a `` Developer`DataStore[...] `` expression using
+/// [`DataStore::from_expr()`].
+impl FromExpr for DataStore {
+    fn from_expr(expr: &Expr) -> Result<Self, FromExprError> {
+        DataStore::from_expr(expr)
+            .map_err(|err| FromExprError::new("DataStore", expr).with_reason(err))
+    }
+}
+
+/// Converts a `NumericArray[data, "type"]` expression.
+impl FromExpr for NumericArray {
+    fn from_expr(expr: &Expr) -> Result<Self, FromExprError> {
+        let invalid = || FromExprError::new("NumericArray", expr);
+
+        match expr.kind() {
+            ExprKind::Normal(normal) if has_head(normal, "System`NumericArray") => {
+                numeric_array_from_expr(expr, normal)
+                    .map_err(|err| invalid().with_reason(err))
+            },
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Converts a `NumericArray[data, "type"]` expression whose type is `T`, or a
+/// rectangular `List[...]` whose elements can be represented as `T`.
+impl<T: NumericArrayType> FromExpr for NumericArray<T> {
+    fn from_expr(expr: &Expr) -> Result<Self, FromExprError> {
+        let invalid = || FromExprError::new(T::TYPE.name(), expr);
+
+        let array = match expr.kind() {
+            ExprKind::Normal(normal) if has_head(normal, "System`List") => {
+                let typed = Expr::normal(Symbol::new("System`NumericArray"), vec![
+                    expr.clone(),
+                    Expr::string(T::TYPE.name()),
+                ]);
+
+                NumericArray::from_expr(&typed).map_err(|err| match err.reason {
+                    Some(reason) => invalid().with_reason(reason),
+                    None => invalid(),
+                })?
+            },
+            _ => NumericArray::from_expr(expr)?,
+        };
+
+        array.try_into_kind::<T>().map_err(|_| invalid())
+    }
+}
+
+/// Converts an `Image[data, "type", opts]` or `Image3D[data, "type", opts]` expression.
+impl FromExpr for Image {
+    fn from_expr(expr: &Expr) -> Result<Self, FromExprError> {
+        image_from_expr(expr)
+            .map_err(|err| FromExprError::new("Image", expr).with_reason(err))
+    }
+}
+
+/// Converts an `Image[data, "type", opts]` or `Image3D[data, "type", opts]` expression
+/// whose type is the [`ImageType`][crate::ImageType] of `T`.
+impl<T: ImageData> FromExpr for Image<T> {
+    fn from_expr(expr: &Expr) -> Result<Self, FromExprError> {
+        let image: Image = Image::from_expr(expr)?;
+
+        if image.data_type() != T::TYPE {
+            return Err(FromExprError::new(T::TYPE.name(), expr));
+        }
+
+        // Safety: The data type of `image` was checked above.
+        Ok(unsafe { Image::from_raw(image.into_raw()) })
+    }
+}
@@ -7,12 +7,21 @@ use crate::{
     sys::{self, mbool, mint, MImage_CS_Type::*, MImage_Data_Type::*},
 };
 
+mod pixels;
+
+#[cfg(feature = "image")]
+mod image_crate;
+
+pub use self::pixels::{Pixels, Rows, Samples};
+
+#[cfg(feature = "image")]
+pub use self::image_crate::ImageConversionError;
+
 /// Native Wolfram [`Image`][ref/Image]<sub>WL</sub> or
 /// [`Image3D`][ref/Image3D]<sub>WL</sub>.
 ///
-// TODO: This represents a 2-dimensional image.
-///
-/// Use [`UninitImage::new_2d()`] to construct a new 2-dimensional image.
+/// Use [`UninitImage::new_2d()`] to construct a new 2-dimensional image, or
+/// [`UninitImage::new_3d()`] to construct a new 3-dimensional image.
 ///
 /// [ref/Image]: https://reference.wolfram.com/language/ref/Image.html
 /// [ref/Image3D]: https://reference.wolfram.com/language/ref/Image3D.html
@@ -26,6 +35,7 @@ pub struct Image<T = ()>(sys::MImage, PhantomData<T>);
 pub struct UninitImage<T: ImageData>(sys::MImage, PhantomData<T>);
 
 /// Type of data stored in an [`Image`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(i32)]
 #[allow(missing_docs)]
 pub enum ImageType {
@@ -37,6 +47,7 @@ pub enum ImageType {
 }
 
 /// Color space used by an [`Image`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(i32)]
 #[allow(missing_docs)]
 pub enum ColorSpace {
@@ -64,6 +75,27 @@ pub enum Pixel {
     D3([usize; 3]),
 }
 
+/// Iterator over the position of every pixel in an [`Image`].
+///
+/// Pixel positions are visited in the order they are stored in the image data: in a
+/// 3-dimensional image, every position in the first slice is visited before any
+/// position in the second slice, and in each slice, every position in the first row is
+/// visited before any position in the second row.
+///
+/// Positions are 1-indexed, following the convention used by the LibraryLink pixel
+/// getter and setter functions.
+///
+/// Created by [`Image::pixel_positions()`] and [`UninitImage::pixel_positions()`].
+#[derive(Debug, Clone)]
+pub struct PixelPositions {
+    rank: usize,
+    /// `[slices, rows, columns]`. `slices` is 1 for 2-dimensional images.
+    dimensions: [usize; 3],
+    /// Linear index of the next pixel position to return.
+    index: usize,
+    len: usize,
+}
+
 impl Pixel {
     /// Construct a pixel position from a slice of indices.
     ///
@@ -82,6 +114,32 @@ impl Pixel {
         }
     }
 
+    /// Get the slice index of this position.
+    ///
+    /// Returns `None` if this is a 2-dimensional position.
+    pub fn slice(&self) -> Option<usize> {
+        match *self {
+            Pixel::D2(_) => None,
+            Pixel::D3([slice, _, _]) => Some(slice),
+        }
+    }
+
+    /// Get the row index of this position.
+    pub fn row(&self) -> usize {
+        match *self {
+            Pixel::D2([row, _]) => row,
+            Pixel::D3([_, row, _]) => row,
+        }
+    }
+
+    /// Get the column index of this position.
+    pub fn column(&self) -> usize {
+        match *self {
+            Pixel::D2([_, column]) => column,
+            Pixel::D3([_, _, column]) => column,
+        }
+    }
+
     fn as_slice(&self) -> &[usize] {
         match self {
             Pixel::D2(array) => array,
@@ -269,7 +327,7 @@ impl<T: ImageData> Image<T> {
     /// # let image: Image<u8> = todo!();
     /// // let image: Image<u8> = ...
     ///
-    /// let value: u8 = image.get(Pixel::D2([0, 0]), 2).unwrap();
+    /// let value: u8 = image.get(Pixel::D2([1, 1]), 2).unwrap();
     /// ```
     ///
     /// In an [`RGB`][ColorSpace::RGB] image, this is the value of the green channel for
@@ -396,6 +454,29 @@ impl<T> Image<T> {
         crate::bool_from_mbool(boole)
     }
 
+    /// Iterate over the position of every pixel in this image.
+    ///
+    /// The returned positions are [`Pixel::D2`] values for 2-dimensional images, and
+    /// [`Pixel::D3`] values for 3-dimensional images.
+    ///
+    /// # Example
+    ///
+    /// Compute the sum of the first channel of every pixel in an image.
+    ///
+    /// ```no_run
+    /// # use wolfram_library_link::Image;
+    /// # let image: Image<u8> = todo!();
+    /// // let image: Image<u8> = ...
+    ///
+    /// let sum: u64 = image
+    ///     .pixel_positions()
+    ///     .map(|pixel| u64::from(image.get(pixel, 1).unwrap()))
+    ///     .sum();
+    /// ```
+    pub fn pixel_positions(&self) -> PixelPositions {
+        unsafe { PixelPositions::new(self.as_raw()) }
+    }
+
     /// Returns the share count of this `Image`.
     ///
     /// If this `Image` is not shared, the share count is 0.
@@ -411,6 +492,44 @@ impl<T> Image<T> {
         usize::try_from(count).expect("Image share count mint overflows usize")
     }
 
+    /// Convert this image to a new image with element type `T2`.
+    ///
+    /// If `interleaving` is true, the returned image will store its channels in
+    /// interleaved order, otherwise the returned image will store each channel as a
+    /// separate plane.
+    ///
+    /// Returns `None` if the conversion fails.
+    ///
+    /// # Example
+    ///
+    /// Convert a [`Byte`][ImageType::Bit8] image into a non-interleaved
+    /// [`Real32`][ImageType::Real32] image.
+    ///
+    /// ```no_run
+    /// # use wolfram_library_link::Image;
+    /// # let image: Image<u8> = todo!();
+    /// // let image: Image<u8> = ...
+    ///
+    /// let image: Image<f32> = image.convert_to(false).unwrap();
+    /// ```
+    ///
+    /// *LibraryLink C API Documentation:* [`MImage_convertType`](https://reference.wolfram.com/language/LibraryLink/ref/callback/MImage_convertType.html)
+    pub fn convert_to<T2: ImageData>(&self, interleaving: bool) -> Option<Image<T2>> {
+        let new_raw: sys::MImage = unsafe {
+            rtl::MImage_convertType(
+                self.as_raw(),
+                T2::TYPE.as_raw(),
+                mbool::from(interleaving),
+            )
+        };
+
+        if new_raw.is_null() {
+            return None;
+        }
+
+        Some(unsafe { Image::from_raw(new_raw) })
+    }
+
     //
     // Raw Image's
     //
@@ -443,6 +562,13 @@ impl<T> Image<T> {
     pub unsafe fn raw_data(&self) -> *mut c_void {
         rtl::MImage_getRawData(self.as_raw())
     }
+
+    /// Erase the concrete `T` data type associated with this `Image`.
+    pub(crate) fn as_generic(&self) -> &Image {
+        // Safety: `Image` is `repr(transparent)`, so `Image<T>` and `Image` have the
+        //         same layout.
+        unsafe { &*(self as *const Image<T> as *const Image) }
+    }
 }
 
 impl<T: ImageData> UninitImage<T> {
@@ -497,6 +623,88 @@ impl<T: ImageData> UninitImage<T> {
         Ok(UninitImage(new_raw, PhantomData))
     }
 
+    /// Construct a new uninitialized 3-dimensional image with the specified properties.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if [`UninitImage::try_new_3d()`] returns an error.
+    pub fn new_3d(
+        slices: usize,
+        width: usize,
+        height: usize,
+        channels: usize,
+        space: ColorSpace,
+        interleaving: bool,
+    ) -> UninitImage<T> {
+        UninitImage::try_new_3d(slices, width, height, channels, space, interleaving)
+            .expect("UninitImage::new_3d: failed to create image")
+    }
+
+    /// Construct a new uninitialized 3D image.
+    // TODO: Use a better error type than i64.
+    pub fn try_new_3d(
+        slices: usize,
+        width: usize,
+        height: usize,
+        channels: usize,
+        space: ColorSpace,
+        interleaving: bool,
+    ) -> Result<UninitImage<T>, i64> {
+        let slices = mint::try_from(slices).expect("image slice count overflows `mint`");
+        let width = mint::try_from(width).expect("image width overflows `mint`");
+        let height = mint::try_from(height).expect("image height overflows `mint`");
+        let channels =
+            mint::try_from(channels).expect("image channels count overflows `mint`");
+
+        let mut new_raw: sys::MImage = std::ptr::null_mut();
+
+        let err_code: c_int = unsafe {
+            rtl::MImage_new3D(
+                slices,
+                width,
+                height,
+                channels,
+                T::TYPE.as_raw(),
+                space.as_raw(),
+                mbool::from(interleaving),
+                &mut new_raw,
+            )
+        };
+
+        if err_code != 0 || new_raw.is_null() {
+            return Err(i64::from(err_code));
+        }
+
+        Ok(UninitImage(new_raw, PhantomData))
+    }
+
+    /// Iterate over the position of every pixel in this image.
+    ///
+    /// See [`Image::pixel_positions()`].
+    ///
+    /// # Example
+    ///
+    /// Initialize every pixel of a single channel 3D image.
+    ///
+    /// ```no_run
+    /// use wolfram_library_link::{ColorSpace, Image, UninitImage};
+    ///
+    /// let mut image = UninitImage::<u8>::new_3d(4, 16, 16, 1, ColorSpace::Gray, false);
+    ///
+    /// for pixel in image.pixel_positions() {
+    ///     let value = if pixel.slice() == Some(1) { 0 } else { 255 };
+    ///
+    ///     image.set(pixel, 1, value);
+    /// }
+    ///
+    /// let image: Image<u8> = unsafe { image.assume_init() };
+    /// ```
+    pub fn pixel_positions(&self) -> PixelPositions {
+        let UninitImage(raw, PhantomData) = *self;
+
+        unsafe { PixelPositions::new(raw) }
+    }
+
     /// Efficiently set every pixel value in this image to zero.
     ///
     /// This fully initializes this image, albeit to a black image.
@@ -544,6 +752,35 @@ impl<T: ImageData> UninitImage<T> {
         }
     }
 
+    /// Initialize every pixel in this image by copying from `source`, which must use the
+    /// layout of the underlying image buffer.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `source` does not have the same length as the
+    /// flattened image data.
+    pub(crate) fn init_from_storage(self, source: &[T::STORAGE]) -> Image<T> {
+        let UninitImage(raw, PhantomData) = self;
+
+        let len: mint = unsafe { rtl::MImage_getFlattenedLength(raw) };
+        let len =
+            usize::try_from(len).expect("UninitImage flattened length overflows usize");
+
+        assert_eq!(
+            source.len(),
+            len,
+            "image data and source slices have different lengths"
+        );
+
+        unsafe {
+            let data_ptr = rtl::MImage_getRawData(raw) as *mut T::STORAGE;
+
+            std::ptr::copy_nonoverlapping(source.as_ptr(), data_ptr, len);
+
+            self.assume_init()
+        }
+    }
+
     /// Assume that the data in this image has been initialized.
     ///
     /// Use [`UninitImage::zero()`] to quickly ensure that every pixel value has been
@@ -567,6 +804,33 @@ impl<T: ImageData> UninitImage<T> {
     }
 }
 
+impl PixelPositions {
+    unsafe fn new(raw: sys::MImage) -> Self {
+        let rank = rtl::MImage_getRank(raw);
+        let slices = rtl::MImage_getSliceCount(raw);
+        let rows = rtl::MImage_getRowCount(raw);
+        let columns = rtl::MImage_getColumnCount(raw);
+
+        let rank = usize::try_from(rank).expect("Image rank overflows usize");
+        let rows = usize::try_from(rows).expect("Image row count overflows usize");
+        let columns =
+            usize::try_from(columns).expect("Image column count overflows usize");
+
+        let slices = match rank {
+            2 => 1,
+            3 => usize::try_from(slices).expect("Image slice count overflows usize"),
+            _ => panic!("Image has unexpected rank: {}", rank),
+        };
+
+        PixelPositions {
+            rank,
+            dimensions: [slices, rows, columns],
+            index: 0,
+            len: slices * rows * columns,
+        }
+    }
+}
+
 impl ImageType {
     #[allow(missing_docs)]
     pub fn as_raw(self) -> sys::imagedata_t {
@@ -605,6 +869,42 @@ impl ColorSpace {
 // Trait Impls
 //======================================
 
+impl Iterator for PixelPositions {
+    type Item = Pixel;
+
+    fn next(&mut self) -> Option<Pixel> {
+        if self.index >= self.len {
+            return None;
+        }
+
+        let [_, rows, columns] = self.dimensions;
+
+        let index = self.index;
+        self.index += 1;
+
+        // Convert the 0-indexed linear index into a 1-indexed position.
+        let column = index % columns + 1;
+        let row = (index / columns) % rows + 1;
+        let slice = index / (columns * rows) + 1;
+
+        let pixel = match self.rank {
+            2 => Pixel::D2([row, column]),
+            3 => Pixel::D3([slice, row, column]),
+            _ => unreachable!(),
+        };
+
+        Some(pixel)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.len - self.index;
+
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for PixelPositions {}
+
 impl TryFrom<sys::imagedata_t> for ImageType {
     type Error = ();
 
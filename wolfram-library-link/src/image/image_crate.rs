@@ -0,0 +1,470 @@
+//! Conversions between [`Image`] and the [`image`][::image] crate types
+//! [`ImageBuffer`] and [`DynamicImage`].
+//!
+//! This module is only available when the `"image"` feature of `wolfram-library-link`
+//! is enabled.
+
+use std::{fmt, ops::Deref};
+
+use ::image::{DynamicImage, ImageBuffer, Primitive};
+use ref_cast::RefCast;
+
+use super::{ColorSpace, Image, ImageData, ImageType, UninitImage};
+
+/// Error returned when an [`Image`] cannot be converted into an [`image`][::image]
+/// crate type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImageConversionError {
+    /// The image is not 2-dimensional.
+    ///
+    /// The [`image`][::image] crate does not support [`Image3D`][ref/Image3D] images.
+    ///
+    /// [ref/Image3D]: https://reference.wolfram.com/language/ref/Image3D.html
+    UnsupportedRank(usize),
+    /// The image data type has no equivalent [`DynamicImage`] variant.
+    UnsupportedDataType(ImageType),
+    /// The image color space has no equivalent [`DynamicImage`] variant.
+    UnsupportedColorSpace(ColorSpace),
+    /// The number of channels in the image is not valid for its color space.
+    UnsupportedChannelCount {
+        #[allow(missing_docs)]
+        space: ColorSpace,
+        #[allow(missing_docs)]
+        channels: usize,
+    },
+    /// The number of channels in the image does not match the number of channels in
+    /// the requested [`image::Pixel`][::image::Pixel] type.
+    ChannelCountMismatch {
+        #[allow(missing_docs)]
+        expected: usize,
+        #[allow(missing_docs)]
+        actual: usize,
+    },
+}
+
+/// Arrangement of the channels of a single pixel, using the naming conventions of the
+/// [`image`][::image] crate.
+#[derive(Copy, Clone)]
+enum PixelLayout {
+    Luma,
+    LumaA,
+    Rgb,
+    Rgba,
+}
+
+/// Types that can be stored in an [`Image`] and in a [`DynamicImage`].
+trait DynamicImageData: ImageData<STORAGE = Self> + Primitive {
+    /// Convert this value to the range `0.0..=1.0`.
+    fn to_unit(self) -> f32;
+
+    /// Convert a value in the range `0.0..=1.0` to `Self`.
+    fn from_unit(value: f32) -> Self;
+
+    fn into_dynamic_image(
+        layout: PixelLayout,
+        width: u32,
+        height: u32,
+        data: Vec<Self>,
+    ) -> DynamicImage;
+}
+
+//======================================
+// Impls
+//======================================
+
+impl<T> Image<T>
+where
+    T: ImageData<STORAGE = T> + Primitive,
+{
+    /// Copy this image into an [`ImageBuffer`] with pixel type `P`.
+    ///
+    /// The pixel data is always returned in interleaved order, regardless of the value
+    /// of [`is_interleaved()`][Image::is_interleaved].
+    ///
+    /// The [`color_space()`][Image::color_space] of this image is not taken into
+    /// account; only the number of channels in `P` must match
+    /// [`channels()`][Image::channels]. Use [`Image::to_dynamic_image()`] to perform a
+    /// color space aware conversion.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use wolfram_library_link::Image;
+    /// use image::{ImageBuffer, Rgb};
+    ///
+    /// # let image: Image<u8> = todo!();
+    /// // let image: Image<u8> = ...
+    ///
+    /// let buffer: ImageBuffer<Rgb<u8>, Vec<u8>> = image.to_image_buffer().unwrap();
+    /// ```
+    pub fn to_image_buffer<P>(
+        &self,
+    ) -> Result<ImageBuffer<P, Vec<T>>, ImageConversionError>
+    where
+        P: ::image::Pixel<Subpixel = T>,
+    {
+        let (width, height) = dimensions_2d(self)?;
+
+        let expected = usize::from(P::CHANNEL_COUNT);
+        let actual = self.channels();
+
+        if expected != actual {
+            return Err(ImageConversionError::ChannelCountMismatch { expected, actual });
+        }
+
+        let buffer = ImageBuffer::from_raw(width, height, self.interleaved_data())
+            .expect("Image::to_image_buffer: image data has unexpected length");
+
+        Ok(buffer)
+    }
+
+    /// Construct a new interleaved 2D image from the contents of an [`ImageBuffer`].
+    ///
+    /// The color space of the returned image is [`ColorSpace::Gray`] for 1 or 2 channel
+    /// pixel types, and [`ColorSpace::RGB`] for 3 or 4 channel pixel types. The last
+    /// channel of a 2 or 4 channel pixel type is the alpha channel.
+    pub fn from_image_buffer<P, C>(buffer: &ImageBuffer<P, C>) -> Image<T>
+    where
+        P: ::image::Pixel<Subpixel = T>,
+        C: Deref<Target = [T]>,
+    {
+        let channels = usize::from(P::CHANNEL_COUNT);
+
+        let space = match channels {
+            1 | 2 => ColorSpace::Gray,
+            3 | 4 => ColorSpace::RGB,
+            _ => ColorSpace::Automatic,
+        };
+
+        let width = usize::try_from(buffer.width()).expect("image width overflows usize");
+        let height =
+            usize::try_from(buffer.height()).expect("image height overflows usize");
+
+        let samples: &[T] = buffer;
+
+        let image = UninitImage::<T>::new_2d(width, height, channels, space, true);
+
+        let UninitImage(raw, _) = image;
+        let len = unsafe { crate::rtl::MImage_getFlattenedLength(raw) };
+
+        assert_eq!(
+            usize::try_from(len).ok(),
+            Some(samples.len()),
+            "Image::from_image_buffer: image data has unexpected length"
+        );
+
+        // Safety: The image was allocated with the same width, height, and number of
+        //         channels as `buffer`, and both store their data in interleaved order.
+        unsafe {
+            let data = crate::rtl::MImage_getRawData(raw) as *mut T;
+            std::ptr::copy_nonoverlapping(samples.as_ptr(), data, samples.len());
+
+            image.assume_init()
+        }
+    }
+
+    /// Get a copy of the data in this image in interleaved order.
+    fn interleaved_data(&self) -> Vec<T> {
+        let data: &[T] = self.as_slice();
+        let channels = self.channels();
+
+        if self.is_interleaved() || channels == 1 {
+            return data.to_vec();
+        }
+
+        // Non-interleaved images store each channel as a separate contiguous plane.
+        let plane_len = data.len() / channels;
+
+        let mut interleaved = Vec::with_capacity(data.len());
+
+        for index in 0..plane_len {
+            for channel in 0..channels {
+                interleaved.push(data[channel * plane_len + index]);
+            }
+        }
+
+        interleaved
+    }
+}
+
+impl<T> Image<T> {
+    /// Convert this image into a [`DynamicImage`].
+    ///
+    /// Images with data type [`ImageType::Bit8`], [`ImageType::Bit16`], or
+    /// [`ImageType::Real32`] in the [`Gray`][ColorSpace::Gray],
+    /// [`RGB`][ColorSpace::RGB], or [`CMYK`][ColorSpace::CMYK] color spaces are
+    /// supported, with or without an alpha channel.
+    ///
+    /// [`CMYK`][ColorSpace::CMYK] images are converted to RGB. `Real32` grayscale images
+    /// are converted to RGB, because [`DynamicImage`] has no 32-bit grayscale variant.
+    pub fn to_dynamic_image(&self) -> Result<DynamicImage, ImageConversionError> {
+        let (width, height) = dimensions_2d(self)?;
+
+        let (space, alpha) = color_model(self)?;
+
+        let image = match self.data_type() {
+            ImageType::Bit8 => convert::<u8>(self.typed(), space, alpha, width, height),
+            ImageType::Bit16 => convert::<u16>(self.typed(), space, alpha, width, height),
+            ImageType::Real32 => {
+                convert::<f32>(self.typed(), space, alpha, width, height)
+            },
+            other => return Err(ImageConversionError::UnsupportedDataType(other)),
+        };
+
+        Ok(image)
+    }
+
+    /// View this image as having element type `U`.
+    ///
+    /// Callers must ensure that the [`data_type()`][Image::data_type] of this image
+    /// matches `U`.
+    fn typed<U>(&self) -> &Image<U> {
+        let Image(ref raw, _) = *self;
+
+        Image::ref_cast(raw)
+    }
+
+    fn into_untyped(self) -> Image {
+        unsafe { Image::from_raw(self.into_raw()) }
+    }
+}
+
+impl Image {
+    /// Construct a new interleaved 2D image from a [`DynamicImage`].
+    ///
+    /// 8-bit, 16-bit, and 32-bit floating point `image` variants are converted into
+    /// [`Image`]s with the corresponding [`ImageType`]. Any other variant is first
+    /// converted to [`DynamicImage::ImageRgba32F`].
+    pub fn from_dynamic_image(image: &DynamicImage) -> Image {
+        use DynamicImage::*;
+
+        match image {
+            ImageLuma8(buffer) => Image::from_image_buffer(buffer).into_untyped(),
+            ImageLumaA8(buffer) => Image::from_image_buffer(buffer).into_untyped(),
+            ImageRgb8(buffer) => Image::from_image_buffer(buffer).into_untyped(),
+            ImageRgba8(buffer) => Image::from_image_buffer(buffer).into_untyped(),
+            ImageLuma16(buffer) => Image::from_image_buffer(buffer).into_untyped(),
+            ImageLumaA16(buffer) => Image::from_image_buffer(buffer).into_untyped(),
+            ImageRgb16(buffer) => Image::from_image_buffer(buffer).into_untyped(),
+            ImageRgba16(buffer) => Image::from_image_buffer(buffer).into_untyped(),
+            ImageRgb32F(buffer) => Image::from_image_buffer(buffer).into_untyped(),
+            ImageRgba32F(buffer) => Image::from_image_buffer(buffer).into_untyped(),
+            other => Image::from_image_buffer(&other.to_rgba32f()).into_untyped(),
+        }
+    }
+}
+
+//======================================
+// Utilities
+//======================================
+
+fn dimensions_2d<T>(image: &Image<T>) -> Result<(u32, u32), ImageConversionError> {
+    let rank = image.rank();
+
+    if rank != 2 {
+        return Err(ImageConversionError::UnsupportedRank(rank));
+    }
+
+    let width = u32::try_from(image.column_count()).expect("image width overflows u32");
+    let height = u32::try_from(image.row_count()).expect("image height overflows u32");
+
+    Ok((width, height))
+}
+
+/// Determine the color space and whether the image has an alpha channel, validating
+/// that the color space is supported and the number of channels is consistent.
+fn color_model<T>(image: &Image<T>) -> Result<(ColorSpace, bool), ImageConversionError> {
+    let channels = image.channels();
+    let alpha = image.has_alpha_channel();
+    let color_channels = channels - usize::from(alpha);
+
+    let space = match (image.color_space(), color_channels) {
+        (ColorSpace::Gray | ColorSpace::Automatic, 1) => ColorSpace::Gray,
+        (ColorSpace::RGB | ColorSpace::Automatic, 3) => ColorSpace::RGB,
+        (ColorSpace::CMYK, 4) => ColorSpace::CMYK,
+        (
+            space @ (ColorSpace::Gray
+            | ColorSpace::RGB
+            | ColorSpace::CMYK
+            | ColorSpace::Automatic),
+            _,
+        ) => {
+            return Err(ImageConversionError::UnsupportedChannelCount { space, channels })
+        },
+        (space, _) => return Err(ImageConversionError::UnsupportedColorSpace(space)),
+    };
+
+    Ok((space, alpha))
+}
+
+fn convert<T: DynamicImageData>(
+    image: &Image<T>,
+    space: ColorSpace,
+    alpha: bool,
+    width: u32,
+    height: u32,
+) -> DynamicImage {
+    let data = image.interleaved_data();
+
+    let (layout, data) = match (space, alpha) {
+        (ColorSpace::Gray, false) => (PixelLayout::Luma, data),
+        (ColorSpace::Gray, true) => (PixelLayout::LumaA, data),
+        (ColorSpace::RGB, false) => (PixelLayout::Rgb, data),
+        (ColorSpace::RGB, true) => (PixelLayout::Rgba, data),
+        (ColorSpace::CMYK, false) => (PixelLayout::Rgb, cmyk_to_rgb(&data, alpha)),
+        (ColorSpace::CMYK, true) => (PixelLayout::Rgba, cmyk_to_rgb(&data, alpha)),
+        _ => unreachable!("unsupported color space should have been rejected"),
+    };
+
+    T::into_dynamic_image(layout, width, height, data)
+}
+
+fn cmyk_to_rgb<T: DynamicImageData>(data: &[T], alpha: bool) -> Vec<T> {
+    let channels = 4 + usize::from(alpha);
+
+    let mut rgb = Vec::with_capacity(data.len() / channels * (channels - 1));
+
+    for pixel in data.chunks_exact(channels) {
+        let black = 1.0 - pixel[3].to_unit();
+
+        for &value in &pixel[..3] {
+            rgb.push(T::from_unit((1.0 - value.to_unit()) * black));
+        }
+
+        if alpha {
+            rgb.push(pixel[4]);
+        }
+    }
+
+    rgb
+}
+
+fn buffer<P: ::image::Pixel>(
+    width: u32,
+    height: u32,
+    data: Vec<P::Subpixel>,
+) -> ImageBuffer<P, Vec<P::Subpixel>> {
+    ImageBuffer::from_raw(width, height, data)
+        .expect("image data length does not match image dimensions")
+}
+
+//======================================
+// DynamicImageData Impls
+//======================================
+
+impl DynamicImageData for u8 {
+    fn to_unit(self) -> f32 {
+        f32::from(self) / f32::from(u8::MAX)
+    }
+
+    fn from_unit(value: f32) -> Self {
+        (value.clamp(0.0, 1.0) * f32::from(u8::MAX)).round() as u8
+    }
+
+    fn into_dynamic_image(
+        layout: PixelLayout,
+        width: u32,
+        height: u32,
+        data: Vec<Self>,
+    ) -> DynamicImage {
+        match layout {
+            PixelLayout::Luma => DynamicImage::ImageLuma8(buffer(width, height, data)),
+            PixelLayout::LumaA => DynamicImage::ImageLumaA8(buffer(width, height, data)),
+            PixelLayout::Rgb => DynamicImage::ImageRgb8(buffer(width, height, data)),
+            PixelLayout::Rgba => DynamicImage::ImageRgba8(buffer(width, height, data)),
+        }
+    }
+}
+
+impl DynamicImageData for u16 {
+    fn to_unit(self) -> f32 {
+        f32::from(self) / f32::from(u16::MAX)
+    }
+
+    fn from_unit(value: f32) -> Self {
+        (value.clamp(0.0, 1.0) * f32::from(u16::MAX)).round() as u16
+    }
+
+    fn into_dynamic_image(
+        layout: PixelLayout,
+        width: u32,
+        height: u32,
+        data: Vec<Self>,
+    ) -> DynamicImage {
+        match layout {
+            PixelLayout::Luma => DynamicImage::ImageLuma16(buffer(width, height, data)),
+            PixelLayout::LumaA => DynamicImage::ImageLumaA16(buffer(width, height, data)),
+            PixelLayout::Rgb => DynamicImage::ImageRgb16(buffer(width, height, data)),
+            PixelLayout::Rgba => DynamicImage::ImageRgba16(buffer(width, height, data)),
+        }
+    }
+}
+
+impl DynamicImageData for f32 {
+    fn to_unit(self) -> f32 {
+        self
+    }
+
+    fn from_unit(value: f32) -> Self {
+        value
+    }
+
+    fn into_dynamic_image(
+        layout: PixelLayout,
+        width: u32,
+        height: u32,
+        data: Vec<Self>,
+    ) -> DynamicImage {
+        // DynamicImage has no 32-bit floating point grayscale variants, so expand the
+        // gray channel into the red, green, and blue channels.
+        match layout {
+            PixelLayout::Luma => {
+                let data = data.iter().flat_map(|&gray| [gray, gray, gray]).collect();
+                DynamicImage::ImageRgb32F(buffer(width, height, data))
+            },
+            PixelLayout::LumaA => {
+                let data = data
+                    .chunks_exact(2)
+                    .flat_map(|pixel| [pixel[0], pixel[0], pixel[0], pixel[1]])
+                    .collect();
+                DynamicImage::ImageRgba32F(buffer(width, height, data))
+            },
+            PixelLayout::Rgb => DynamicImage::ImageRgb32F(buffer(width, height, data)),
+            PixelLayout::Rgba => DynamicImage::ImageRgba32F(buffer(width, height, data)),
+        }
+    }
+}
+
+//======================================
+// Formatting Impls
+//======================================
+
+impl fmt::Display for ImageConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ImageConversionError::UnsupportedRank(rank) => write!(
+                f,
+                "unsupported image rank: {}: only 2-dimensional images are supported",
+                rank
+            ),
+            ImageConversionError::UnsupportedDataType(ty) => {
+                write!(f, "unsupported image data type: \"{}\"", ty.name())
+            },
+            ImageConversionError::UnsupportedColorSpace(space) => {
+                write!(f, "unsupported image color space: {:?}", space)
+            },
+            ImageConversionError::UnsupportedChannelCount { space, channels } => write!(
+                f,
+                "unsupported channel count for {:?} color space: {}",
+                space, channels
+            ),
+            ImageConversionError::ChannelCountMismatch { expected, actual } => write!(
+                f,
+                "image channel count mismatch: expected {}, got {}",
+                expected, actual
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ImageConversionError {}
@@ -0,0 +1,486 @@
+//! Efficient iteration over and mapping of the pixel data stored in an [`Image`].
+//!
+//! The types in this module operate directly on the image data buffer, and do not call
+//! the LibraryLink pixel getter and setter functions.
+
+use std::ops::Range;
+
+use super::{ColorSpace, Image, ImageData, UninitImage};
+
+/// Arrangement of the samples stored in an image data buffer.
+#[derive(Debug, Copy, Clone)]
+struct Layout {
+    channels: usize,
+    /// Number of pixels in each channel plane.
+    pixel_count: usize,
+    interleaved: bool,
+}
+
+/// Iterator over a sequence of samples in an [`Image`].
+///
+/// A `Samples` iterator is returned for each pixel by [`Pixels`], and by
+/// [`Image::channel_values()`].
+#[derive(Debug, Clone)]
+pub struct Samples<'a, S> {
+    data: &'a [S],
+    offset: usize,
+    stride: usize,
+    remaining: usize,
+}
+
+/// Iterator over the pixels in an [`Image`].
+///
+/// Each item is a [`Samples`] iterator over the channel values of a single pixel.
+///
+/// Created by [`Image::pixels()`], and returned for each row by [`Rows`].
+#[derive(Debug, Clone)]
+pub struct Pixels<'a, S> {
+    data: &'a [S],
+    layout: Layout,
+    range: Range<usize>,
+}
+
+/// Iterator over the rows of pixels in an [`Image`].
+///
+/// Each item is a [`Pixels`] iterator over the pixels in a single row.
+///
+/// Created by [`Image::rows()`].
+#[derive(Debug, Clone)]
+pub struct Rows<'a, S> {
+    data: &'a [S],
+    layout: Layout,
+    row_len: usize,
+    range: Range<usize>,
+}
+
+//======================================
+// Impls
+//======================================
+
+impl<T: ImageData> Image<T> {
+    /// Access the data in this [`Image`] as a mutable flat buffer.
+    ///
+    /// If the [`share_count()`][Image::share_count] of this image is >= 1, this function
+    /// will return `None`.
+    pub fn as_slice_mut(&mut self) -> Option<&mut [T::STORAGE]> {
+        if self.share_count() == 0 {
+            // This is not a shared image. We have unique access to it's data.
+            unsafe { Some(self.as_slice_mut_unchecked()) }
+        } else {
+            None
+        }
+    }
+
+    /// Access the data in this [`Image`] as a mutable flat buffer.
+    ///
+    /// # Safety
+    ///
+    /// `Image` is an immutable shared data structure. There is no robust, easy way to
+    /// determine whether mutation of an `Image` is safe. Prefer to use [`UninitImage`]
+    /// or [`Image::map_pixels()`] to create a new image instead of mutating an existing
+    /// `Image`.
+    pub unsafe fn as_slice_mut_unchecked(&mut self) -> &mut [T::STORAGE] {
+        let raw = self.raw_data() as *mut T::STORAGE;
+        let len: usize = self.flattened_length();
+
+        std::slice::from_raw_parts_mut(raw, len)
+    }
+
+    /// Iterate over the pixels of this image.
+    ///
+    /// Pixels are visited in the same order as [`Image::pixel_positions()`]. Each item
+    /// is an iterator over the channel values of a single pixel, regardless of whether
+    /// this image [is interleaved][Image::is_interleaved].
+    ///
+    /// # Example
+    ///
+    /// Compute the average brightness of each pixel in an RGB image.
+    ///
+    /// ```no_run
+    /// # use wolfram_library_link::Image;
+    /// # let image: Image<f32> = todo!();
+    /// // let image: Image<f32> = ...
+    ///
+    /// let brightness: Vec<f32> = image
+    ///     .pixels()
+    ///     .map(|pixel| pixel.sum::<f32>() / 3.0)
+    ///     .collect();
+    /// ```
+    pub fn pixels(&self) -> Pixels<'_, T::STORAGE> {
+        let layout = self.layout();
+
+        Pixels {
+            data: self.as_slice(),
+            layout,
+            range: 0..layout.pixel_count,
+        }
+    }
+
+    /// Iterate over the rows of pixels in this image.
+    ///
+    /// The rows of a 3-dimensional image are visited slice by slice.
+    pub fn rows(&self) -> Rows<'_, T::STORAGE> {
+        let layout = self.layout();
+        let row_len = self.column_count();
+
+        let row_count = layout.pixel_count.checked_div(row_len).unwrap_or(0);
+
+        Rows {
+            data: self.as_slice(),
+            layout,
+            row_len,
+            range: 0..row_count,
+        }
+    }
+
+    /// Iterate over the values of a single channel of every pixel in this image.
+    ///
+    /// Like [`Image::get()`], the first channel has index 1.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `channel` is 0 or greater than
+    /// [`channels()`][Image::channels].
+    pub fn channel_values(&self, channel: usize) -> Samples<'_, T::STORAGE> {
+        let layout = self.layout();
+
+        assert!(
+            channel >= 1 && channel <= layout.channels,
+            "Image::channel_values: invalid channel index {} for image with {} channels",
+            channel,
+            layout.channels
+        );
+
+        let (offset, stride) = layout.channel(channel - 1);
+
+        Samples {
+            data: self.as_slice(),
+            offset,
+            stride,
+            remaining: layout.pixel_count,
+        }
+    }
+
+    /// Construct a new image by applying `func` to every pixel of this image.
+    ///
+    /// The returned image has the same dimensions and color space as this image, and
+    /// `channels` channels of element type `U`. The returned image is always interleaved.
+    ///
+    /// `func` is called with the channel values of an input pixel and a mutable slice of
+    /// length `channels`, initialized to zero, in which to store the channel values of
+    /// the corresponding output pixel.
+    ///
+    /// # Example
+    ///
+    /// Convert an RGB image into a grayscale image.
+    ///
+    /// ```no_run
+    /// # use wolfram_library_link::Image;
+    /// # let image: Image<f32> = todo!();
+    /// // let image: Image<f32> = ...
+    ///
+    /// let gray: Image<f32> = image.map_pixels(1, |rgb, gray| {
+    ///     gray[0] = 0.299 * rgb[0] + 0.587 * rgb[1] + 0.114 * rgb[2];
+    /// });
+    /// ```
+    ///
+    /// Use [`Image::map_pixels_with_color_space()`] to specify the color space of the
+    /// returned image, or [`Image::par_map_pixels()`] to process pixels in parallel.
+    pub fn map_pixels<U, F>(&self, channels: usize, func: F) -> Image<U>
+    where
+        U: ImageData,
+        F: FnMut(&[T::STORAGE], &mut [U::STORAGE]),
+    {
+        let space = self.default_map_color_space(channels);
+
+        self.map_pixels_with_color_space(channels, space, func)
+    }
+
+    /// Construct a new image with color space `space` by applying `func` to every
+    /// pixel of this image.
+    ///
+    /// See [`Image::map_pixels()`].
+    pub fn map_pixels_with_color_space<U, F>(
+        &self,
+        channels: usize,
+        space: ColorSpace,
+        mut func: F,
+    ) -> Image<U>
+    where
+        U: ImageData,
+        F: FnMut(&[T::STORAGE], &mut [U::STORAGE]),
+    {
+        let mut output = self.new_map_output::<U>(channels, space);
+
+        let input = self.as_slice();
+        let layout = self.layout();
+
+        map_pixel_range(
+            input,
+            layout,
+            0..layout.pixel_count,
+            output.as_zeroed_slice_mut(),
+            channels,
+            &mut func,
+        );
+
+        // Safety: Every element of `output` was initialized to zero, and then
+        //         optionally overwritten by `func`.
+        unsafe { output.assume_init() }
+    }
+
+    /// Construct a new image by applying `func` to every pixel of this image, using
+    /// multiple threads.
+    ///
+    /// This function behaves identically to [`Image::map_pixels()`], except that the
+    /// pixels of this image are divided into contiguous chunks which are processed
+    /// concurrently using [`std::thread::scope()`]. The order in which `func` is called
+    /// on each pixel is not specified.
+    pub fn par_map_pixels<U, F>(&self, channels: usize, func: F) -> Image<U>
+    where
+        U: ImageData,
+        T::STORAGE: Sync,
+        U::STORAGE: Send,
+        F: Fn(&[T::STORAGE], &mut [U::STORAGE]) + Sync,
+    {
+        let space = self.default_map_color_space(channels);
+
+        let mut output = self.new_map_output::<U>(channels, space);
+
+        let input = self.as_slice();
+        let layout = self.layout();
+
+        let threads = std::thread::available_parallelism()
+            .map(|count| count.get())
+            .unwrap_or(1);
+
+        // Round up so that at most `threads` chunks are created.
+        let chunk_pixels = std::cmp::max(1, layout.pixel_count.div_ceil(threads));
+
+        let output_data = output.as_zeroed_slice_mut();
+        let func = &func;
+
+        std::thread::scope(|scope| {
+            for (index, output_chunk) in
+                output_data.chunks_mut(chunk_pixels * channels).enumerate()
+            {
+                let start = index * chunk_pixels;
+                let end = start + output_chunk.len() / channels;
+
+                scope.spawn(move || {
+                    map_pixel_range(
+                        input,
+                        layout,
+                        start..end,
+                        output_chunk,
+                        channels,
+                        &mut |pixel, out| func(pixel, out),
+                    );
+                });
+            }
+        });
+
+        // Safety: Every element of `output` was initialized to zero, and then
+        //         optionally overwritten by `func`.
+        unsafe { output.assume_init() }
+    }
+
+    fn layout(&self) -> Layout {
+        let channels = self.channels();
+        let len = self.flattened_length();
+
+        Layout {
+            channels,
+            pixel_count: len.checked_div(channels).unwrap_or(0),
+            interleaved: self.is_interleaved(),
+        }
+    }
+
+    /// Use the color space of this image if the channel count is unchanged, otherwise
+    /// let the Wolfram Language decide.
+    fn default_map_color_space(&self, channels: usize) -> ColorSpace {
+        if channels == self.channels() {
+            self.color_space()
+        } else {
+            ColorSpace::Automatic
+        }
+    }
+
+    /// Allocate an interleaved image with the same dimensions as this image.
+    fn new_map_output<U: ImageData>(
+        &self,
+        channels: usize,
+        space: ColorSpace,
+    ) -> UninitImage<U> {
+        assert!(
+            channels > 0,
+            "Image::map_pixels: channel count must be at least 1"
+        );
+
+        let width = self.column_count();
+        let height = self.row_count();
+
+        let mut output = match self.rank() {
+            2 => UninitImage::new_2d(width, height, channels, space, true),
+            3 => UninitImage::new_3d(
+                self.slice_count(),
+                width,
+                height,
+                channels,
+                space,
+                true,
+            ),
+            rank => panic!("Image::map_pixels: image has unexpected rank: {}", rank),
+        };
+
+        output.zero();
+
+        output
+    }
+}
+
+impl<T: ImageData> UninitImage<T> {
+    /// Access the data in this image after it has been [zeroed][UninitImage::zero].
+    fn as_zeroed_slice_mut(&mut self) -> &mut [T::STORAGE] {
+        let UninitImage(raw, _) = *self;
+
+        unsafe {
+            let data = crate::rtl::MImage_getRawData(raw) as *mut T::STORAGE;
+            let len = crate::rtl::MImage_getFlattenedLength(raw);
+            let len = usize::try_from(len)
+                .expect("UninitImage flattened length overflows usize");
+
+            std::slice::from_raw_parts_mut(data, len)
+        }
+    }
+}
+
+impl Layout {
+    /// Returns the `(offset, stride)` of the samples of the pixel at `index`.
+    fn pixel(&self, index: usize) -> (usize, usize) {
+        if self.interleaved {
+            (index * self.channels, 1)
+        } else {
+            (index, self.pixel_count)
+        }
+    }
+
+    /// Returns the `(offset, stride)` of the samples of the 0-indexed `channel`.
+    fn channel(&self, channel: usize) -> (usize, usize) {
+        if self.interleaved {
+            (channel, self.channels)
+        } else {
+            (channel * self.pixel_count, 1)
+        }
+    }
+
+    fn samples<'a, S>(&self, data: &'a [S], index: usize) -> Samples<'a, S> {
+        let (offset, stride) = self.pixel(index);
+
+        Samples {
+            data,
+            offset,
+            stride,
+            remaining: self.channels,
+        }
+    }
+}
+
+/// Apply `func` to the input pixels in `range`, writing to `output`, which contains the
+/// interleaved output samples for the same pixels.
+fn map_pixel_range<S: Copy, O>(
+    input: &[S],
+    layout: Layout,
+    range: Range<usize>,
+    output: &mut [O],
+    output_channels: usize,
+    func: &mut dyn FnMut(&[S], &mut [O]),
+) {
+    let output_pixels = output.chunks_exact_mut(output_channels);
+
+    if layout.interleaved || layout.channels == 1 {
+        let input = &input[range.start * layout.channels..range.end * layout.channels];
+
+        for (pixel, out) in input.chunks_exact(layout.channels).zip(output_pixels) {
+            func(pixel, out);
+        }
+    } else {
+        // Gather the samples of each non-interleaved input pixel into a reusable buffer.
+        let mut pixel: Vec<S> = Vec::with_capacity(layout.channels);
+
+        for (index, out) in range.zip(output_pixels) {
+            pixel.clear();
+            pixel.extend(layout.samples(input, index));
+
+            func(&pixel, out);
+        }
+    }
+}
+
+//======================================
+// Trait Impls
+//======================================
+
+impl<'a, S: Copy> Iterator for Samples<'a, S> {
+    type Item = S;
+
+    fn next(&mut self) -> Option<S> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let value = self.data[self.offset];
+
+        self.remaining -= 1;
+
+        if self.remaining > 0 {
+            self.offset += self.stride;
+        }
+
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, S: Copy> ExactSizeIterator for Samples<'a, S> {}
+
+impl<'a, S: Copy> Iterator for Pixels<'a, S> {
+    type Item = Samples<'a, S>;
+
+    fn next(&mut self) -> Option<Samples<'a, S>> {
+        let index = self.range.next()?;
+
+        Some(self.layout.samples(self.data, index))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+}
+
+impl<'a, S: Copy> ExactSizeIterator for Pixels<'a, S> {}
+
+impl<'a, S: Copy> Iterator for Rows<'a, S> {
+    type Item = Pixels<'a, S>;
+
+    fn next(&mut self) -> Option<Pixels<'a, S>> {
+        let row = self.range.next()?;
+
+        let start = row * self.row_len;
+
+        Some(Pixels {
+            data: self.data,
+            layout: self.layout,
+            range: start..start + self.row_len,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+}
+
+impl<'a, S: Copy> ExactSizeIterator for Rows<'a, S> {}
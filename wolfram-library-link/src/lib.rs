@@ -218,13 +218,24 @@
 #![cfg_attr(feature = "nightly", feature(panic_info_message))]
 #![warn(missing_docs)]
 
+// Allow the `wl!` macro, which generates paths starting with
+// `::wolfram_library_link`, to be used within this crate.
+extern crate self as wolfram_library_link;
+
 mod args;
 mod async_tasks;
+mod big_number;
 mod catch_panic;
 mod data_store;
+mod evaluation;
+mod expr_match;
+mod from_expr;
 mod image;
 mod library_data;
+mod main_thread;
 mod numeric_array;
+mod to_expr;
+mod wstp_args;
 
 /// This module is *semver exempt*. This is not intended to be part of the public API of
 /// wolfram-library-link.
@@ -234,6 +245,7 @@ mod numeric_array;
 pub mod macro_utils;
 pub mod managed;
 pub mod rtl;
+pub mod wxf;
 
 pub mod docs;
 
@@ -258,17 +270,43 @@ pub use inventory;
 #[cfg(feature = "automate-function-loading-boilerplate")]
 pub use self::macro_utils::exported_library_functions_association;
 
+#[cfg(feature = "image")]
+pub use self::image::ImageConversionError;
+
 
 pub use self::{
-    args::{FromArg, IntoArg, NativeFunction, WstpFunction},
-    async_tasks::AsyncTaskObject,
-    data_store::{DataStore, DataStoreNode, DataStoreNodeValue, Nodes},
-    image::{ColorSpace, Image, ImageData, ImageType, Pixel, UninitImage},
+    args::{AsyncFunction, FromArg, IntoArg, NativeFunction, WstpFunction},
+    async_tasks::{
+        AsyncEventSender, AsyncTaskJoinHandle, AsyncTaskObject, BackpressurePolicy,
+        BoundedEventSender, CancellationSignal, EventDelivery, EventQueueStats, Progress,
+    },
+    big_number::{BigReal, ParseBigRealError, RealPrecision},
+    data_store::{
+        DataStore, DataStoreFromExprError, DataStoreNode, DataStoreNodeValue,
+        FromDataStore, FromDataStoreError, FromDataStoreErrorKind, FromDataStoreNode,
+        IntoDataStore, IntoDataStoreNode, Nodes, SparseArrayRef, TensorRef,
+    },
+    evaluation::{
+        evaluate_as, set_symbol_value, symbol_value, Evaluation, EvaluationError,
+        EvaluationOutcome, EvaluationOutput,
+    },
+    expr_match::ExprMatchError,
+    from_expr::{FromExpr, FromExprError},
+    image::{
+        ColorSpace, Image, ImageData, ImageType, Pixel, PixelPositions, Pixels, Rows,
+        Samples, UninitImage,
+    },
     library_data::{get_library_data, initialize, WolframLibraryData},
+    main_thread::{
+        run_main_thread_jobs, spawn_on_main_thread, start_main_thread_executor,
+        MainThreadJob,
+    },
     numeric_array::{
         NumericArray, NumericArrayConvertMethod, NumericArrayDataType, NumericArrayKind,
         NumericArrayType, UninitNumericArray,
     },
+    to_expr::{ToExpr, ToExprError},
+    wstp_args::{Args, ArgsError, ArgsErrorKind, FromLink, IntoLink, ResultWriter},
 };
 
 
@@ -382,6 +420,18 @@ pub use wolfram_library_link_macros::init;
 /// # }
 /// ```
 ///
+/// Convert the [`DataStore`] returned by a native function into a `List` or
+/// `Association` in the automatic [`generate_loader!`] output. See
+/// [`#[export(unpack = ...)]`](#exportunpack--).
+///
+/// ```
+/// # mod scope {
+/// # use wolfram_library_link::export;
+/// #[export(unpack = "List")]
+/// # fn min_max(x: i64, y: i64) -> (i64, i64) { (x, y) }
+/// # }
+/// ```
+///
 /// # Examples
 ///
 /// ### Primitive data types
@@ -477,6 +527,123 @@ pub use wolfram_library_link_macros::init;
 /// LibraryFunctionLoad["<library name>", "native_square", {Integer}, Integer]
 /// ```
 ///
+/// ### Export an `async fn`
+///
+/// *This requires the `futures` feature to be enabled.*
+///
+/// `#[export]` can also be used on an `async fn`. The future returned by the function
+/// is run to completion on the executor shared with
+/// [`AsyncTaskObject::spawn_future()`], and its output is used as the return value.
+///
+/// While waiting for the future, the calling thread periodically checks whether the
+/// evaluation has been [aborted][crate::aborted]. If it has, the future is dropped,
+/// cancelling any work it has not completed yet, and the function returns an error
+/// code without writing a result.
+///
+/// Because the future runs on an executor thread, it should not call LibraryLink
+/// functions that must be called from the thread the function was called on, and it
+/// should avoid blocking for long periods, which would delay other futures.
+///
+/// ```
+/// # #[cfg(feature = "futures")]
+/// # mod scope {
+/// use wolfram_library_link::export;
+/// # async fn fetch_count(_: &str) -> i64 { 0 }
+///
+/// #[export]
+/// async fn count_records(table: String) -> i64 {
+///     fetch_count(&table).await
+/// }
+/// # }
+/// ```
+///
+/// ```wolfram
+/// LibraryFunctionLoad["<library name>", "count_records", {String}, Integer]
+/// ```
+///
+/// The future must be `Send + 'static`, so every parameter must be an owned type, like
+/// [`String`] or [`NumericArray<T>`], instead of a borrowed type, like `&str` or
+/// [`&NumericArray<T>`][NumericArray]. Using a borrowed parameter type is a compile
+/// error:
+///
+/// ```compile_fail
+/// # mod scope {
+/// use wolfram_library_link::export;
+/// # async fn fetch_count(_: &str) -> i64 { 0 }
+///
+/// #[export]
+/// async fn count_records(table: &str) -> i64 {
+///     fetch_count(table).await
+/// }
+/// # }
+/// ```
+///
+/// ### Return multiple values
+///
+/// A native function can return a tuple, whose elements are stored as the unnamed
+/// nodes of a [`DataStore`]:
+///
+/// ```
+/// # mod scope {
+/// # use wolfram_library_link::export;
+/// #[export]
+/// fn min_max(a: i64, b: i64) -> (i64, i64) {
+///     (a.min(b), a.max(b))
+/// }
+/// # }
+/// ```
+///
+/// ```wolfram
+/// minMax = LibraryFunctionLoad["...", "min_max", {Integer, Integer}, "DataStore"];
+///
+/// minMax[5, 2]    (* Returns Developer`DataStore[2, 5] *)
+/// ```
+///
+/// Structs can be returned the same way using
+/// [`#[derive(IntoArg)]`][macro@IntoArg] with the `#[arg(data_store)]` attribute.
+///
+/// ### `#[export(unpack = ...)]`
+///
+/// Functions that return a [`DataStore`] can use the `unpack = "List"` or
+/// `unpack = "Association"` argument to have the function loaded by
+/// [`generate_loader!`] convert the returned data store, and any data stores nested
+/// inside it:
+///
+/// * `unpack = "List"` converts every data store into a `List`. Named nodes become
+///   rules in the list.
+/// * `unpack = "Association"` converts data stores whose nodes are all named into an
+///   `Association`, and any other data store into a `List`.
+///
+/// ```
+/// # mod scope {
+/// # use wolfram_library_link::export;
+/// #[export(unpack = "List")]
+/// fn min_max(a: i64, b: i64) -> (i64, i64) {
+///     (a.min(b), a.max(b))
+/// }
+/// # }
+/// ```
+///
+/// ```wolfram
+/// $functions["min_max"][5, 2]    (* Returns {2, 5} *)
+/// ```
+///
+/// Using `unpack` on a function that does not return a data store is a compile error:
+///
+/// ```compile_fail
+/// # mod scope {
+/// # use wolfram_library_link::export;
+/// #[export(unpack = "List")]
+/// fn min(a: i64, b: i64) -> i64 {
+///     a.min(b)
+/// }
+/// # }
+/// ```
+///
+/// The conversion is performed by Wolfram Language code that wraps the loaded
+/// `LibraryFunction`, so it has no effect on functions loaded using a manually written
+/// `LibraryFunctionLoad` call.
+///
 ///
 // TODO: Add a "Memory Management" section to this comment and discuss "Constant".
 //
@@ -550,6 +717,7 @@ pub use wolfram_library_link_macros::init;
 /// [`NumericArray`]                   | `LibraryDataType[NumericArray]`
 /// [`NumericArray<T>`]                | `LibraryDataType[NumericArray, `[`"..."`][ref/NumericArray][^1]`]`
 /// [`DataStore`]                      | `"DataStore"`
+/// `(A, B, ...)`                      | `"DataStore"`
 ///
 /// [^1]: The Details and Options section of the Wolfram Language
 ///       [`NumericArray` reference page][ref/NumericArray] lists the available element
@@ -578,6 +746,18 @@ pub use wolfram_library_link_macros::init;
 ///
 /// [ref/Failure]: https://reference.wolfram.com/language/ref/Failure.html
 ///
+/// A WSTP function can also return `impl ExactSizeIterator<Item = T>`, where `T`
+/// implements [`IntoLink`]. The elements produced by the iterator are written to the link
+/// one at a time as a `List`, without first collecting them into an [`Expr`]. The return
+/// type must be written as an `impl ExactSizeIterator<..>` type for this to be detected.
+/// Other `impl ..Iterator` return types, like `impl Iterator<Item = T>`, are rejected at
+/// compile time, because the length of the `List` must be known before it is written.
+///
+/// If the iterator panics, or an element cannot be written to the link, after some
+/// elements have been written, the element that was being produced is replaced by a
+/// `Failure[...]` expression, and any remaining elements by `Nothing`, so that the link
+/// is left in a valid state.
+///
 /// # Syntax
 ///
 /// Export a LibraryLink WSTP function.
@@ -659,8 +839,494 @@ pub use wolfram_library_link_macros::init;
 /// ```wolfram
 /// LibraryFunctionLoad["...", "total_args_i64", LinkObject, LinkObject]
 /// ```
+///
+/// ##### WSTP function that streams a table of rows as its result:
+///
+/// ```
+/// # mod scope {
+/// use wolfram_library_link::{export, expr::Expr};
+///
+/// #[export(wstp)]
+/// fn powers_table(_args: Vec<Expr>) -> impl ExactSizeIterator<Item = (i32, i32, i32)> {
+///     (1..1001).map(|n: i32| (n, n * n, n * n * n))
+/// }
+/// # }
+/// ```
+///
+/// ```wolfram
+/// LibraryFunctionLoad["...", "powers_table", LinkObject, LinkObject]
+/// ```
+///
+/// Returning an iterator whose length is not known is a compile error:
+///
+/// ```compile_fail
+/// # mod scope {
+/// use wolfram_library_link::{export, expr::Expr};
+///
+/// #[export(wstp)]
+/// fn powers(_args: Vec<Expr>) -> impl Iterator<Item = i32> {
+///     (1..).map(|n: i32| n * n).take_while(|&n| n < 1000)
+/// }
+/// # }
+/// ```
+///
+///
+///
+/// <br/><br/><br/>
+///
+/// # `#[export(async)]`
+///
+/// Export a native *LibraryLink* function that starts a new Wolfram Language
+/// asynchronous task, which runs the specified function on a background thread.
+///
+/// To be exported by this macro, the specified function must implement
+/// [`AsyncFunction`]. Its first parameter is the [`AsyncTaskObject`] for the new task,
+/// and its remaining parameters are the arguments passed from the Wolfram Language.
+/// These parameters must be owned types, like [`mint`], [`String`] or
+/// [`DataStore`], because they are moved to the background thread.
+///
+/// The exported *LibraryLink* function returns the ID of the new task. It is intended
+/// to be called using `` Internal`CreateAsynchronousTask ``.
+///
+/// `#[export(async_task)]` is accepted as an alias of `#[export(async)]`, for code that
+/// wants to make the difference from exporting an `async fn` explicit.
+///
+/// Functions exported using this macro will automatically:
+///
+/// * Call [`initialize()`][crate::initialize] to initialize this library.
+/// * Catch any panics that occur, either while starting the task or on the background
+///   thread.
+///
+/// # Syntax
+///
+/// Export a function that starts an asynchronous task.
+///
+/// ```
+/// # mod scope {
+/// # use wolfram_library_link::{export, AsyncTaskObject};
+/// #[export(async)]
+/// # fn count_down(task: AsyncTaskObject, count: i64) {}
+/// # }
+/// ```
+///
+/// # Example
+///
+/// ##### Asynchronous task that raises an event for each of the numbers 1 through `n`:
+///
+/// ```
+/// # mod scope {
+/// use wolfram_library_link::{export, AsyncTaskObject, DataStore};
+///
+/// #[export(async)]
+/// fn count_to(task: AsyncTaskObject, n: i64) {
+///     for i in 1..=n {
+///         let mut data = DataStore::new();
+///         data.add_i64(i);
+///
+///         task.raise_async_event("count", data);
+///     }
+/// }
+/// # }
+/// ```
+///
+/// ```wolfram
+/// Internal`CreateAsynchronousTask[
+///     LibraryFunctionLoad["...", "count_to", {Integer}, Integer],
+///     {10},
+///     Print
+/// ]
+/// ```
+///
+/// When loaded using [`generate_loader!`], an `#[export(async)]` function is loaded
+/// as a Wolfram Language function that takes the task arguments followed by the event
+/// handler, and returns an [`AsynchronousTaskObject`][ref/AsynchronousTaskObject]<sub>WL</sub>:
+///
+/// ```wolfram
+/// task = $functions["count_to"][10, Print]
+/// ```
+///
+/// [ref/AsynchronousTaskObject]: https://reference.wolfram.com/language/ref/AsynchronousTaskObject.html
 pub use wolfram_library_link_macros::export;
 
+/// Derive an implementation of [`IntoDataStore`] for a struct.
+///
+/// Each field of a struct with named fields is stored as a named node, using the field
+/// name as the node name. Each field of a tuple struct is stored as an unnamed node.
+///
+/// Every field type must implement [`IntoDataStoreNode`]. Structs that derive
+/// `IntoDataStore` also implement `IntoDataStoreNode`, and are stored as a nested
+/// `DataStore`.
+///
+/// # Example
+///
+/// ```no_run
+/// # mod scope {
+/// use wolfram_library_link::{self as wll, DataStore, IntoDataStore, NumericArray};
+///
+/// #[derive(IntoDataStore)]
+/// struct Measurement {
+///     label: String,
+///     values: NumericArray<f64>,
+///     range: Range,
+/// }
+///
+/// #[derive(IntoDataStore)]
+/// struct Range(f64, f64);
+///
+/// #[wll::export]
+/// fn measure() -> DataStore {
+///     let measurement = Measurement {
+///         label: "temperature".to_owned(),
+///         values: NumericArray::from_slice(&[20.5, 21.0, 19.5]),
+///         range: Range(19.5, 21.0),
+///     };
+///
+///     measurement.into_data_store()
+/// }
+/// # }
+/// ```
+///
+/// The `DataStore` returned by `measure()` has the following representation in the
+/// Wolfram Language:
+///
+/// ```wolfram
+/// Developer`DataStore[
+///     "label" -> "temperature",
+///     "values" -> NumericArray[{20.5, 21., 19.5}, "Real64"],
+///     "range" -> Developer`DataStore[19.5, 21.]
+/// ]
+/// ```
+pub use wolfram_library_link_macros::IntoDataStore;
+
+/// Derive an implementation of [`FromDataStore`] for a struct.
+///
+/// Each field of a struct with named fields is read from the node with the same name.
+/// The order of the nodes does not matter, and nodes that do not correspond to a field
+/// are ignored. Each field of a tuple struct is read from the node at the same position,
+/// and the data store must contain exactly as many nodes as the struct has fields.
+///
+/// Every field type must implement [`FromDataStoreNode`]. Structs that derive
+/// `FromDataStore` also implement `FromDataStoreNode`, and are read from a nested
+/// `DataStore`.
+///
+/// A [`FromDataStoreError`] describing the location of the problem is returned if a
+/// node is missing or has the wrong type.
+///
+/// # Example
+///
+/// ```no_run
+/// # mod scope {
+/// use wolfram_library_link::{self as wll, DataStore, FromDataStore};
+///
+/// #[derive(FromDataStore)]
+/// struct Options {
+///     path: String,
+///     retries: i64,
+/// }
+///
+/// #[wll::export]
+/// fn describe_options(options: DataStore) -> String {
+///     match Options::from_data_store(&options) {
+///         Ok(Options { path, retries }) => format!("{} ({} retries)", path, retries),
+///         Err(err) => format!("invalid options: {}", err),
+///     }
+/// }
+/// # }
+/// ```
+pub use wolfram_library_link_macros::FromDataStore;
+
+/// Derive an implementation of [`FromArg`] for a fieldless enum or a newtype struct.
+///
+/// A struct with exactly one field is passed as the type of that field, so the field
+/// type must implement `FromArg`.
+///
+/// An enum whose variants have no fields is passed as a `String` containing the variant
+/// name. A different name can be used for a variant with `#[arg(name = "...")]`. If the
+/// enum has the `#[arg(integer)]` attribute, it is instead passed as an `Integer` equal
+/// to the variant discriminant.
+///
+/// If the argument is not the value of any variant, the function is not called, and it
+/// returns a `LIBRARY_TYPE_ERROR` error code to the Kernel.
+///
+/// The [`parameter_type()`][FromArg::parameter_type] used by
+/// [`generate_loader!`] is `String`, `Integer`, or the parameter type of the newtype
+/// field, respectively.
+///
+/// # Example
+///
+/// ```no_run
+/// # mod scope {
+/// use wolfram_library_link::{self as wll, FromArg, IntoArg};
+///
+/// #[derive(FromArg)]
+/// enum Interpolation {
+///     Linear,
+///     Cubic,
+///     #[arg(name = "Nearest")]
+///     NearestNeighbor,
+/// }
+///
+/// #[derive(FromArg, IntoArg)]
+/// struct Meters(f64);
+///
+/// #[wll::export]
+/// fn scaled_length(length: Meters, method: Interpolation) -> Meters {
+///     match method {
+///         Interpolation::Linear => Meters(length.0 * 2.0),
+///         Interpolation::Cubic => Meters(length.0 * 3.0),
+///         Interpolation::NearestNeighbor => Meters(length.0.round()),
+///     }
+/// }
+/// # }
+/// ```
+///
+/// ```wolfram
+/// scaledLength = LibraryFunctionLoad["...", "scaled_length", {Real, String}, Real];
+///
+/// scaledLength[1.5, "Cubic"]      (* Returns 4.5 *)
+/// scaledLength[1.5, "Quadratic"]  (* Fails with a type error *)
+/// ```
+pub use wolfram_library_link_macros::FromArg;
+
+/// Derive an implementation of [`IntoArg`] for a fieldless enum or a newtype struct.
+///
+/// A struct with exactly one field is returned as the value of that field, so the field
+/// type must implement `IntoArg`.
+///
+/// An enum whose variants have no fields is returned as a `String` containing the
+/// variant name, or the name specified by `#[arg(name = "...")]`. If the enum has the
+/// `#[arg(integer)]` attribute, it is instead returned as an `Integer` equal to the
+/// variant discriminant.
+///
+/// A struct with the `#[arg(data_store)]` attribute is returned as the [`DataStore`]
+/// constructed by its [`IntoDataStore`] implementation, so it can have any number of
+/// fields. Use [`#[export(unpack = "Association")]`][export#exportunpack--] to have the
+/// generated loader convert the returned data store into an `Association`.
+///
+/// See [`FromArg`][macro@FromArg] for an example.
+///
+/// # Example
+///
+/// ```no_run
+/// # mod scope {
+/// use wolfram_library_link::{self as wll, IntoArg, IntoDataStore};
+///
+/// #[derive(IntoDataStore, IntoArg)]
+/// #[arg(data_store)]
+/// struct Bounds {
+///     min: f64,
+///     max: f64,
+/// }
+///
+/// #[wll::export(unpack = "Association")]
+/// fn bounds(a: f64, b: f64) -> Bounds {
+///     Bounds {
+///         min: a.min(b),
+///         max: a.max(b),
+///     }
+/// }
+/// # }
+/// ```
+///
+/// ```wolfram
+/// $functions["bounds"][3.0, 1.0]    (* Returns <|"min" -> 1., "max" -> 3.|> *)
+/// ```
+pub use wolfram_library_link_macros::IntoArg;
+
+/// Construct an [`Expr`] from Wolfram Language syntax.
+///
+/// `wl!` parses its input at compile time, and expands to code that constructs the
+/// equivalent [`Expr`] value.
+///
+/// ```
+/// use wolfram_library_link::{
+///     expr::{Expr, Symbol},
+///     wl,
+/// };
+///
+/// let expr = wl!(Plot[Sin[x], {x, 0, 2 * Pi}]);
+///
+/// assert_eq!(expr, Expr::normal(Symbol::new("System`Plot"), vec![
+///     Expr::normal(Symbol::new("System`Sin"), vec![Expr::from(Symbol::new("Global`x"))]),
+///     Expr::normal(Symbol::new("System`List"), vec![
+///         Expr::from(Symbol::new("Global`x")),
+///         Expr::from(0),
+///         Expr::normal(Symbol::new("System`Times"), vec![
+///             Expr::from(2),
+///             Expr::from(Symbol::new("System`Pi")),
+///         ]),
+///     ]),
+/// ]));
+/// ```
+///
+/// # Symbols
+///
+/// The input to `wl!` is split into tokens by the Rust compiler, so the backtick
+/// character cannot be used to write a symbol context. Instead, contexts are separated
+/// using `::`:
+///
+/// | Rust                          | Wolfram Language                   |
+/// |-------------------------------|------------------------------------|
+/// | `Global::x`                   | ``Global`x``                       |
+/// | `Developer::DataStore[1, 2]`  | ``Developer`DataStore[1, 2]``      |
+/// | `$Context`                    | ``System`$Context``                |
+///
+/// Symbols written without a context are resolved using the naming convention of the
+/// Wolfram Language: names that begin with a lowercase letter are in the ``Global` ``
+/// context, and the names of commonly used ``System` `` symbols, like `List`, `Plot`, or
+/// `$Version`, are in the ``System` `` context. Any other name that begins with an
+/// uppercase letter or `$` is a compile error, instead of being silently created in
+/// ``System` ``. Write the context explicitly for these symbols, e.g.
+/// `Global::MyFunction`, `MyPackage::Helper`, or `System::ImageResize`.
+///
+/// # Interpolation
+///
+/// `#name` inserts the value of the Rust variable `name`, and `#(...)` inserts the value
+/// of an arbitrary Rust expression. Interpolated values are converted using
+/// [`Expr::from()`].
+///
+/// ```
+/// use wolfram_library_link::{expr::Expr, wl};
+///
+/// let name = "count";
+/// let values = Expr::list(vec![Expr::from(1), Expr::from(2)]);
+/// let offset: i64 = 10;
+///
+/// let expr = wl!(#name -> Total[#values] + #(offset * 2));
+///
+/// assert_eq!(expr, wl!("count" -> Total[{1, 2}] + 20));
+/// ```
+///
+/// `#` followed by an integer, or by nothing, is a [`Slot`][ref/Slot]<sub>WL</sub> as
+/// in the Wolfram Language, and `##` is a
+/// [`SlotSequence`][ref/SlotSequence]<sub>WL</sub>:
+///
+/// ```
+/// use wolfram_library_link::wl;
+///
+/// assert_eq!(wl!(# + 1 &), wl!(Function[Slot[1] + 1]));
+/// assert_eq!(wl!(f[##2] &), wl!(Function[f[SlotSequence[2]]]));
+/// ```
+///
+/// # Supported syntax
+///
+/// * Integers, reals, and strings, using Rust literal syntax.
+/// * Function application `f[x, y]`, `f @ x`, `f /@ list`, and `f @@ expr`.
+/// * Lists `{a, b}`, associations `<|a -> 1, b -> 2|>`, and parts `expr[[1, 2]]`.
+/// * Arithmetic: `+`, `-`, `*`, `/`, and `^`.
+/// * String concatenation `a <> b`.
+/// * Comparisons: `==`, `!=`, `===`, `=!=`, `<`, `>`, `<=`, and `>=`.
+/// * Logical operators: `&&`, `||`, and `!`.
+/// * Rules and assignments: `->`, `:>`, `=`, and `:=`.
+/// * Patterns `x_`, `x__`, `x___`, and `x_Integer`, and alternatives `a | b`.
+/// * Pure functions `body &`, and compound expressions `a; b`.
+/// * Grouping using parentheses. As in the Wolfram Language, a parenthesized operand is
+///   not flattened, so `(a + b) + c` is `Plus[Plus[a, b], c]`.
+///
+/// `//` cannot be used, because it begins a Rust comment.
+///
+/// [ref/Slot]: https://reference.wolfram.com/language/ref/Slot.html
+/// [ref/SlotSequence]: https://reference.wolfram.com/language/ref/SlotSequence.html
+pub use wolfram_library_link_macros::wl;
+
+/// Match an [`Expr`] against Wolfram Language patterns, binding the matched parts to
+/// Rust variables.
+///
+/// ```text
+/// expr_match!(EXPR, {
+///     PATTERN => BODY,
+///     ...
+/// })
+/// ```
+///
+/// The patterns are tried in order, and the body of the first pattern that matches is
+/// evaluated. `expr_match!` evaluates to `Ok(body)`, or to an [`ExprMatchError`]
+/// describing the expression and the patterns if none of the patterns matched. `EXPR`
+/// must be a reference to an [`Expr`], and the matched parts borrow from it.
+///
+/// ```
+/// use wolfram_library_link::{expr::Expr, expr_match, wl};
+///
+/// fn describe(expr: &Expr) -> String {
+///     let result = expr_match!(expr, {
+///         {x_Integer, s_String, opts___Rule} => {
+///             format!("{} and {:?} with {} options", x, s, opts.len())
+///         },
+///         Point[{x_Real, y_Real}] => format!("point at ({}, {})", x, y),
+///         Missing[reason_String] => format!("missing: {}", reason),
+///     });
+///
+///     result.unwrap_or_else(|err| format!("no match for: {}", err.expr()))
+/// }
+///
+/// assert_eq!(describe(&wl!({1, "two", a -> 3})), "1 and \"two\" with 1 options");
+/// assert_eq!(describe(&wl!(Point[{1.5, 2.0}])), "point at (1.5, 2)");
+/// assert_eq!(describe(&wl!(Missing["NotFound"])), "missing: NotFound");
+/// ```
+///
+/// The error lists the patterns that were tried, using Wolfram Language syntax:
+///
+/// ```
+/// use wolfram_library_link::{expr_match, wl};
+///
+/// let err = expr_match!(&wl!(Point[{1, 2}]), {
+///     Point[{x_Real, y_Real}] => (x, y),
+/// })
+/// .unwrap_err();
+///
+/// assert_eq!(err.patterns(), ["Point[{x_Real, y_Real}]"]);
+/// assert_eq!(err.expr(), &wl!(Point[{1, 2}]));
+/// ```
+///
+/// # Patterns
+///
+/// Patterns are written using the same syntax as [`wl!`], and symbols are resolved
+/// using the same rules. The following forms are supported:
+///
+/// | Pattern                  | Matches                                | Binds           |
+/// |--------------------------|----------------------------------------|-----------------|
+/// | `x_`                     | any expression                         | `x: &Expr`      |
+/// | `x_Integer`              | an integer                             | `x: i64`        |
+/// | `x_Real`                 | a real number                          | `x: f64`        |
+/// | `x_String`               | a string                               | `x: &str`       |
+/// | `x_Symbol`               | a symbol                               | `x: &Symbol`    |
+/// | `x_Head`                 | a normal expression with head `Head`   | `x: &Expr`      |
+/// | `x__`, `x___Integer`, …  | a sequence of elements                 | `x: Vec<_>`     |
+/// | `f[p1, p2]`, `{p1, p2}`  | a normal expression with those parts   |                 |
+/// | `Null`, `1`, `"text"`, … | an equal symbol, number, or string     |                 |
+/// | `#name`, `#(...)`        | an expression equal to `Expr::from(…)` |                 |
+///
+/// The pattern name may be omitted (e.g. `_Integer` or `___`) to match without binding
+/// a variable. `x__` matches one or more elements, and `x___` matches zero or more;
+/// at most one sequence pattern may be used in each list of elements. Each element of
+/// a sequence is converted as described above, so `x__Integer` binds a `Vec<i64>`.
+///
+/// `_Real` matches only real numbers; it does not match integers. Use
+/// [`FromExpr`] on an `x_` binding to accept either.
+///
+/// # Example
+///
+/// Destructure the arguments of a WSTP function:
+///
+/// ```no_run
+/// # mod scope {
+/// use wolfram_library_link::{self as wll, expr::Expr, expr_match};
+///
+/// #[wll::export(wstp)]
+/// fn scale_points(args: Vec<Expr>) -> Expr {
+///     expr_match!(&Expr::list(args), {
+///         {factor_Real, points___List} => {
+///             Expr::list(points.into_iter().map(|point| {
+///                 wll::wl!(#(Expr::real(factor)) * #(point.clone()))
+///             }).collect())
+///         },
+///     })
+///     .unwrap_or_else(|err| panic!("{}", err))
+/// }
+/// # }
+/// ```
+pub use wolfram_library_link_macros::expr_match;
+
 const BACKTRACE_ENV_VAR: &str = "LIBRARY_LINK_RUST_BACKTRACE";
 
 //======================================
@@ -669,8 +1335,14 @@ const BACKTRACE_ENV_VAR: &str = "LIBRARY_LINK_RUST_BACKTRACE";
 
 /// Evaluate `expr` by calling back into the Wolfram Kernel.
 ///
+/// # Panics
+///
+/// This function will panic if it is not called from the main Kernel thread. Use
+/// [`spawn_on_main_thread()`] to evaluate an expression from a background thread.
+///
 /// TODO: Specify and document what happens if the evaluation of `expr` triggers a
-///       kernel abort (such as a `Throw[]` in the code).
+///       kernel abort (such as a `Throw[]` in the code). Use [`Evaluation::run()`] to
+///       capture aborts and uncaught throws explicitly.
 pub fn evaluate(expr: &Expr) -> Expr {
     match try_evaluate(expr) {
         Ok(returned) => returned,
@@ -687,10 +1359,7 @@ pub fn try_evaluate(expr: &Expr) -> Result<Expr, String> {
     with_link(|link: &mut Link| {
         // Send an EvaluatePacket['expr].
         let _: () = link
-            // .put_expr(&Expr! { EvaluatePacket['expr] })
-            .put_expr(&Expr::normal(Symbol::new("System`EvaluatePacket"), vec![
-                expr.clone(),
-            ]))
+            .put_expr(&wl!(EvaluatePacket[#(expr.clone())]))
             .map_err(|e| e.to_string())?;
 
         let _: () = process_wstp_link(link)?;
@@ -1,14 +1,21 @@
-use std::os::raw::c_int;
+use std::{collections::HashMap, fmt, os::raw::c_int};
 
 use wstp::{self, Link};
 
 use crate::{
     catch_panic::{call_and_catch_panic, CaughtPanic},
-    expr::{Expr, Symbol},
+    expr::Expr,
     sys::{self, MArgument, LIBRARY_NO_ERROR},
-    NativeFunction, WstpFunction,
+    wl, AsyncFunction, DataStore, DataStoreNode, DataStoreNodeValue, ExprMatchError,
+    FromDataStoreError, FromDataStoreErrorKind, FromDataStoreNode, NativeFunction,
+    WstpFunction,
 };
 
+pub use crate::args::StreamList;
+
+#[cfg(feature = "futures")]
+pub use crate::args::BlockOn;
+
 /// Error codes returned by macro-generated wrapper code.
 ///
 /// If no error occured, [`sys::LIBRARY_NO_ERROR`] is returned.
@@ -32,6 +39,28 @@ mod error_code {
     //
     // TODO: Wherever this code is set, also set a $LastError-like variable.
     pub const FAILED_WITH_PANIC: c_int = OFFSET + 2;
+
+    /// The evaluation was aborted before the function wrote its return value.
+    pub const ABORTED: c_int = OFFSET + 3;
+}
+
+/// Error returned by [`NativeFunction::try_call()`] when the function did not write a
+/// return value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NativeCallError {
+    /// The evaluation was aborted before the function completed.
+    Aborted,
+    /// An argument had a value that is not valid for its Rust type, so the function was
+    /// not called.
+    ArgType(ArgTypeError),
+}
+
+/// Error returned by [`FromArg::try_from_arg()`][crate::FromArg::try_from_arg] when an
+/// argument has a value that is not valid for the Rust type it is converted into.
+#[derive(Debug, Clone, PartialEq)]
+#[doc(hidden)]
+pub struct ArgTypeError {
+    message: String,
 }
 
 //==================
@@ -150,13 +179,16 @@ pub unsafe fn call_native_wolfram_library_function<'a, F: NativeFunction<'a>>(
     //        E.g. `fn foo(link: &'static mut str) { ... }`
     let args: &[MArgument] = std::slice::from_raw_parts(args, argc);
 
-    if call_and_catch_panic(AssertUnwindSafe(move || func.call(args, res))).is_err() {
-        // TODO: Store the panic into a "LAST_ERROR" static, and provide an accessor to
-        //       get it from WL? E.g. RustLink`GetLastError[<optional func name>].
-        return error_code::FAILED_WITH_PANIC;
-    };
-
-    sys::LIBRARY_NO_ERROR as c_int
+    match call_and_catch_panic(AssertUnwindSafe(move || func.try_call(args, res))) {
+        Ok(Ok(())) => sys::LIBRARY_NO_ERROR as c_int,
+        Ok(Err(NativeCallError::Aborted)) => error_code::ABORTED,
+        Ok(Err(NativeCallError::ArgType(_))) => sys::LIBRARY_TYPE_ERROR as c_int,
+        Err(_) => {
+            // TODO: Store the panic into a "LAST_ERROR" static, and provide an accessor
+            //       to get it from WL? E.g. RustLink`GetLastError[<optional func name>].
+            error_code::FAILED_WITH_PANIC
+        },
+    }
 }
 
 pub unsafe fn call_wstp_wolfram_library_function<
@@ -175,10 +207,51 @@ pub unsafe fn call_wstp_wolfram_library_function<
     )
 }
 
+pub unsafe fn call_async_wolfram_library_function<F: AsyncFunction>(
+    lib_data: sys::WolframLibraryData,
+    args: *mut MArgument,
+    argc: sys::mint,
+    res: MArgument,
+    func: F,
+) -> c_int {
+    /// Adapts an [`AsyncFunction`] to the [`NativeFunction`] interface, so that the
+    /// same initialization and panic handling logic is used.
+    struct StartAsyncTask<F>(F);
+
+    impl<'a, F: AsyncFunction> NativeFunction<'a> for StartAsyncTask<F> {
+        unsafe fn call(&self, args: &'a [MArgument], ret: MArgument) {
+            let StartAsyncTask(ref func) = *self;
+
+            func.call(args, ret)
+        }
+
+        unsafe fn try_call(
+            &self,
+            args: &'a [MArgument],
+            ret: MArgument,
+        ) -> Result<(), NativeCallError> {
+            let StartAsyncTask(ref func) = *self;
+
+            func.try_call(args, ret).map_err(NativeCallError::ArgType)
+        }
+
+        fn signature(&self) -> Result<(Vec<Expr>, Expr), String> {
+            let StartAsyncTask(ref func) = *self;
+
+            func.signature()
+        }
+    }
+
+    call_native_wolfram_library_function(lib_data, args, argc, res, StartAsyncTask(func))
+}
+
 //======================================
 // Automatic Loader
 //======================================
 
+/// Returns the parameter and return types of an exported function.
+type SignatureFn = fn() -> Result<(Vec<Expr>, Expr), String>;
+
 pub enum LibraryLinkFunction {
     Native {
         name: &'static str,
@@ -211,12 +284,44 @@ pub enum LibraryLinkFunction {
         /// function type is still available) to avoid trying and failing to box up or
         /// return the `NativeFunction` trait object.
         signature: fn() -> Result<(Vec<Expr>, Expr), String>,
+        /// Set by `#[export(unpack = "...")]`.
+        unpack: Option<Unpack>,
     },
     Wstp {
         name: &'static str,
     },
+    /// A function exported using `#[export(async)]`, which starts an asynchronous
+    /// task.
+    ///
+    /// See [`LibraryLinkFunction::Native::signature`] for an explanation of the type of
+    /// the `signature` field.
+    Async {
+        name: &'static str,
+        signature: SignatureFn,
+    },
 }
 
+/// How the automatic loader converts the `DataStore` returned by a native function.
+pub enum Unpack {
+    /// `#[export(unpack = "List")]`: convert every data store into a `List`, keeping
+    /// any node names as rules.
+    List,
+    /// `#[export(unpack = "Association")]`: convert data stores whose nodes are all
+    /// named into an `Association`, and any other data store into a `List`.
+    Association,
+}
+
+/// Marker trait implemented for the return types that are passed as a [`DataStore`]:
+/// `DataStore` itself, tuples, and structs that derive `IntoArg` with the
+/// `#[arg(data_store)]` attribute.
+pub trait ReturnsDataStore {}
+
+impl ReturnsDataStore for DataStore {}
+
+/// Used by `#[export(unpack = "...")]` to check at compile time that the exported
+/// function returns a [`DataStore`].
+pub fn assert_data_store_return<R: ReturnsDataStore>() {}
+
 #[cfg(feature = "automate-function-loading-boilerplate")]
 inventory::collect!(LibraryLinkFunction);
 
@@ -443,6 +548,8 @@ pub unsafe fn load_library_functions_impl(
 pub fn exported_library_functions_association(
     library: Option<std::path::PathBuf>,
 ) -> Expr {
+    use crate::expr::Symbol;
+
     let library: std::path::PathBuf = library.unwrap_or_else(|| {
         process_path::get_dylib_path()
             .expect("unable to automatically determine Rust LibraryLink dynamic library file path. Suggestion: pass the library name or path to exported_library_functions_association(..)")
@@ -475,85 +582,67 @@ impl LibraryLinkFunction {
         match self {
             LibraryLinkFunction::Native { name, .. } => name,
             LibraryLinkFunction::Wstp { name } => name,
+            LibraryLinkFunction::Async { name, .. } => name,
         }
     }
 
     fn loading_code(&self, library: &std::path::PathBuf) -> Result<Expr, String> {
-        fn sys(name: &str) -> Symbol {
-            Symbol::new(&format!("System`{}", name))
-        }
-
-        let lib_func_load = sys("LibraryFunctionLoad");
-        let link_object = Expr::from(sys("LinkObject"));
-        let library = Expr::string(
-            library
-                .to_str()
-                .expect("unable to convert library file path to str"),
-        );
+        let library = library
+            .to_str()
+            .expect("unable to convert library file path to str");
 
         let code = match self {
-            LibraryLinkFunction::Native { name, signature } => {
+            LibraryLinkFunction::Native {
+                name,
+                signature,
+                unpack,
+            } => {
                 let (args, ret) = signature()?;
+                let args = Expr::list(args);
 
-                Expr::normal(&lib_func_load, vec![
-                    library.clone(),
-                    Expr::string(*name),
-                    Expr::normal(sys("List"), args),
-                    ret,
-                ])
+                let load_call = wl!(LibraryFunctionLoad[#library, #(*name), #args, #ret]);
+
+                match unpack {
+                    Some(unpack) => unpack.wrap(load_call),
+                    None => load_call,
+                }
             },
-            /*
-                With[{
-                    var = LibraryFunctionLoad[...]
-                },
+            LibraryLinkFunction::Wstp { name } => {
+                let load_call =
+                    wl!(LibraryFunctionLoad[#library, #(*name), LinkObject, LinkObject]);
+
+                wl!(With[{RustLink::Private::wstpFunc = #load_call},
                     Function[
-                        (* Note:
-                            Set $Context and $ContextPath to force symbols sent across
-                            the LinkObject to contain the symbol context explicitly.
-                        *)
+                        // Note:
+                        //  Set $Context and $ContextPath to force symbols sent across
+                        //  the LinkObject to contain the symbol context explicitly.
                         Block[{$Context = "RustLinkWSTPPrivateContext`", $ContextPath = {}},
-                            var[##]
+                            RustLink::Private::wstpFunc[##]
+                        ]
+                    ]
+                ])
+            },
+            LibraryLinkFunction::Async { name, signature } => {
+                let (args, ret) = signature()?;
+                let arg_count = args.len();
+                let args = Expr::list(args);
+
+                let load_call = wl!(LibraryFunctionLoad[#library, #(*name), #args, #ret]);
+
+                // The task arguments are the first `arg_count` arguments, and the event
+                // handler is the last argument.
+                let slot = |index: usize| wl!(Slot[#(index as i64)]);
+                let task_args = Expr::list((1..=arg_count).map(slot).collect());
+                let handler = slot(arg_count + 1);
+
+                wl!(With[{RustLink::Private::asyncFunc = #load_call},
+                    Function[
+                        Internal::CreateAsynchronousTask[
+                            RustLink::Private::asyncFunc,
+                            #task_args,
+                            #handler
                         ]
                     ]
-                ]
-            */
-            LibraryLinkFunction::Wstp { name } => {
-                let load_call = Expr::normal(&lib_func_load, vec![
-                    library.clone(),
-                    Expr::string(*name),
-                    link_object.clone(),
-                    link_object,
-                ]);
-
-                let var = Expr::from(Symbol::new("RustLink`Private`wstpFunc"));
-
-                Expr::normal(sys("With"), vec![
-                    Expr::normal(sys("List"), vec![Expr::normal(sys("Set"), vec![
-                        var.clone(),
-                        load_call,
-                    ])]),
-                    Expr::normal(sys("Function"), vec![Expr::normal(
-                        sys("Block"),
-                        vec![
-                            Expr::normal(sys("List"), vec![
-                                // $Context = "RustLinkWSTPPrivateContext`"
-                                Expr::normal(sys("Set"), vec![
-                                    Expr::from(sys("$Context")),
-                                    Expr::string("RustLinkWSTPPrivateContext`"),
-                                ]),
-                                // $ContextPath = {}
-                                Expr::normal(sys("Set"), vec![
-                                    Expr::from(sys("$ContextPath")),
-                                    Expr::normal(sys("List"), vec![]),
-                                ]),
-                            ]),
-                            // var[##]
-                            Expr::normal(var, vec![Expr::normal(
-                                sys("SlotSequence"),
-                                vec![Expr::from(1)],
-                            )]),
-                        ],
-                    )]),
                 ])
             },
         };
@@ -562,6 +651,39 @@ impl LibraryLinkFunction {
     }
 }
 
+#[cfg_attr(
+    not(feature = "automate-function-loading-boilerplate"),
+    allow(dead_code)
+)]
+impl Unpack {
+    /// Wrap `load_call` in a function that calls the loaded function and converts the
+    /// returned data store, including any nested data stores.
+    ///
+    /// Errors like `LibraryFunctionError[..]` are returned unchanged.
+    fn wrap(&self, load_call: Expr) -> Expr {
+        let nodes = wl!(Pattern[RustLink::Private::nodes, BlankNullSequence[]]);
+        let named_nodes = wl!(Pattern[RustLink::Private::nodes, BlankNullSequence[Rule]]);
+
+        let to_list = wl!(Developer::DataStore[#nodes] :> List[RustLink::Private::nodes]);
+
+        let rules = match self {
+            Unpack::List => wl!({#to_list}),
+            Unpack::Association => wl!({
+                Developer::DataStore[#named_nodes] :> Association[RustLink::Private::nodes],
+                #to_list
+            }),
+        };
+
+        // Replace from the innermost level outwards, so that nested data stores are
+        // converted before the data store that contains them.
+        wl!(With[{RustLink::Private::nativeFunc = #load_call},
+            Function[
+                Replace[RustLink::Private::nativeFunc[##], #rules, {0, Infinity}]
+            ]
+        ])
+    }
+}
+
 //======================================
 // Initialization
 //======================================
@@ -580,3 +702,110 @@ pub unsafe fn init_with_user_function(
         sys::LIBRARY_NO_ERROR as c_int
     }
 }
+
+//======================================
+// #[derive(FromArg, IntoArg)] helpers
+//======================================
+
+/// Construct the error returned when a native function argument has a value that is not
+/// valid for the type named `type_name`.
+#[doc(hidden)]
+pub fn arg_type_error(
+    type_name: &str,
+    found: &dyn fmt::Debug,
+    expected: &str,
+) -> ArgTypeError {
+    ArgTypeError {
+        message: format!(
+            "invalid {} argument value: {:?} (expected {})",
+            type_name, found, expected
+        ),
+    }
+}
+
+impl fmt::Display for ArgTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+//======================================
+// #[derive(IntoDataStore, FromDataStore)] helpers
+//======================================
+
+/// Get the named nodes of `store`, keyed by name, in a single pass over the store.
+///
+/// If several nodes have the same name, the first one is used, like
+/// [`DataStore::get_node()`].
+pub fn data_store_named_nodes(store: &DataStore) -> HashMap<&str, DataStoreNode<'_>> {
+    let mut nodes = HashMap::new();
+
+    for node in store.nodes() {
+        if let Some(name) = node.name_str() {
+            nodes.entry(name).or_insert(node);
+        }
+    }
+
+    nodes
+}
+
+/// Find the node named `name` in `nodes`, and convert its value into `T`.
+pub fn data_store_named_node<T: FromDataStoreNode>(
+    nodes: &HashMap<&str, DataStoreNode>,
+    name: &str,
+) -> Result<T, FromDataStoreError> {
+    let node = nodes.get(name).ok_or_else(|| {
+        FromDataStoreError::new(FromDataStoreErrorKind::MissingNode).in_node(name)
+    })?;
+
+    T::from_data_store_node(node).map_err(|err| err.in_node(name))
+}
+
+/// Get the nodes of `store`, returning an error if there are not exactly `len` nodes.
+pub fn data_store_nodes_exact(
+    store: &DataStore,
+    len: usize,
+) -> Result<Vec<DataStoreNode<'_>>, FromDataStoreError> {
+    let nodes: Vec<DataStoreNode> = store.nodes().collect();
+
+    if nodes.len() != len {
+        return Err(FromDataStoreError::new(
+            FromDataStoreErrorKind::UnexpectedLength {
+                expected: len,
+                found: nodes.len(),
+            },
+        ));
+    }
+
+    Ok(nodes)
+}
+
+/// Convert the value of the unnamed `node` at 0-based `index` into `T`.
+pub fn data_store_positional_node<T: FromDataStoreNode>(
+    index: usize,
+    node: &DataStoreNode,
+) -> Result<T, FromDataStoreError> {
+    T::from_data_store_node(node).map_err(|err| err.at_position(index + 1))
+}
+
+/// Borrow the value of `node` as a [`DataStore`].
+pub fn data_store_node_as_data_store<'node>(
+    node: &'node DataStoreNode,
+) -> Result<&'node DataStore, FromDataStoreError> {
+    match node.value() {
+        DataStoreNodeValue::DataStore(store) => Ok(store),
+        other => Err(FromDataStoreError::unexpected_type("DataStore", &other)),
+    }
+}
+
+//======================================
+// expr_match! helpers
+//======================================
+
+/// Construct the error returned when `expr` does not match any of `patterns`.
+pub fn expr_match_error(
+    expr: &Expr,
+    patterns: &'static [&'static str],
+) -> ExprMatchError {
+    ExprMatchError::new(expr, patterns)
+}
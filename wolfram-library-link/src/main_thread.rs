@@ -0,0 +1,309 @@
+//! Running closures on the main Wolfram Kernel thread from background threads.
+
+use std::{
+    any::Any,
+    collections::VecDeque,
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::{Arc, Condvar, Mutex},
+    task::{Context, Poll, Waker},
+    thread,
+};
+
+use once_cell::sync::Lazy;
+
+use crate::{
+    assert_main_thread, library_data::is_main_thread, AsyncEventSender, AsyncTaskObject,
+    DataStore,
+};
+
+/// Jobs waiting to be run on the main thread, and the asynchronous task used to notify
+/// the Kernel that there are jobs to run.
+static EXECUTOR: Lazy<Mutex<Executor>> = Lazy::new(|| {
+    Mutex::new(Executor {
+        task: None,
+        jobs: VecDeque::new(),
+    })
+});
+
+struct Executor {
+    task: Option<AsyncEventSender>,
+    /// Jobs waiting to be run. The argument is `true` if the job is being cancelled
+    /// instead of run.
+    jobs: VecDeque<Box<dyn FnOnce(bool) + Send>>,
+}
+
+/// Panic payload of the result of a job that was cancelled because the executor task
+/// was removed.
+const EXECUTOR_REMOVED: &str = "main thread executor task is no longer alive";
+
+/// Handle to the result of a closure submitted using [`spawn_on_main_thread()`].
+///
+/// The result can be retrieved by blocking with [`wait()`][MainThreadJob::wait], or by
+/// `.await`ing the `MainThreadJob`, which implements [`Future`].
+///
+/// If the closure panicked, the result is an `Err` containing the panic payload. If the
+/// task created by [`start_main_thread_executor()`] was removed before the closure could
+/// run, the closure is never run and the result is an `Err` containing a `&'static str`
+/// message.
+pub struct MainThreadJob<T> {
+    shared: Arc<JobShared<T>>,
+}
+
+struct JobShared<T> {
+    state: Mutex<JobState<T>>,
+    finished: Condvar,
+}
+
+struct JobState<T> {
+    result: Option<thread::Result<T>>,
+    waker: Option<Waker>,
+}
+
+//======================================
+// Executor
+//======================================
+
+/// Start the main thread executor, which runs closures submitted by
+/// [`spawn_on_main_thread()`].
+///
+/// This function creates a new asynchronous task which has no background thread, and
+/// returns its ID. Each time a closure is submitted, an asynchronous event named
+/// `"run"` is raised for this task. The Wolfram Language handler for this task should
+/// call a library function that calls [`run_main_thread_jobs()`].
+///
+/// This function should be called from within a LibraryLink function that was called
+/// via `` Internal`CreateAsynchronousTask ``. If the executor was already started, the
+/// new task replaces the previous one.
+///
+/// # Panics
+///
+/// This function will panic if it is not called from the main Kernel thread.
+///
+/// # Example
+///
+/// ```no_run
+/// # mod scope {
+/// use wolfram_library_link::{self as wll, sys::mint};
+///
+/// #[wll::export]
+/// fn start_executor() -> mint {
+///     wll::start_main_thread_executor()
+/// }
+///
+/// #[wll::export]
+/// fn run_jobs() -> mint {
+///     wll::run_main_thread_jobs() as mint
+/// }
+/// # }
+/// ```
+///
+/// ```wolfram
+/// runJobs = LibraryFunctionLoad["...", "run_jobs", {}, Integer];
+///
+/// Internal`CreateAsynchronousTask[
+///     LibraryFunctionLoad["...", "start_executor", {}, Integer],
+///     {},
+///     runJobs[] &
+/// ]
+/// ```
+pub fn start_main_thread_executor() -> crate::sys::mint {
+    assert_main_thread();
+
+    let (task, events) = AsyncTaskObject::new_without_thread();
+
+    let mut executor = EXECUTOR.lock().unwrap();
+
+    // Jobs may have been submitted before the executor was started.
+    if !executor.jobs.is_empty() {
+        events.raise_async_event("run", DataStore::new());
+    }
+
+    executor.task = Some(events);
+
+    task.id()
+}
+
+/// Run all of the closures submitted by [`spawn_on_main_thread()`] that are waiting to
+/// be run.
+///
+/// Returns the number of closures that were run.
+///
+/// This is intended to be called from a library function that is called by the handler
+/// of the task created by [`start_main_thread_executor()`].
+///
+/// # Panics
+///
+/// This function will panic if it is not called from the main Kernel thread.
+pub fn run_main_thread_jobs() -> usize {
+    assert_main_thread();
+
+    let mut count = 0;
+
+    loop {
+        // Don't hold the lock while running the job, which might itself submit new
+        // jobs.
+        let job = match EXECUTOR.lock().unwrap().jobs.pop_front() {
+            Some(job) => job,
+            None => return count,
+        };
+
+        job(false);
+        count += 1;
+    }
+}
+
+/// Submit a closure to be run on the main Kernel thread.
+///
+/// Functions that call back into the Wolfram Kernel, like [`evaluate()`][crate::evaluate],
+/// can only be called from the main Kernel thread. `spawn_on_main_thread()` allows
+/// background threads to make these calls safely.
+///
+/// `f` is run the next time the Kernel handles an asynchronous event for the task
+/// created by [`start_main_thread_executor()`]. If the executor has not been started,
+/// `f` is run once it is. If this function is called from the main Kernel thread, `f`
+/// is run immediately.
+///
+/// If the executor task has been removed, e.g. by `RemoveAsynchronousTask`, `f` and any
+/// other closures still waiting to be run are not run, and their results are an `Err`.
+/// Closures submitted after that are run once the executor is started again.
+///
+/// Returns a [`MainThreadJob`] that can be used to get the value returned by `f`.
+///
+/// # Example
+///
+/// Evaluate an expression from a background thread:
+///
+/// ```no_run
+/// use wolfram_library_link::{self as wll, expr::{Expr, Symbol}};
+///
+/// std::thread::spawn(|| {
+///     let job = wll::spawn_on_main_thread(|| {
+///         wll::evaluate(&Expr::normal(Symbol::new("System`Now"), vec![]))
+///     });
+///
+///     let now: Expr = job.wait().expect("evaluation panicked");
+/// });
+/// ```
+pub fn spawn_on_main_thread<F, T>(f: F) -> MainThreadJob<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let shared = Arc::new(JobShared {
+        state: Mutex::new(JobState {
+            result: None,
+            waker: None,
+        }),
+        finished: Condvar::new(),
+    });
+
+    let job_shared = Arc::clone(&shared);
+
+    let job = move |cancelled: bool| {
+        let result = if cancelled {
+            Err(Box::new(EXECUTOR_REMOVED) as Box<dyn Any + Send>)
+        } else {
+            panic::catch_unwind(AssertUnwindSafe(f))
+        };
+
+        let mut state = job_shared.state.lock().unwrap();
+        state.result = Some(result);
+
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+
+        job_shared.finished.notify_all();
+    };
+
+    if is_main_thread() {
+        job(false);
+        return MainThreadJob { shared };
+    }
+
+    let mut executor = EXECUTOR.lock().unwrap();
+
+    let executor_removed = match executor.task {
+        Some(ref task) => !task.is_alive(),
+        None => false,
+    };
+
+    if executor_removed {
+        // Nothing will ever run the waiting jobs, so complete them with an error.
+        executor.task = None;
+
+        let cancelled: Vec<_> = executor.jobs.drain(..).collect();
+
+        // Don't hold the lock while completing the jobs.
+        drop(executor);
+
+        for cancelled_job in cancelled {
+            cancelled_job(true);
+        }
+
+        job(true);
+    } else {
+        executor.jobs.push_back(Box::new(job));
+
+        if let Some(ref task) = executor.task {
+            task.raise_async_event("run", DataStore::new());
+        }
+    }
+
+    MainThreadJob { shared }
+}
+
+//======================================
+// MainThreadJob
+//======================================
+
+impl<T> MainThreadJob<T> {
+    /// Returns `true` if the closure has finished running.
+    pub fn is_finished(&self) -> bool {
+        self.shared.state.lock().unwrap().result.is_some()
+    }
+
+    /// Block the current thread until the closure has finished running, and return its
+    /// result.
+    ///
+    /// This must not be called from the main Kernel thread while the closure is still
+    /// waiting to be run, because the closure would never run.
+    pub fn wait(self) -> thread::Result<T> {
+        let JobShared {
+            ref state,
+            ref finished,
+        } = *self.shared;
+
+        let mut state = finished
+            .wait_while(state.lock().unwrap(), |state| state.result.is_none())
+            .unwrap();
+
+        state.result.take().unwrap()
+    }
+}
+
+impl<T> Future for MainThreadJob<T> {
+    type Output = thread::Result<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            },
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for MainThreadJob<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MainThreadJob")
+            .field("is_finished", &self.is_finished())
+            .finish()
+    }
+}
@@ -503,6 +503,14 @@ impl<T> NumericArray<T> {
         NumericArray(na, PhantomData)
     }
 
+    /// Erase the concrete `T` data type associated with this `NumericArray`, without
+    /// taking ownership of it.
+    pub(crate) fn as_generic(&self) -> &NumericArray {
+        // Safety: `NumericArray` is `repr(transparent)`, so `NumericArray<T>` and
+        //         `NumericArray` have the same layout.
+        unsafe { &*(self as *const NumericArray<T> as *const NumericArray) }
+    }
+
     /// Construct a `NumericArray<T>` from a raw [`MNumericArray`][sys::MNumericArray].
     ///
     /// # Safety
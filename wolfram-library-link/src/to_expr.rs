@@ -0,0 +1,196 @@
+//! Conversion of Rust values into Wolfram Language expressions.
+
+use std::fmt;
+
+use crate::{
+    data_store::expr::{
+        complex_expr, image_to_expr, numeric_array_to_expr, real_expr, u64_expr,
+    },
+    expr::{Expr, Symbol},
+    sys::mcomplex,
+    DataStore, Image, NumericArray,
+};
+
+/// Trait implemented for types that can be converted into an [`Expr`].
+///
+/// This is the inverse of [`FromExpr`][crate::FromExpr].
+///
+/// # Example
+///
+/// ```
+/// use wolfram_library_link::{expr::Expr, ToExpr};
+///
+/// assert_eq!(
+///     vec![1, 2, 3].to_expr().unwrap(),
+///     Expr::list(vec![Expr::from(1), Expr::from(2), Expr::from(3)])
+/// );
+/// ```
+pub trait ToExpr {
+    /// Construct an expression representing this value.
+    ///
+    /// Returns an error if this value cannot be represented as an [`Expr`], e.g. an
+    /// integer that does not fit in an `i64`.
+    fn to_expr(&self) -> Result<Expr, ToExprError>;
+}
+
+/// Error returned by [`ToExpr::to_expr()`] and [`DataStore::to_expr()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToExprError {
+    message: String,
+}
+
+impl ToExprError {
+    pub(crate) fn new(message: String) -> Self {
+        ToExprError { message }
+    }
+
+    pub(crate) fn integer_overflow(value: u64) -> Self {
+        ToExprError::new(format!(
+            "integer {} is too large to be represented as an Expr",
+            value
+        ))
+    }
+}
+
+impl fmt::Display for ToExprError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unable to convert value to an Expr: {}", self.message)
+    }
+}
+
+impl std::error::Error for ToExprError {}
+
+//======================================
+// Impls
+//======================================
+
+impl ToExpr for Expr {
+    fn to_expr(&self) -> Result<Expr, ToExprError> {
+        Ok(self.clone())
+    }
+}
+
+impl ToExpr for bool {
+    fn to_expr(&self) -> Result<Expr, ToExprError> {
+        Ok(Expr::from(*self))
+    }
+}
+
+macro_rules! impl_ToExpr_for_integer {
+    ($($type:ty),*) => {
+        $(
+            impl ToExpr for $type {
+                fn to_expr(&self) -> Result<Expr, ToExprError> {
+                    Ok(Expr::from(i64::from(*self)))
+                }
+            }
+        )*
+    };
+}
+
+impl_ToExpr_for_integer!(i8, i16, i32, i64, u8, u16, u32);
+
+/// Returns an error if the value does not fit in an `i64`.
+impl ToExpr for u64 {
+    fn to_expr(&self) -> Result<Expr, ToExprError> {
+        u64_expr(*self)
+    }
+}
+
+/// Uses `Indeterminate` and `DirectedInfinity[..]` for values that have no literal
+/// representation.
+impl ToExpr for f64 {
+    fn to_expr(&self) -> Result<Expr, ToExprError> {
+        Ok(real_expr(*self))
+    }
+}
+
+impl ToExpr for f32 {
+    fn to_expr(&self) -> Result<Expr, ToExprError> {
+        Ok(real_expr(f64::from(*self)))
+    }
+}
+
+/// Converts into a `Complex[re, im]` expression.
+impl ToExpr for mcomplex {
+    fn to_expr(&self) -> Result<Expr, ToExprError> {
+        Ok(complex_expr(self.ri))
+    }
+}
+
+impl ToExpr for str {
+    fn to_expr(&self) -> Result<Expr, ToExprError> {
+        Ok(Expr::string(self))
+    }
+}
+
+impl ToExpr for String {
+    fn to_expr(&self) -> Result<Expr, ToExprError> {
+        Ok(Expr::string(self.as_str()))
+    }
+}
+
+impl ToExpr for Symbol {
+    fn to_expr(&self) -> Result<Expr, ToExprError> {
+        Ok(Expr::symbol(self.clone()))
+    }
+}
+
+/// Converts into a `List[...]` expression.
+impl<T: ToExpr> ToExpr for [T] {
+    fn to_expr(&self) -> Result<Expr, ToExprError> {
+        let elements = self.iter().map(T::to_expr).collect::<Result<_, _>>()?;
+
+        Ok(Expr::list(elements))
+    }
+}
+
+/// Converts into a `List[...]` expression.
+impl<T: ToExpr> ToExpr for Vec<T> {
+    fn to_expr(&self) -> Result<Expr, ToExprError> {
+        self.as_slice().to_expr()
+    }
+}
+
+/// Converts `None` into `Missing[]`. This is the inverse of the
+/// [`FromExpr`][crate::FromExpr] implementation for `Option<T>`.
+impl<T: ToExpr> ToExpr for Option<T> {
+    fn to_expr(&self) -> Result<Expr, ToExprError> {
+        match self {
+            Some(value) => value.to_expr(),
+            None => Ok(Expr::normal(Symbol::new("System`Missing"), vec![])),
+        }
+    }
+}
+
+impl<T: ToExpr + ?Sized> ToExpr for &T {
+    fn to_expr(&self) -> Result<Expr, ToExprError> {
+        T::to_expr(self)
+    }
+}
+
+/// Converts into a ``Developer`DataStore[...]`` expression using
+/// [`DataStore::to_expr()`].
+impl ToExpr for DataStore {
+    fn to_expr(&self) -> Result<Expr, ToExprError> {
+        DataStore::to_expr(self)
+    }
+}
+
+/// Converts into a `NumericArray[data, "type"]` expression.
+///
+/// Returns an error if this is an `"UnsignedInteger64"` array containing a value that
+/// does not fit in an `i64`.
+impl<T> ToExpr for NumericArray<T> {
+    fn to_expr(&self) -> Result<Expr, ToExprError> {
+        numeric_array_to_expr(self.as_generic())
+    }
+}
+
+/// Converts into an `Image[data, "type", opts]` or `Image3D[data, "type", opts]`
+/// expression.
+impl<T> ToExpr for Image<T> {
+    fn to_expr(&self) -> Result<Expr, ToExprError> {
+        Ok(image_to_expr(self.as_generic()))
+    }
+}
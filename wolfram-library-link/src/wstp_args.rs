@@ -0,0 +1,1267 @@
+//! Lazily reading the arguments of, and streaming the result of, WSTP functions.
+//!
+//! See [`Args`] and [`ResultWriter`].
+
+use std::{cell::Cell, fmt, panic::AssertUnwindSafe};
+
+use wstp::{Array, Link, LinkStr, Token};
+
+use crate::{
+    big_number::put_integer_digits,
+    catch_panic::call_and_catch_panic,
+    data_store::expr::{
+        complex_expr, image_dimensions, image_head, image_options,
+        image_options_from_exprs, image_shape, new_image,
+    },
+    expr::{Expr, Symbol},
+    wl, FromExpr, Image, ImageData, ImageType, NumericArray, NumericArrayDataType,
+    NumericArrayKind, NumericArrayType,
+};
+
+/// Arguments of a WSTP function, which are read from the [`Link`] on demand.
+///
+/// Unlike `fn(Vec<Expr>)`, which reads every argument into an [`Expr`] before the
+/// function is called, a function that takes `Args` reads each argument directly from
+/// the link when it is needed. Strings can be borrowed from the link, numeric arrays
+/// are read as a single flat buffer, and arguments that are not needed can be skipped
+/// without being read.
+///
+/// Any arguments that have not been read when the function returns are skipped
+/// automatically.
+///
+/// [`#[export(wstp)]`][crate::export#exportwstp] can be used with functions that have
+/// either of the signatures:
+///
+/// * `fn(_: Args) -> Expr`
+/// * `fn(_: Args)`
+///
+/// A function with the second signature can write its result incrementally using
+/// [`Args::into_result_writer()`]. If it does not, the function returns `Null`.
+///
+/// # Example
+///
+/// Compute the total of a large list of reals, and skip any remaining arguments:
+///
+/// ```no_run
+/// # mod scope {
+/// use wolfram_library_link::{self as wll, expr::Expr, Args};
+///
+/// #[wll::export(wstp)]
+/// fn total_with_label(mut args: Args) -> Expr {
+///     let label = args.next_str().unwrap().as_str().to_uppercase();
+///
+///     let values = args.next_f64_array().unwrap();
+///     let total: f64 = values.data().iter().sum();
+///
+///     Expr::rule(Expr::string(label), Expr::real(total))
+/// }
+/// # }
+/// ```
+///
+/// ```wolfram
+/// totalWithLabel = LibraryFunctionLoad["...", "total_with_label", LinkObject, LinkObject];
+///
+/// totalWithLabel["sum", RandomReal[1, 10^6], "ignored"]
+/// ```
+pub struct Args<'link> {
+    link: &'link mut Link,
+    state: &'link CallState,
+}
+
+/// Streaming writer for the result of a WSTP function.
+///
+/// A `ResultWriter` is created by [`Args::into_result_writer()`]. It is used to write
+/// exactly one result expression piece by piece, without first constructing an
+/// [`Expr`]. Each `put_*()` method writes one expression; `put_function()` and
+/// `put_list()` write the head of a normal expression, which must be followed by its
+/// elements.
+///
+/// # Panics
+///
+/// The `put_*()` methods panic if the result expression has already been completely
+/// written. The exported function will panic if it returns before the result
+/// expression is complete.
+///
+/// # Example
+///
+/// Return `{{1, 1}, {2, 4}, ..., {n, n^2}}` without constructing the whole list in
+/// memory first:
+///
+/// ```no_run
+/// # mod scope {
+/// use wolfram_library_link::{self as wll, Args};
+///
+/// #[wll::export(wstp)]
+/// fn squares(mut args: Args) {
+///     let n: i64 = args.next().unwrap();
+///
+///     let mut result = args.into_result_writer().unwrap();
+///
+///     result.put_list(n as usize).unwrap();
+///
+///     for i in 1..=n {
+///         result.put_i64_array(&[i, i * i], &[2]).unwrap();
+///     }
+/// }
+/// # }
+/// ```
+pub struct ResultWriter<'link> {
+    link: &'link mut Link,
+    state: &'link CallState,
+}
+
+/// Trait implemented for types that can be read from a [`Link`] by [`Args::next()`].
+pub trait FromLink: Sized {
+    /// Read a value of this type from the next expression on `link`.
+    fn from_link(link: &mut Link) -> Result<Self, ArgsError>;
+}
+
+/// Trait implemented for types that can be written to a [`Link`] as a single
+/// expression.
+///
+/// This is used to write the elements of an iterator returned by a WSTP function, and
+/// by [`ResultWriter::put()`].
+///
+/// Implementations must write exactly one complete expression to `link`.
+pub trait IntoLink {
+    /// Write this value to `link` as a single expression.
+    fn into_link(self, link: &mut Link) -> Result<(), wstp::Error>;
+}
+
+/// Error returned when a WSTP function argument could not be read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArgsError {
+    /// 1-based position of the argument that caused this error.
+    position: Option<usize>,
+    kind: ArgsErrorKind,
+}
+
+/// The kind of error represented by an [`ArgsError`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgsErrorKind {
+    /// All of the arguments have already been read.
+    MissingArgument {
+        /// Number of arguments the function was called with.
+        count: usize,
+    },
+    /// A WSTP error occurred while reading the argument.
+    Link(wstp::Error),
+    /// The argument did not have the expected type.
+    UnexpectedType {
+        /// Description of the expected type.
+        expected: String,
+        /// Description of the value that was found.
+        found: String,
+    },
+}
+
+/// State shared between [`Args`], [`ResultWriter`], and the WSTP function wrapper that
+/// created them.
+pub(crate) struct CallState {
+    /// Number of arguments the function was called with.
+    count: usize,
+    /// Number of arguments that have been read or skipped.
+    read: Cell<usize>,
+    /// Number of expressions that must still be written to complete the result, or
+    /// `None` if no [`ResultWriter`] has been created.
+    pending: Cell<Option<usize>>,
+}
+
+//======================================
+// Impls
+//======================================
+
+impl<'link> Args<'link> {
+    /// Number of arguments the function was called with.
+    pub fn len(&self) -> usize {
+        self.state.count
+    }
+
+    /// Returns `true` if the function was called with no arguments.
+    pub fn is_empty(&self) -> bool {
+        self.state.count == 0
+    }
+
+    /// Number of arguments that have not been read or skipped yet.
+    pub fn remaining(&self) -> usize {
+        self.state.count - self.state.read.get()
+    }
+
+    /// Read the next argument as a value of type `T`.
+    ///
+    /// ```no_run
+    /// # use wolfram_library_link::{Args, ArgsError};
+    /// # fn example(mut args: Args) -> Result<(), ArgsError> {
+    /// let count: i64 = args.next()?;
+    /// let name: String = args.next()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    // Note: This can't implement `Iterator`, because the item type is chosen per call.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next<T: FromLink>(&mut self) -> Result<T, ArgsError> {
+        let position = self.advance()?;
+
+        T::from_link(self.link).map_err(|err| err.at_position(position))
+    }
+
+    /// Read the next argument as a string borrowed from the link, without copying it.
+    pub fn next_str(&mut self) -> Result<LinkStr<'_>, ArgsError> {
+        let position = self.advance()?;
+
+        self.link
+            .get_string_ref()
+            .map_err(|err| ArgsError::from(err).at_position(position))
+    }
+
+    /// Read the next argument as a packed array of integers, without converting each
+    /// element into an [`Expr`].
+    pub fn next_i64_array(&mut self) -> Result<Array<'_, i64>, ArgsError> {
+        let position = self.advance()?;
+
+        self.link
+            .get_i64_array()
+            .map_err(|err| ArgsError::from(err).at_position(position))
+    }
+
+    /// Read the next argument as a packed array of reals, without converting each
+    /// element into an [`Expr`].
+    pub fn next_f64_array(&mut self) -> Result<Array<'_, f64>, ArgsError> {
+        let position = self.advance()?;
+
+        self.link
+            .get_f64_array()
+            .map_err(|err| ArgsError::from(err).at_position(position))
+    }
+
+    /// Skip the next argument without reading it into memory.
+    pub fn skip(&mut self) -> Result<(), ArgsError> {
+        let position = self.advance()?;
+
+        skip_expr(self.link).map_err(|err| ArgsError::from(err).at_position(position))
+    }
+
+    /// Skip any remaining arguments, and return a [`ResultWriter`] that can be used to
+    /// write the result of this function directly to the link.
+    ///
+    /// This can only be used by functions with the signature `fn(Args)`. Functions that
+    /// return an [`Expr`] will panic if they call this method.
+    pub fn into_result_writer(mut self) -> Result<ResultWriter<'link>, ArgsError> {
+        self.skip_remaining()?;
+
+        let Args { link, state } = self;
+
+        state.pending.set(Some(1));
+
+        Ok(ResultWriter { link, state })
+    }
+
+    fn skip_remaining(&mut self) -> Result<(), ArgsError> {
+        while self.remaining() > 0 {
+            self.skip()?;
+        }
+
+        Ok(())
+    }
+
+    /// Mark the next argument as read, returning its 1-based position.
+    fn advance(&mut self) -> Result<usize, ArgsError> {
+        let read = self.state.read.get();
+
+        if read == self.state.count {
+            return Err(ArgsError::new(ArgsErrorKind::MissingArgument {
+                count: self.state.count,
+            })
+            .at_position(read + 1));
+        }
+
+        self.state.read.set(read + 1);
+
+        Ok(read + 1)
+    }
+}
+
+impl<'link> ResultWriter<'link> {
+    /// Returns `true` if the result expression has been completely written.
+    pub fn is_complete(&self) -> bool {
+        self.state.pending.get() == Some(0)
+    }
+
+    /// Write `value` as a single expression.
+    pub fn put<T: IntoLink>(&mut self, value: T) -> Result<(), wstp::Error> {
+        self.begin(0);
+        value.into_link(self.link)
+    }
+
+    /// Write the head of a normal expression with `len` elements, which must be written
+    /// next.
+    pub fn put_function(&mut self, head: &str, len: usize) -> Result<(), wstp::Error> {
+        self.begin(len);
+        self.link.put_function(head, len)
+    }
+
+    /// Write the head of a list with `len` elements, which must be written next.
+    pub fn put_list(&mut self, len: usize) -> Result<(), wstp::Error> {
+        self.put_function("System`List", len)
+    }
+
+    #[allow(missing_docs)]
+    pub fn put_i64(&mut self, value: i64) -> Result<(), wstp::Error> {
+        self.begin(0);
+        self.link.put_i64(value)
+    }
+
+    #[allow(missing_docs)]
+    pub fn put_f64(&mut self, value: f64) -> Result<(), wstp::Error> {
+        self.begin(0);
+        self.link.put_f64(value)
+    }
+
+    #[allow(missing_docs)]
+    pub fn put_str(&mut self, value: &str) -> Result<(), wstp::Error> {
+        self.begin(0);
+        self.link.put_str(value)
+    }
+
+    /// Write the symbol named `symbol`, e.g. ``"System`True"``.
+    pub fn put_symbol(&mut self, symbol: &str) -> Result<(), wstp::Error> {
+        self.begin(0);
+        self.link.put_symbol(symbol)
+    }
+
+    #[allow(missing_docs)]
+    pub fn put_expr(&mut self, expr: &Expr) -> Result<(), wstp::Error> {
+        self.begin(0);
+        self.link.put_expr(expr)
+    }
+
+    /// Write a packed array of integers.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the product of `dimensions` is not equal to
+    /// `data.len()`.
+    pub fn put_i64_array(
+        &mut self,
+        data: &[i64],
+        dimensions: &[usize],
+    ) -> Result<(), wstp::Error> {
+        self.begin(0);
+        self.link.put_i64_array(data, dimensions)
+    }
+
+    /// Write a packed array of reals.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the product of `dimensions` is not equal to
+    /// `data.len()`.
+    pub fn put_f64_array(
+        &mut self,
+        data: &[f64],
+        dimensions: &[usize],
+    ) -> Result<(), wstp::Error> {
+        self.begin(0);
+        self.link.put_f64_array(data, dimensions)
+    }
+
+    /// Record that an expression with `elements` elements is about to be written.
+    fn begin(&mut self, elements: usize) {
+        let pending = self.state.pending.get().unwrap_or(0);
+
+        if pending == 0 {
+            panic!("ResultWriter: the result expression has already been written");
+        }
+
+        self.state.pending.set(Some(pending - 1 + elements));
+    }
+}
+
+impl CallState {
+    pub(crate) fn new(count: usize) -> Self {
+        CallState {
+            count,
+            read: Cell::new(0),
+            pending: Cell::new(None),
+        }
+    }
+
+    pub(crate) fn args<'link>(&'link self, link: &'link mut Link) -> Args<'link> {
+        Args { link, state: self }
+    }
+
+    /// Complete a call to a WSTP function that took [`Args`] and returned `()`, by
+    /// skipping any unread arguments.
+    ///
+    /// If the function did not use a [`ResultWriter`], `Null` is written.
+    pub(crate) fn finish(&self, link: &mut Link) {
+        self.skip_unread(link);
+
+        let written = match self.pending.get() {
+            None => link.put_symbol("System`Null"),
+            Some(0) => Ok(()),
+            Some(pending) => panic!(
+                "WstpFunction: function returned before the result expression was \
+                completely written ({} more expressions were expected)",
+                pending
+            ),
+        };
+
+        if let Err(err) = written {
+            panic!(
+                "WstpFunction: WSTP error writing return Null expression to link: {}",
+                err
+            );
+        }
+    }
+
+    /// Complete a call to a WSTP function that took [`Args`] and returned a value, by
+    /// skipping any unread arguments and then calling `write_result`.
+    pub(crate) fn finish_with<F>(&self, link: &mut Link, write_result: F)
+    where
+        F: FnOnce(&mut Link) -> Result<(), wstp::Error>,
+    {
+        self.skip_unread(link);
+
+        if self.pending.get().is_some() {
+            panic!(
+                "WstpFunction: Args::into_result_writer() cannot be used by a function \
+                that returns a value"
+            );
+        }
+
+        if let Err(err) = write_result(link) {
+            panic!(
+                "WstpFunction: WSTP error writing return expression to link: {}",
+                err
+            );
+        }
+    }
+
+    fn skip_unread(&self, link: &mut Link) {
+        if let Err(err) = self.args(link).skip_remaining() {
+            panic!("WstpFunction: error skipping unread arguments: {}", err);
+        }
+    }
+}
+
+/// Read and discard the next expression on `link`.
+fn skip_expr(link: &mut Link) -> Result<(), wstp::Error> {
+    let length = match link.get_token()? {
+        Token::Function { length } => length,
+        Token::Integer(_) | Token::Real(_) | Token::String(_) | Token::Symbol(_) => {
+            return Ok(())
+        },
+    };
+
+    // Skip the head, followed by the elements.
+    for _ in 0..=length {
+        skip_expr(link)?;
+    }
+
+    Ok(())
+}
+
+/// Write the elements produced by `iter` to `link` as a `List`, one at a time.
+///
+/// The length of the list is determined by [`ExactSizeIterator::len()`]; any elements
+/// produced beyond that length are ignored.
+///
+/// Once the `List` head has been written, the list must be completed for the link to
+/// remain in a valid state. If a panic occurs in [`Iterator::next()`] or
+/// [`IntoLink::into_link()`], or `iter` ends before producing `len()` elements, the
+/// element being produced is replaced by a `Failure["RustPanic", ..]` expression, and the
+/// remaining elements by `Nothing`. If `into_link()` returns an error, the element is
+/// replaced by a `Failure["WSTPError", ..]` expression instead.
+///
+/// Each element is written to a loopback link, and only transferred to `link` once it
+/// is complete, so a failed element does not leave partially written data on `link`.
+pub(crate) fn write_list_from_iter<I>(
+    link: &mut Link,
+    mut iter: I,
+) -> Result<(), wstp::Error>
+where
+    I: ExactSizeIterator,
+    I::Item: IntoLink,
+{
+    let len = iter.len();
+
+    let mut buffer = Link::new_loopback()?;
+
+    link.put_function("System`List", len)?;
+
+    for index in 0..len {
+        let result = call_and_catch_panic(AssertUnwindSafe(|| match iter.next() {
+            Some(elem) => elem.into_link(&mut buffer),
+            None => panic!(
+                "ExactSizeIterator returned {} elements, but reported a length of {}",
+                index, len
+            ),
+        }));
+
+        let failure = match result {
+            Ok(Ok(())) => {
+                buffer.transfer_expr_to(link)?;
+                continue;
+            },
+            Ok(Err(err)) => wl!(Failure["WSTPError", <|
+                "MessageTemplate" -> "WSTP error writing List element: `message`",
+                "MessageParameters" -> <| "message" -> #(err.to_string()) |>
+            |>]),
+            Err(caught_panic) => caught_panic.to_pretty_expr(),
+        };
+
+        // Any partial element data is discarded when `buffer` is dropped.
+        link.put_expr(&failure)?;
+
+        for _ in index + 1..len {
+            link.put_symbol("System`Nothing")?;
+        }
+
+        return Ok(());
+    }
+
+    Ok(())
+}
+
+//--------------------------------------
+// ArgsError
+//--------------------------------------
+
+impl ArgsError {
+    /// Construct a new error of the specified kind.
+    pub fn new(kind: ArgsErrorKind) -> Self {
+        ArgsError {
+            position: None,
+            kind,
+        }
+    }
+
+    /// Construct an [`UnexpectedType`][ArgsErrorKind::UnexpectedType] error.
+    pub fn unexpected_type(expected: &str, found: &str) -> Self {
+        ArgsError::new(ArgsErrorKind::UnexpectedType {
+            expected: expected.to_owned(),
+            found: found.to_owned(),
+        })
+    }
+
+    /// Record the 1-based position of the argument that caused this error.
+    pub fn at_position(mut self, position: usize) -> Self {
+        self.position = Some(position);
+        self
+    }
+
+    /// 1-based position of the argument that caused this error, if known.
+    pub fn position(&self) -> Option<usize> {
+        self.position
+    }
+
+    /// The kind of this error.
+    pub fn kind(&self) -> &ArgsErrorKind {
+        &self.kind
+    }
+}
+
+impl From<wstp::Error> for ArgsError {
+    fn from(err: wstp::Error) -> Self {
+        ArgsError::new(ArgsErrorKind::Link(err))
+    }
+}
+
+impl fmt::Display for ArgsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ArgsError { position, kind } = self;
+
+        if let Some(position) = position {
+            write!(f, "argument #{}: ", position)?;
+        }
+
+        match kind {
+            ArgsErrorKind::MissingArgument { count } => {
+                write!(
+                    f,
+                    "missing argument (function was called with {} arguments)",
+                    count
+                )
+            },
+            ArgsErrorKind::Link(err) => write!(f, "WSTP error: {}", err),
+            ArgsErrorKind::UnexpectedType { expected, found } => {
+                write!(f, "expected {}, found {}", expected, found)
+            },
+        }
+    }
+}
+
+impl std::error::Error for ArgsError {}
+
+//======================================
+// FromLink Impls
+//======================================
+
+macro_rules! impl_FromLink {
+    ($($type:ty => $method:ident),* $(,)?) => {
+        $(
+            impl FromLink for $type {
+                fn from_link(link: &mut Link) -> Result<Self, ArgsError> {
+                    Ok(link.$method()?)
+                }
+            }
+        )*
+    };
+}
+
+impl_FromLink!(
+    i64 => get_i64,
+    i32 => get_i32,
+    i16 => get_i16,
+    u8 => get_u8,
+    f64 => get_f64,
+    f32 => get_f32,
+    String => get_string,
+    Expr => get_expr,
+);
+
+impl FromLink for bool {
+    fn from_link(link: &mut Link) -> Result<Self, ArgsError> {
+        let symbol = link.get_symbol_ref()?;
+
+        match symbol.as_str() {
+            "True" | "System`True" => Ok(true),
+            "False" | "System`False" => Ok(false),
+            other => Err(ArgsError::unexpected_type("True or False", other)),
+        }
+    }
+}
+
+/// Reads a one-dimensional packed array of integers.
+impl FromLink for Vec<i64> {
+    fn from_link(link: &mut Link) -> Result<Self, ArgsError> {
+        let array = link.get_i64_array()?;
+
+        match array.rank() {
+            1 => Ok(array.data().to_vec()),
+            rank => Err(ArgsError::unexpected_type(
+                "list of integers",
+                &format!("array of rank {}", rank),
+            )),
+        }
+    }
+}
+
+/// Reads a one-dimensional packed array of reals.
+impl FromLink for Vec<f64> {
+    fn from_link(link: &mut Link) -> Result<Self, ArgsError> {
+        let array = link.get_f64_array()?;
+
+        match array.rank() {
+            1 => Ok(array.data().to_vec()),
+            rank => Err(ArgsError::unexpected_type(
+                "list of reals",
+                &format!("array of rank {}", rank),
+            )),
+        }
+    }
+}
+
+/// Reads a `NumericArray[data, "type"]` expression, or any other expression that can be
+/// converted using [`FromExpr`].
+impl FromLink for NumericArray {
+    fn from_link(link: &mut Link) -> Result<Self, ArgsError> {
+        from_link_via_expr(link)
+    }
+}
+
+macro_rules! impl_FromLink_for_NumericArray {
+    ($($type:ty),*) => {
+        $(
+            /// Reads a `NumericArray[data, "type"]` expression whose type matches, or a
+            /// packed array whose elements can be represented as this type.
+            impl FromLink for NumericArray<$type> {
+                fn from_link(link: &mut Link) -> Result<Self, ArgsError> {
+                    numeric_array_from_link(link)
+                }
+            }
+        )*
+    };
+}
+
+impl_FromLink_for_NumericArray!(i8, i16, i32, i64, u8, u16, u32, f32, f64);
+
+/// Reads an `Image[data, "type", opts]` or `Image3D[data, "type", opts]` expression.
+impl FromLink for Image {
+    fn from_link(link: &mut Link) -> Result<Self, ArgsError> {
+        from_link_via_expr(link)
+    }
+}
+
+macro_rules! impl_FromLink_for_Image {
+    ($($type:ty),*) => {
+        $(
+            /// Reads an `Image[data, "type", opts]` or `Image3D[data, "type", opts]`
+            /// expression whose type matches. The image data is read as a packed array.
+            impl FromLink for Image<$type> {
+                fn from_link(link: &mut Link) -> Result<Self, ArgsError> {
+                    image_from_link(link)
+                }
+            }
+        )*
+    };
+}
+
+impl_FromLink_for_Image!(bool, u8, u16, f32, f64);
+
+fn from_link_via_expr<T: FromExpr>(link: &mut Link) -> Result<T, ArgsError> {
+    let expr = link.get_expr()?;
+
+    T::from_expr(&expr).map_err(|err| {
+        ArgsError::unexpected_type(err.expected(), &err.expr().to_string())
+    })
+}
+
+fn numeric_array_from_link<T>(link: &mut Link) -> Result<NumericArray<T>, ArgsError>
+where
+    T: NumericArrayType + LinkArrayElement,
+{
+    let expected = || format!("NumericArray of type \"{}\"", T::TYPE.name());
+
+    match test_system_head(link, "NumericArray")? {
+        Some(2) => {
+            let array = match with_array_or_skip::<T, _>(link, NumericArray::from_array) {
+                Ok(array) => array,
+                Err(err) => {
+                    // Skip the type name, so that the link is left positioned after the
+                    // NumericArray[..] expression.
+                    skip_expr(link)?;
+                    return Err(err);
+                },
+            };
+
+            let type_name = link.get_string_ref()?;
+
+            if type_name.as_str() != T::TYPE.name() {
+                return Err(ArgsError::unexpected_type(
+                    &expected(),
+                    &format!("NumericArray of type \"{}\"", type_name.as_str()),
+                ));
+            }
+
+            Ok(array)
+        },
+        Some(length) => {
+            skip_elements(link, length)?;
+
+            Err(ArgsError::unexpected_type(
+                &expected(),
+                &format!("NumericArray with {} elements", length),
+            ))
+        },
+        None => with_array_or_skip::<T, _>(link, NumericArray::from_array),
+    }
+}
+
+fn image_from_link<T>(link: &mut Link) -> Result<Image<T>, ArgsError>
+where
+    T: ImageData,
+    T::STORAGE: LinkArrayElement,
+{
+    let expected = || format!("Image of type \"{}\"", T::TYPE.name());
+
+    let (rank, length) = match test_system_head(link, "Image")? {
+        Some(length) => (2, length),
+        None => match test_system_head(link, "Image3D")? {
+            Some(length) => (3, length),
+            None => {
+                let found = link.get_expr()?;
+                return Err(ArgsError::unexpected_type(&expected(), &found.to_string()));
+            },
+        },
+    };
+
+    if length < 2 {
+        skip_elements(link, length)?;
+
+        return Err(ArgsError::unexpected_type(
+            &expected(),
+            &format!("Image with {} elements", length),
+        ));
+    }
+
+    // The image data may be wrapped in a NumericArray[data, "type"] expression.
+    let wrapped = test_system_head(link, "NumericArray")?;
+
+    if let Some(wrapped_length) = wrapped {
+        if wrapped_length != 2 {
+            skip_elements(link, wrapped_length)?;
+            skip_elements(link, length - 1)?;
+
+            return Err(ArgsError::unexpected_type(
+                &expected(),
+                "Image with invalid NumericArray data",
+            ));
+        }
+    }
+
+    // The data is copied out of the link, because the image cannot be created until its
+    // options have been read.
+    let data = with_array_or_skip::<T::STORAGE, _>(link, |dimensions, data| {
+        (dimensions.to_vec(), data.to_vec())
+    });
+
+    if wrapped.is_some() {
+        skip_expr(link)?;
+    }
+
+    let (dimensions, data) = match data {
+        Ok(data) => data,
+        Err(err) => {
+            // Skip the type name and options, so that the link is left positioned after
+            // the Image[..] expression.
+            skip_elements(link, length - 1)?;
+            return Err(err);
+        },
+    };
+
+    let type_name = link.get_string()?;
+
+    let options = (2..length)
+        .map(|_| link.get_expr())
+        .collect::<Result<Vec<Expr>, wstp::Error>>()?;
+
+    if !(type_name == T::TYPE.name()
+        || (type_name == "Real" && T::TYPE == ImageType::Real64))
+    {
+        return Err(ArgsError::unexpected_type(
+            &expected(),
+            &format!("Image of type \"{}\"", type_name),
+        ));
+    }
+
+    let (color_space, interleaving) =
+        image_options_from_exprs(&options).map_err(|err| {
+            ArgsError::unexpected_type("Image option", &err.expr().to_string())
+        })?;
+
+    let (pixels, channels) =
+        image_shape(rank, &dimensions, interleaving).ok_or_else(|| {
+            ArgsError::unexpected_type(
+                &expected(),
+                &format!("Image data with dimensions {:?}", dimensions),
+            )
+        })?;
+
+    new_image::<T>(pixels, channels, color_space, interleaving, &data).ok_or_else(|| {
+        ArgsError::unexpected_type(
+            &expected(),
+            &format!(
+                "Image data with dimensions {:?} that could not be used to create an Image",
+                dimensions
+            ),
+        )
+    })
+}
+
+/// Read a packed array from `link` using [`LinkArrayElement::with_array()`].
+///
+/// If the array cannot be read from the link, the link error is cleared and the
+/// expression is skipped, so that the link is always left positioned after it.
+fn with_array_or_skip<T: LinkArrayElement, R>(
+    link: &mut Link,
+    f: impl FnOnce(&[usize], &[T]) -> R,
+) -> Result<R, ArgsError> {
+    let err = match T::with_array(link, f) {
+        Ok(value) => return Ok(value),
+        Err(err) => err,
+    };
+
+    // Other errors are returned after the array has been read.
+    if let ArgsErrorKind::Link(_) = err.kind() {
+        link.clear_error();
+
+        // If the expression cannot be skipped either, the link itself is broken, and
+        // the original error is more informative.
+        let _ = skip_expr(link);
+    }
+
+    Err(err)
+}
+
+/// Test whether the next expression on `link` is a normal expression with the specified
+/// `System` context head, returning its length if it is.
+///
+/// If the head does not match, the link is left positioned on the same expression.
+pub(crate) fn test_system_head(
+    link: &mut Link,
+    name: &str,
+) -> Result<Option<usize>, wstp::Error> {
+    for head in [name.to_owned(), format!("System`{}", name)] {
+        match link.test_head(&head) {
+            Ok(length) => return Ok(Some(length)),
+            Err(err) if err.code() == Some(wstp::sys::WSEGSEQ) => link.clear_error(),
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(None)
+}
+
+pub(crate) fn skip_elements(link: &mut Link, length: usize) -> Result<(), wstp::Error> {
+    for _ in 0..length {
+        skip_expr(link)?;
+    }
+
+    Ok(())
+}
+
+/// Write the flat array `data` as a nested `List` with the specified dimensions, using
+/// `put` to write each element.
+fn put_nested_list<T: Copy>(
+    link: &mut Link,
+    dimensions: &[usize],
+    data: &[T],
+    put: &impl Fn(&mut Link, T) -> Result<(), wstp::Error>,
+) -> Result<(), wstp::Error> {
+    let (&len, rest) = match dimensions.split_first() {
+        Some(split) => split,
+        None => return put(link, data[0]),
+    };
+
+    let stride: usize = rest.iter().product();
+
+    link.put_function("System`List", len)?;
+
+    for index in 0..len {
+        put_nested_list(link, rest, &data[index * stride..(index + 1) * stride], put)?;
+    }
+
+    Ok(())
+}
+
+//======================================
+// IntoLink Impls
+//======================================
+
+macro_rules! impl_IntoLink {
+    ($($type:ty => $method:ident),* $(,)?) => {
+        $(
+            impl IntoLink for $type {
+                fn into_link(self, link: &mut Link) -> Result<(), wstp::Error> {
+                    link.$method(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_IntoLink!(
+    i64 => put_i64,
+    i32 => put_i32,
+    i16 => put_i16,
+    u8 => put_u8,
+    f64 => put_f64,
+    f32 => put_f32,
+);
+
+impl IntoLink for bool {
+    fn into_link(self, link: &mut Link) -> Result<(), wstp::Error> {
+        link.put_symbol(if self { "System`True" } else { "System`False" })
+    }
+}
+
+impl IntoLink for &str {
+    fn into_link(self, link: &mut Link) -> Result<(), wstp::Error> {
+        link.put_str(self)
+    }
+}
+
+impl IntoLink for String {
+    fn into_link(self, link: &mut Link) -> Result<(), wstp::Error> {
+        link.put_str(&self)
+    }
+}
+
+impl IntoLink for Expr {
+    fn into_link(self, link: &mut Link) -> Result<(), wstp::Error> {
+        link.put_expr(&self)
+    }
+}
+
+impl IntoLink for &Expr {
+    fn into_link(self, link: &mut Link) -> Result<(), wstp::Error> {
+        link.put_expr(self)
+    }
+}
+
+impl IntoLink for &Symbol {
+    fn into_link(self, link: &mut Link) -> Result<(), wstp::Error> {
+        link.put_symbol(self.as_str())
+    }
+}
+
+/// Writes a `List` of the elements.
+impl<T: IntoLink> IntoLink for Vec<T> {
+    fn into_link(self, link: &mut Link) -> Result<(), wstp::Error> {
+        link.put_function("System`List", self.len())?;
+
+        for elem in self {
+            elem.into_link(link)?;
+        }
+
+        Ok(())
+    }
+}
+
+macro_rules! impl_IntoLink_for_tuple {
+    ($len:literal; $($type:ident),*) => {
+        /// Writes a `List` of the tuple fields.
+        impl<$($type: IntoLink),*> IntoLink for ($($type,)*) {
+            fn into_link(self, link: &mut Link) -> Result<(), wstp::Error> {
+                // Re-use the $type name as the local variable names.
+                #[allow(non_snake_case)]
+                let ($($type,)*) = self;
+
+                link.put_function("System`List", $len)?;
+
+                $($type.into_link(link)?;)*
+
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_IntoLink_for_tuple!(1; A1);
+impl_IntoLink_for_tuple!(2; A1, A2);
+impl_IntoLink_for_tuple!(3; A1, A2, A3);
+impl_IntoLink_for_tuple!(4; A1, A2, A3, A4);
+impl_IntoLink_for_tuple!(5; A1, A2, A3, A4, A5);
+impl_IntoLink_for_tuple!(6; A1, A2, A3, A4, A5, A6);
+
+/// Writes a `NumericArray[data, "type"]` expression, where `data` is written as a
+/// packed array.
+impl<T> IntoLink for &NumericArray<T> {
+    fn into_link(self, link: &mut Link) -> Result<(), wstp::Error> {
+        let array = self.as_generic();
+
+        fn put<T>(link: &mut Link, array: &NumericArray<T>) -> Result<(), wstp::Error>
+        where
+            T: NumericArrayType + LinkArrayElement,
+        {
+            link.put_function("System`NumericArray", 2)?;
+            T::put_array(link, array.as_slice(), array.dimensions())?;
+            link.put_str(T::TYPE.name())
+        }
+
+        // WSTP has no packed array types that can represent the elements of the
+        // remaining array types, so those are written element by element.
+        fn put_elements<T: Copy>(
+            link: &mut Link,
+            array: &NumericArray,
+            data: &[T],
+            put: impl Fn(&mut Link, T) -> Result<(), wstp::Error>,
+        ) -> Result<(), wstp::Error> {
+            link.put_function("System`NumericArray", 2)?;
+            put_nested_list(link, array.dimensions(), data, &put)?;
+            link.put_str(array.data_type().name())
+        }
+
+        // Note: NumericArray::kind() does not support ComplexReal32 arrays.
+        if array.data_type() == NumericArrayDataType::ComplexReal32 {
+            let data: &[[f32; 2]] = unsafe {
+                std::slice::from_raw_parts(
+                    array.data_ptr() as *const [f32; 2],
+                    array.flattened_length(),
+                )
+            };
+
+            return put_elements(link, array, data, |link, [re, im]| {
+                link.put_expr(&complex_expr([f64::from(re), f64::from(im)]))
+            });
+        }
+
+        match array.kind() {
+            NumericArrayKind::Bit8(array) => put(link, array),
+            NumericArrayKind::Bit16(array) => put(link, array),
+            NumericArrayKind::Bit32(array) => put(link, array),
+            NumericArrayKind::Bit64(array) => put(link, array),
+            NumericArrayKind::UBit8(array) => put(link, array),
+            NumericArrayKind::UBit16(array) => put(link, array),
+            NumericArrayKind::UBit32(array) => put(link, array),
+            NumericArrayKind::Real32(array) => put(link, array),
+            NumericArrayKind::Real64(array) => put(link, array),
+            NumericArrayKind::UBit64(array) => {
+                put_elements(link, array.as_generic(), array.as_slice(), |link, value| {
+                    match i64::try_from(value) {
+                        Ok(value) => link.put_i64(value),
+                        Err(_) => put_integer_digits(link, &value.to_string()),
+                    }
+                })
+            },
+            NumericArrayKind::ComplexReal64(array) => {
+                put_elements(link, array.as_generic(), array.as_slice(), |link, value| {
+                    link.put_expr(&complex_expr(value.ri))
+                })
+            },
+        }
+    }
+}
+
+/// Writes a `NumericArray[data, "type"]` expression, where `data` is written as a
+/// packed array.
+impl<T> IntoLink for NumericArray<T> {
+    fn into_link(self, link: &mut Link) -> Result<(), wstp::Error> {
+        (&self).into_link(link)
+    }
+}
+
+/// Writes an `Image[data, "type", opts]` or `Image3D[data, "type", opts]` expression,
+/// where `data` is written as a packed array.
+impl<T> IntoLink for &Image<T> {
+    fn into_link(self, link: &mut Link) -> Result<(), wstp::Error> {
+        let image = self.as_generic();
+
+        /// # Safety
+        ///
+        /// `S` must be the storage type of the data in `image`.
+        unsafe fn put_data<S: LinkArrayElement>(
+            link: &mut Link,
+            image: &Image,
+        ) -> Result<(), wstp::Error> {
+            let data: &[S] = std::slice::from_raw_parts(
+                image.raw_data() as *const S,
+                image.flattened_length(),
+            );
+
+            S::put_array(link, data, &image_dimensions(image))
+        }
+
+        let options = image_options(image);
+
+        link.put_function(image_head(image), 2 + options.len())?;
+
+        unsafe {
+            match image.data_type() {
+                ImageType::Bit => put_data::<i8>(link, image)?,
+                ImageType::Bit8 => put_data::<u8>(link, image)?,
+                ImageType::Bit16 => put_data::<u16>(link, image)?,
+                ImageType::Real32 => put_data::<f32>(link, image)?,
+                ImageType::Real64 => put_data::<f64>(link, image)?,
+            }
+        }
+
+        link.put_str(image.data_type().name())?;
+
+        for option in &options {
+            link.put_expr(option)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes an `Image[data, "type", opts]` or `Image3D[data, "type", opts]` expression,
+/// where `data` is written as a packed array.
+impl<T> IntoLink for Image<T> {
+    fn into_link(self, link: &mut Link) -> Result<(), wstp::Error> {
+        (&self).into_link(link)
+    }
+}
+
+//======================================
+// Packed arrays
+//======================================
+
+/// Element type of a numeric array that can be read from and written to a [`Link`] as a
+/// packed array.
+pub(crate) trait LinkArrayElement: Copy {
+    /// Read a packed array from `link`, and call `f` with its dimensions and flattened
+    /// data.
+    fn with_array<R>(
+        link: &mut Link,
+        f: impl FnOnce(&[usize], &[Self]) -> R,
+    ) -> Result<R, ArgsError>;
+
+    /// Write `data` to `link` as a packed array with the specified `dimensions`.
+    fn put_array(
+        link: &mut Link,
+        data: &[Self],
+        dimensions: &[usize],
+    ) -> Result<(), wstp::Error>;
+}
+
+macro_rules! impl_LinkArrayElement {
+    ($($type:ty => $get:ident, $put:ident);* $(;)?) => {
+        $(
+            impl LinkArrayElement for $type {
+                fn with_array<R>(
+                    link: &mut Link,
+                    f: impl FnOnce(&[usize], &[Self]) -> R,
+                ) -> Result<R, ArgsError> {
+                    let array = link.$get()?;
+
+                    Ok(f(array.dimensions(), array.data()))
+                }
+
+                fn put_array(
+                    link: &mut Link,
+                    data: &[Self],
+                    dimensions: &[usize],
+                ) -> Result<(), wstp::Error> {
+                    link.$put(data, dimensions)
+                }
+            }
+        )*
+    };
+}
+
+impl_LinkArrayElement!(
+    i16 => get_i16_array, put_i16_array;
+    i32 => get_i32_array, put_i32_array;
+    i64 => get_i64_array, put_i64_array;
+    u8 => get_u8_array, put_u8_array;
+    f32 => get_f32_array, put_f32_array;
+    f64 => get_f64_array, put_f64_array;
+);
+
+/// Implement [`LinkArrayElement`] for a type that WSTP has no packed array functions
+/// for, by converting to and from a wider type that it does.
+macro_rules! impl_LinkArrayElement_via {
+    ($($type:ty => $wide:ty),* $(,)?) => {
+        $(
+            impl LinkArrayElement for $type {
+                fn with_array<R>(
+                    link: &mut Link,
+                    f: impl FnOnce(&[usize], &[Self]) -> R,
+                ) -> Result<R, ArgsError> {
+                    let (dimensions, data) = <$wide>::with_array(link, |dimensions, data| {
+                        let data = data
+                            .iter()
+                            .map(|&value| <$type>::try_from(value).ok())
+                            .collect::<Option<Vec<$type>>>();
+
+                        (dimensions.to_vec(), data)
+                    })?;
+
+                    match data {
+                        Some(data) => Ok(f(&dimensions, &data)),
+                        None => Err(ArgsError::unexpected_type(
+                            concat!("array of ", stringify!($type), " values"),
+                            "array with an element out of range",
+                        )),
+                    }
+                }
+
+                fn put_array(
+                    link: &mut Link,
+                    data: &[Self],
+                    dimensions: &[usize],
+                ) -> Result<(), wstp::Error> {
+                    let data: Vec<$wide> = data.iter().map(|&value| <$wide>::from(value)).collect();
+
+                    <$wide>::put_array(link, &data, dimensions)
+                }
+            }
+        )*
+    };
+}
+
+impl_LinkArrayElement_via!(i8 => i16, u16 => i32, u32 => i64);
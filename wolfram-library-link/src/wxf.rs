@@ -0,0 +1,908 @@
+//! Serialization of [`Expr`]s using the [Wolfram Exchange Format][WXF] (WXF).
+//!
+//! WXF is the binary format used by [`BinarySerialize`][ref/BinarySerialize] and
+//! [`BinaryDeserialize`][ref/BinaryDeserialize]. Serializing an expression to WXF
+//! and passing the resulting bytes as a [`ByteArray`][ref/ByteArray] is often much
+//! faster than transferring a large expression over a WSTP link, and lets native
+//! LibraryLink functions accept and return arbitrary expressions. See [`Wxf`] for
+//! details.
+//!
+//! ```
+//! use wolfram_library_link::{wl, wxf};
+//!
+//! let expr = wl!(f[x, {1, 2, 3}, "text"]);
+//!
+//! let bytes: Vec<u8> = wxf::serialize(&expr).unwrap();
+//!
+//! assert_eq!(wxf::deserialize(&bytes), Ok(expr));
+//! ```
+//!
+//! # Arrays
+//!
+//! Rectangular lists of integers or of reals, like `{1, 2, 3}` or `{{1.0, 2.0}}`, are
+//! serialized as packed arrays, which are stored far more compactly than the equivalent
+//! nested lists. `NumericArray[data, "type"]` expressions are serialized as numeric
+//! arrays, so that they deserialize into [`NumericArray`][ref/NumericArray] values in
+//! the Wolfram Language.
+//!
+//! When deserialized, packed arrays become nested lists, and numeric arrays become
+//! `NumericArray[data, "type"]` expressions, using the same conventions as
+//! [`DataStore::to_expr()`][crate::DataStore::to_expr].
+//!
+//! [WXF]: https://reference.wolfram.com/language/tutorial/WXFFormatDescription.html
+//! [ref/BinarySerialize]: https://reference.wolfram.com/language/ref/BinarySerialize.html
+//! [ref/BinaryDeserialize]: https://reference.wolfram.com/language/ref/BinaryDeserialize.html
+//! [ref/ByteArray]: https://reference.wolfram.com/language/ref/ByteArray.html
+//! [ref/NumericArray]: https://reference.wolfram.com/language/ref/NumericArray.html
+
+use std::fmt;
+
+use crate::{
+    data_store::expr::{
+        complex_expr, complex_parts, flatten_array, has_head, nested_list, real_expr,
+        real_value, u64_expr,
+    },
+    expr::{Expr, ExprKind, Normal, Symbol},
+    NumericArrayDataType,
+};
+
+/// Header of uncompressed WXF data.
+const HEADER: &[u8] = b"8:";
+/// Header of zlib-compressed WXF data.
+const COMPRESSED_HEADER: &[u8] = b"8C:";
+
+/// Maximum nesting depth of an expression that can be serialized or deserialized.
+///
+/// Serialization and deserialization are recursive, so this limit prevents deeply
+/// nested expressions, or malicious WXF data, from overflowing the stack. Each level of
+/// a packed or numeric array counts towards the depth.
+const MAX_DEPTH: usize = 512;
+
+mod token {
+    pub const FUNCTION: u8 = b'f';
+    pub const SYMBOL: u8 = b's';
+    pub const STRING: u8 = b'S';
+    pub const BINARY_STRING: u8 = b'B';
+    pub const INTEGER8: u8 = b'C';
+    pub const INTEGER16: u8 = b'j';
+    pub const INTEGER32: u8 = b'i';
+    pub const INTEGER64: u8 = b'L';
+    pub const REAL64: u8 = b'r';
+    pub const BIG_INTEGER: u8 = b'I';
+    pub const BIG_REAL: u8 = b'R';
+    pub const PACKED_ARRAY: u8 = 0xC1;
+    pub const NUMERIC_ARRAY: u8 = 0xC2;
+    pub const ASSOCIATION: u8 = b'A';
+    pub const RULE: u8 = b'-';
+    pub const RULE_DELAYED: u8 = b':';
+}
+
+/// Error returned by [`deserialize()`] when the data is not valid WXF, or contains
+/// values that cannot be represented as an [`Expr`], and by [`serialize()`] when an
+/// expression is nested too deeply.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WxfError {
+    offset: usize,
+    message: &'static str,
+}
+
+/// An [`Expr`] that is passed to or returned from a native LibraryLink function as a
+/// WXF [`ByteArray`][ref/ByteArray].
+///
+/// `Wxf` implements [`FromArg`][crate::FromArg] and [`IntoArg`][crate::IntoArg] using
+/// the `LibraryDataType[ByteArray]` type. Wolfram Language code calling the function
+/// uses [`BinarySerialize`][ref/BinarySerialize] to create arguments, and
+/// [`BinaryDeserialize`][ref/BinaryDeserialize] to read the result.
+///
+/// # Errors
+///
+/// A function generated by [`#[export]`][crate::export] returns `LIBRARY_TYPE_ERROR`,
+/// without calling the function body, if a `Wxf` argument is not valid WXF data.
+///
+/// # Panics
+///
+/// [`from_arg()`][crate::FromArg::from_arg] will panic if the argument is not valid
+/// WXF data, and [`into_arg()`][crate::IntoArg::into_arg] will panic if the expression
+/// cannot be serialized.
+///
+/// # Example
+///
+/// ```no_run
+/// # mod scope {
+/// use wolfram_library_link::{self as wll, expr::Expr, wxf::Wxf};
+///
+/// #[wll::export]
+/// fn reverse_list(list: Wxf) -> Wxf {
+///     let Wxf(list) = list;
+///
+///     let mut elements: Vec<Expr> = match list.kind() {
+///         wll::expr::ExprKind::Normal(normal) => normal.elements().to_vec(),
+///         _ => panic!("expected a list"),
+///     };
+///     elements.reverse();
+///
+///     Wxf(Expr::list(elements))
+/// }
+/// # }
+/// ```
+///
+/// ```wolfram
+/// reverseList = LibraryFunctionLoad["...", "reverse_list", {LibraryDataType[ByteArray]}, LibraryDataType[ByteArray]];
+///
+/// BinaryDeserialize[reverseList[BinarySerialize[{a, b, c}]]]  (* {c, b, a} *)
+/// ```
+///
+/// [ref/ByteArray]: https://reference.wolfram.com/language/ref/ByteArray.html
+/// [ref/BinarySerialize]: https://reference.wolfram.com/language/ref/BinarySerialize.html
+/// [ref/BinaryDeserialize]: https://reference.wolfram.com/language/ref/BinaryDeserialize.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wxf(pub Expr);
+
+//======================================
+// Serialization
+//======================================
+
+/// Serialize `expr` as WXF data.
+///
+/// The returned bytes can be read by
+/// [`BinaryDeserialize`][ref/BinaryDeserialize] or [`deserialize()`].
+///
+/// Returns an error if `expr` is nested more than 512 levels deep.
+///
+/// ```
+/// use wolfram_library_link::{wl, wxf};
+///
+/// // BinarySerialize[{1, 2, 3}]
+/// assert_eq!(
+///     wxf::serialize(&wl!({1, 2, 3})),
+///     Ok(vec![56, 58, 193, 0, 1, 3, 1, 2, 3])
+/// );
+/// ```
+///
+/// [ref/BinaryDeserialize]: https://reference.wolfram.com/language/ref/BinaryDeserialize.html
+pub fn serialize(expr: &Expr) -> Result<Vec<u8>, WxfError> {
+    let mut out = HEADER.to_vec();
+    write_expr(&mut out, expr, 0)?;
+    Ok(out)
+}
+
+fn write_expr(out: &mut Vec<u8>, expr: &Expr, depth: usize) -> Result<(), WxfError> {
+    if depth >= MAX_DEPTH {
+        return Err(WxfError::new(out.len(), "expression is nested too deeply"));
+    }
+
+    match expr.kind() {
+        ExprKind::Integer(value) => write_integer(out, *value),
+        ExprKind::Real(value) => {
+            out.push(token::REAL64);
+            out.extend_from_slice(&value.to_le_bytes());
+        },
+        ExprKind::String(value) => write_string(out, token::STRING, value),
+        ExprKind::Symbol(symbol) => {
+            // Symbols in the System` context are written without a context, like
+            // BinarySerialize does.
+            let name = symbol.as_str();
+            let name = name.strip_prefix("System`").unwrap_or(name);
+
+            write_string(out, token::SYMBOL, name);
+        },
+        ExprKind::Normal(normal) => {
+            if has_head(normal, "System`Association")
+                && write_association(out, normal, depth)?
+            {
+                return Ok(());
+            }
+
+            if has_head(normal, "System`List") && write_packed_array(out, expr, depth) {
+                return Ok(());
+            }
+
+            if has_head(normal, "System`NumericArray")
+                && write_numeric_array(out, normal, depth)
+            {
+                return Ok(());
+            }
+
+            out.push(token::FUNCTION);
+            write_varint(out, normal.elements().len());
+            write_expr(out, normal.head(), depth + 1)?;
+
+            for element in normal.elements() {
+                write_expr(out, element, depth + 1)?;
+            }
+        },
+    }
+
+    Ok(())
+}
+
+fn write_integer(out: &mut Vec<u8>, value: i64) {
+    if let Ok(value) = i8::try_from(value) {
+        out.push(token::INTEGER8);
+        out.extend_from_slice(&value.to_le_bytes());
+    } else if let Ok(value) = i16::try_from(value) {
+        out.push(token::INTEGER16);
+        out.extend_from_slice(&value.to_le_bytes());
+    } else if let Ok(value) = i32::try_from(value) {
+        out.push(token::INTEGER32);
+        out.extend_from_slice(&value.to_le_bytes());
+    } else {
+        out.push(token::INTEGER64);
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+fn write_string(out: &mut Vec<u8>, token: u8, value: &str) {
+    out.push(token);
+    write_varint(out, value.len());
+    out.extend_from_slice(value.as_bytes());
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: usize) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+
+        out.push(byte | 0x80);
+    }
+}
+
+/// Write `<|key -> value, ...|>`, returning `false` if `normal` contains elements that
+/// are not rules.
+fn write_association(
+    out: &mut Vec<u8>,
+    normal: &Normal,
+    depth: usize,
+) -> Result<bool, WxfError> {
+    let rules: Option<Vec<(u8, &Expr, &Expr)>> = normal
+        .elements()
+        .iter()
+        .map(|element| match element.kind() {
+            ExprKind::Normal(rule) if rule.elements().len() == 2 => {
+                let token = if has_head(rule, "System`Rule") {
+                    token::RULE
+                } else if has_head(rule, "System`RuleDelayed") {
+                    token::RULE_DELAYED
+                } else {
+                    return None;
+                };
+
+                Some((token, &rule.elements()[0], &rule.elements()[1]))
+            },
+            _ => None,
+        })
+        .collect();
+
+    let rules = match rules {
+        Some(rules) => rules,
+        None => return Ok(false),
+    };
+
+    out.push(token::ASSOCIATION);
+    write_varint(out, rules.len());
+
+    for (token, key, value) in rules {
+        out.push(token);
+        write_expr(out, key, depth + 1)?;
+        write_expr(out, value, depth + 1)?;
+    }
+
+    Ok(true)
+}
+
+/// Write `list` as a packed array, returning `false` if it is not a rectangular array
+/// of integers or of reals.
+fn write_packed_array(out: &mut Vec<u8>, list: &Expr, depth: usize) -> bool {
+    let (dimensions, elements) = match array_elements(list, MAX_DEPTH - depth) {
+        Some(array) => array,
+        None => return false,
+    };
+
+    let (data_type, data) = match elements[0].kind() {
+        ExprKind::Integer(_) => {
+            let values: Option<Vec<i64>> = elements
+                .iter()
+                .map(|element| match element.kind() {
+                    ExprKind::Integer(value) => Some(*value),
+                    _ => None,
+                })
+                .collect();
+
+            let values = match values {
+                Some(values) => values,
+                None => return false,
+            };
+
+            // Use the smallest integer type that can hold every element.
+            let min = values.iter().copied().min().unwrap_or(0);
+            let max = values.iter().copied().max().unwrap_or(0);
+
+            let mut data = Vec::new();
+
+            let data_type = if i8::try_from(min).is_ok() && i8::try_from(max).is_ok() {
+                for value in values {
+                    data.extend_from_slice(&(value as i8).to_le_bytes());
+                }
+                NumericArrayDataType::Bit8
+            } else if i16::try_from(min).is_ok() && i16::try_from(max).is_ok() {
+                for value in values {
+                    data.extend_from_slice(&(value as i16).to_le_bytes());
+                }
+                NumericArrayDataType::Bit16
+            } else if i32::try_from(min).is_ok() && i32::try_from(max).is_ok() {
+                for value in values {
+                    data.extend_from_slice(&(value as i32).to_le_bytes());
+                }
+                NumericArrayDataType::Bit32
+            } else {
+                for value in values {
+                    data.extend_from_slice(&value.to_le_bytes());
+                }
+                NumericArrayDataType::Bit64
+            };
+
+            (data_type, data)
+        },
+        ExprKind::Real(_) => {
+            let mut data = Vec::with_capacity(elements.len() * 8);
+
+            for element in elements {
+                match element.kind() {
+                    ExprKind::Real(value) => data.extend_from_slice(&value.to_le_bytes()),
+                    _ => return false,
+                }
+            }
+
+            (NumericArrayDataType::Real64, data)
+        },
+        _ => return false,
+    };
+
+    write_array(out, token::PACKED_ARRAY, data_type, &dimensions, &data);
+
+    true
+}
+
+/// Write `NumericArray[data, "type"]` as a numeric array, returning `false` if `data`
+/// is not a rectangular array of values of that type.
+fn write_numeric_array(out: &mut Vec<u8>, normal: &Normal, depth: usize) -> bool {
+    let (data, data_type) = match normal.elements() {
+        [data, type_name] => match type_name.kind() {
+            ExprKind::String(type_name) => match numeric_array_data_type(type_name) {
+                Some(data_type) => (data, data_type),
+                None => return false,
+            },
+            _ => return false,
+        },
+        _ => return false,
+    };
+
+    let (dimensions, elements) = match array_elements(data, MAX_DEPTH - depth - 1) {
+        Some(array) => array,
+        None => return false,
+    };
+
+    fn integer<T: TryFrom<i64>>(expr: &Expr) -> Option<T> {
+        match expr.kind() {
+            ExprKind::Integer(value) => T::try_from(*value).ok(),
+            _ => None,
+        }
+    }
+
+    fn complex(expr: &Expr) -> Option<[f64; 2]> {
+        match real_value(expr) {
+            Some(re) => Some([re, 0.0]),
+            None => complex_parts(expr),
+        }
+    }
+
+    let mut bytes = Vec::with_capacity(elements.len() * element_size(data_type));
+
+    for element in elements {
+        use NumericArrayDataType as T;
+
+        let ok = match data_type {
+            T::Bit8 => integer::<i8>(element).map(|v| bytes.extend(v.to_le_bytes())),
+            T::Bit16 => integer::<i16>(element).map(|v| bytes.extend(v.to_le_bytes())),
+            T::Bit32 => integer::<i32>(element).map(|v| bytes.extend(v.to_le_bytes())),
+            T::Bit64 => integer::<i64>(element).map(|v| bytes.extend(v.to_le_bytes())),
+            T::UBit8 => integer::<u8>(element).map(|v| bytes.extend(v.to_le_bytes())),
+            T::UBit16 => integer::<u16>(element).map(|v| bytes.extend(v.to_le_bytes())),
+            T::UBit32 => integer::<u32>(element).map(|v| bytes.extend(v.to_le_bytes())),
+            T::UBit64 => integer::<u64>(element).map(|v| bytes.extend(v.to_le_bytes())),
+            T::Real32 => {
+                real_value(element).map(|v| bytes.extend((v as f32).to_le_bytes()))
+            },
+            T::Real64 => real_value(element).map(|v| bytes.extend(v.to_le_bytes())),
+            T::ComplexReal32 => complex(element).map(|[re, im]| {
+                bytes.extend((re as f32).to_le_bytes());
+                bytes.extend((im as f32).to_le_bytes());
+            }),
+            T::ComplexReal64 => complex(element).map(|[re, im]| {
+                bytes.extend(re.to_le_bytes());
+                bytes.extend(im.to_le_bytes());
+            }),
+        };
+
+        if ok.is_none() {
+            return false;
+        }
+    }
+
+    write_array(out, token::NUMERIC_ARRAY, data_type, &dimensions, &bytes);
+
+    true
+}
+
+fn write_array(
+    out: &mut Vec<u8>,
+    token: u8,
+    data_type: NumericArrayDataType,
+    dimensions: &[usize],
+    data: &[u8],
+) {
+    out.push(token);
+    out.push(array_type_byte(data_type));
+    write_varint(out, dimensions.len());
+
+    for &dimension in dimensions {
+        write_varint(out, dimension);
+    }
+
+    out.extend_from_slice(data);
+}
+
+/// Get the dimensions and flattened elements of the rectangular nested list `expr`.
+///
+/// Returns `None` if `expr` is not rectangular, if any of its dimensions are zero, which
+/// cannot be represented by a WXF array, or if it has more than `max_rank` dimensions.
+fn array_elements(expr: &Expr, max_rank: usize) -> Option<(Vec<usize>, Vec<&Expr>)> {
+    // Determine the dimensions of the array from the first element at each level.
+    let mut dimensions = Vec::new();
+    let mut current = expr;
+    while let ExprKind::Normal(list) = current.kind() {
+        if !has_head(list, "System`List") {
+            break;
+        }
+
+        dimensions.push(list.elements().len());
+
+        match list.elements().first() {
+            Some(first) => current = first,
+            None => break,
+        }
+    }
+
+    if dimensions.is_empty() || dimensions.len() > max_rank || dimensions.contains(&0) {
+        return None;
+    }
+
+    let mut elements = Vec::new();
+    if !flatten_array(expr, &dimensions, &mut elements) {
+        return None;
+    }
+
+    Some((dimensions, elements))
+}
+
+//======================================
+// Deserialization
+//======================================
+
+/// Deserialize an [`Expr`] from WXF data.
+///
+/// `data` is typically created by [`BinarySerialize`][ref/BinarySerialize] or
+/// [`serialize()`].
+///
+/// Compressed WXF data, and arbitrary-precision integers and reals, are not currently
+/// supported. An error is returned if the expression is nested more than 512 levels
+/// deep.
+///
+/// ```
+/// use wolfram_library_link::{wl, wxf};
+///
+/// // BinarySerialize[f[x]]
+/// let data = b"8:f\x01s\x08Global`fs\x08Global`x";
+///
+/// assert_eq!(wxf::deserialize(data), Ok(wl!(f[x])));
+///
+/// assert!(wxf::deserialize(b"not wxf").is_err());
+/// ```
+///
+/// [ref/BinarySerialize]: https://reference.wolfram.com/language/ref/BinarySerialize.html
+pub fn deserialize(data: &[u8]) -> Result<Expr, WxfError> {
+    if data.starts_with(COMPRESSED_HEADER) {
+        return Err(WxfError::new(0, "compressed WXF data is not supported"));
+    }
+
+    if !data.starts_with(HEADER) {
+        return Err(WxfError::new(0, "missing WXF header"));
+    }
+
+    let mut reader = Reader {
+        data,
+        offset: HEADER.len(),
+    };
+
+    let expr = reader.read_expr(0)?;
+
+    if reader.offset != data.len() {
+        return Err(reader.error("unexpected data after expression"));
+    }
+
+    Ok(expr)
+}
+
+struct Reader<'d> {
+    data: &'d [u8],
+    offset: usize,
+}
+
+impl<'d> Reader<'d> {
+    fn error(&self, message: &'static str) -> WxfError {
+        WxfError::new(self.offset, message)
+    }
+
+    fn remaining(&self) -> usize {
+        self.data.len() - self.offset
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'d [u8], WxfError> {
+        if len > self.remaining() {
+            return Err(self.error("unexpected end of data"));
+        }
+
+        let bytes = &self.data[self.offset..self.offset + len];
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N], WxfError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn read_byte(&mut self) -> Result<u8, WxfError> {
+        let [byte] = self.take_array()?;
+        Ok(byte)
+    }
+
+    fn read_varint(&mut self) -> Result<usize, WxfError> {
+        let mut value: u64 = 0;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.read_byte()?;
+            let bits = u64::from(byte & 0x7F);
+
+            if bits << shift >> shift != bits {
+                break;
+            }
+
+            value |= bits << shift;
+
+            if byte & 0x80 == 0 {
+                return usize::try_from(value)
+                    .map_err(|_| self.error("varint is too large"));
+            }
+        }
+
+        Err(self.error("varint is too large"))
+    }
+
+    fn read_str(&mut self) -> Result<&'d str, WxfError> {
+        let len = self.read_varint()?;
+        let bytes = self.take(len)?;
+
+        std::str::from_utf8(bytes).map_err(|_| self.error("string is not valid UTF-8"))
+    }
+
+    fn read_expr(&mut self, depth: usize) -> Result<Expr, WxfError> {
+        let start = self.offset;
+
+        if depth >= MAX_DEPTH {
+            return Err(self.error("expression is nested too deeply"));
+        }
+
+        let expr = match self.read_byte()? {
+            token::FUNCTION => {
+                let len = self.read_varint()?;
+                let head = self.read_expr(depth + 1)?;
+
+                // Every element takes up at least one byte, so don't trust the length
+                // for the initial allocation.
+                let mut elements = Vec::with_capacity(len.min(self.remaining()));
+                for _ in 0..len {
+                    elements.push(self.read_expr(depth + 1)?);
+                }
+
+                Expr::normal(head, elements)
+            },
+            token::SYMBOL => {
+                let name = self.read_str()?;
+
+                let symbol = if name.contains('`') {
+                    Symbol::try_new(name)
+                } else {
+                    Symbol::try_new(&format!("System`{}", name))
+                };
+
+                match symbol {
+                    Some(symbol) => Expr::symbol(symbol),
+                    None => return Err(WxfError::new(start, "invalid symbol name")),
+                }
+            },
+            token::STRING => Expr::string(self.read_str()?),
+            token::BINARY_STRING => {
+                let len = self.read_varint()?;
+                let bytes = self.take(len)?;
+
+                // ByteArray[{b1, b2, ...}]
+                Expr::normal(Symbol::new("System`ByteArray"), vec![Expr::list(
+                    bytes.iter().map(|&byte| Expr::from(byte)).collect(),
+                )])
+            },
+            token::INTEGER8 => Expr::from(i8::from_le_bytes(self.take_array()?)),
+            token::INTEGER16 => Expr::from(i16::from_le_bytes(self.take_array()?)),
+            token::INTEGER32 => Expr::from(i32::from_le_bytes(self.take_array()?)),
+            token::INTEGER64 => Expr::from(i64::from_le_bytes(self.take_array()?)),
+            token::REAL64 => real_expr(f64::from_le_bytes(self.take_array()?)),
+            token::BIG_INTEGER => {
+                return Err(WxfError::new(start, "big integers are not supported"))
+            },
+            token::BIG_REAL => {
+                return Err(WxfError::new(start, "big reals are not supported"))
+            },
+            token::ASSOCIATION => {
+                let len = self.read_varint()?;
+
+                let mut rules = Vec::with_capacity(len.min(self.remaining()));
+                for _ in 0..len {
+                    let head = match self.read_byte()? {
+                        token::RULE => "System`Rule",
+                        token::RULE_DELAYED => "System`RuleDelayed",
+                        _ => return Err(self.error("expected association rule")),
+                    };
+
+                    let key = self.read_expr(depth + 1)?;
+                    let value = self.read_expr(depth + 1)?;
+
+                    rules.push(Expr::normal(Symbol::new(head), vec![key, value]));
+                }
+
+                Expr::normal(Symbol::new("System`Association"), rules)
+            },
+            token::PACKED_ARRAY => {
+                let (data_type, dimensions, elements) = self.read_array(depth)?;
+
+                if !matches!(
+                    data_type,
+                    NumericArrayDataType::Bit8
+                        | NumericArrayDataType::Bit16
+                        | NumericArrayDataType::Bit32
+                        | NumericArrayDataType::Bit64
+                        | NumericArrayDataType::Real64
+                        | NumericArrayDataType::ComplexReal64
+                ) {
+                    return Err(WxfError::new(start, "invalid packed array type"));
+                }
+
+                nested_list(&dimensions, &elements)
+            },
+            token::NUMERIC_ARRAY => {
+                let (data_type, dimensions, elements) = self.read_array(depth + 1)?;
+
+                Expr::normal(Symbol::new("System`NumericArray"), vec![
+                    nested_list(&dimensions, &elements),
+                    Expr::string(data_type.name()),
+                ])
+            },
+            _ => return Err(WxfError::new(start, "unknown token")),
+        };
+
+        Ok(expr)
+    }
+
+    /// Read the element type, dimensions, and flattened elements of a packed or numeric
+    /// array whose outermost list is at nesting depth `depth`.
+    fn read_array(
+        &mut self,
+        depth: usize,
+    ) -> Result<(NumericArrayDataType, Vec<usize>, Vec<Expr>), WxfError> {
+        let type_offset = self.offset;
+        let data_type = array_type_from_byte(self.read_byte()?)
+            .ok_or_else(|| WxfError::new(type_offset, "unknown array type"))?;
+
+        let rank = self.read_varint()?;
+        if rank == 0 {
+            return Err(self.error("array rank must be positive"));
+        }
+
+        // Each dimension is a level of the nested list the array is converted into.
+        if rank > MAX_DEPTH - depth {
+            return Err(self.error("expression is nested too deeply"));
+        }
+
+        let mut dimensions = Vec::with_capacity(rank.min(self.remaining()));
+        for _ in 0..rank {
+            let dimension = self.read_varint()?;
+            if dimension == 0 {
+                return Err(self.error("array dimensions must be positive"));
+            }
+            dimensions.push(dimension);
+        }
+
+        let len = dimensions
+            .iter()
+            .try_fold(element_size(data_type), |len, &dim| len.checked_mul(dim))
+            .ok_or_else(|| self.error("array is too large"))?;
+
+        let data = self.take(len)?;
+
+        fn decode<const N: usize, E>(
+            data: &[u8],
+            to_expr: impl Fn([u8; N]) -> E,
+        ) -> Vec<E> {
+            data.chunks_exact(N)
+                .map(|chunk| {
+                    let mut bytes = [0; N];
+                    bytes.copy_from_slice(chunk);
+                    to_expr(bytes)
+                })
+                .collect()
+        }
+
+        fn complex32(bytes: [u8; 8]) -> Expr {
+            let [a, b, c, d, e, f, g, h] = bytes;
+
+            complex_expr([
+                f64::from(f32::from_le_bytes([a, b, c, d])),
+                f64::from(f32::from_le_bytes([e, f, g, h])),
+            ])
+        }
+
+        fn complex64(bytes: [u8; 16]) -> Expr {
+            let mut re = [0; 8];
+            let mut im = [0; 8];
+            re.copy_from_slice(&bytes[..8]);
+            im.copy_from_slice(&bytes[8..]);
+
+            complex_expr([f64::from_le_bytes(re), f64::from_le_bytes(im)])
+        }
+
+        use NumericArrayDataType as T;
+
+        let elements = match data_type {
+            T::Bit8 => decode(data, |b| Expr::from(i8::from_le_bytes(b))),
+            T::Bit16 => decode(data, |b| Expr::from(i16::from_le_bytes(b))),
+            T::Bit32 => decode(data, |b| Expr::from(i32::from_le_bytes(b))),
+            T::Bit64 => decode(data, |b| Expr::from(i64::from_le_bytes(b))),
+            T::UBit8 => decode(data, |b| Expr::from(u8::from_le_bytes(b))),
+            T::UBit16 => decode(data, |b| Expr::from(u16::from_le_bytes(b))),
+            T::UBit32 => decode(data, |b| Expr::from(u32::from_le_bytes(b))),
+            T::UBit64 => decode(data, |b| u64_expr(u64::from_le_bytes(b)))
+                .into_iter()
+                .collect::<Result<_, _>>()
+                .map_err(|_| {
+                    self.error("UnsignedInteger64 array element does not fit in an i64")
+                })?,
+            T::Real32 => decode(data, |b| real_expr(f64::from(f32::from_le_bytes(b)))),
+            T::Real64 => decode(data, |b| real_expr(f64::from_le_bytes(b))),
+            T::ComplexReal32 => decode(data, complex32),
+            T::ComplexReal64 => decode(data, complex64),
+        };
+
+        Ok((data_type, dimensions, elements))
+    }
+}
+
+//======================================
+// Array types
+//======================================
+
+#[rustfmt::skip]
+fn array_type_byte(data_type: NumericArrayDataType) -> u8 {
+    match data_type {
+        NumericArrayDataType::Bit8  => 0x00,
+        NumericArrayDataType::Bit16 => 0x01,
+        NumericArrayDataType::Bit32 => 0x02,
+        NumericArrayDataType::Bit64 => 0x03,
+
+        NumericArrayDataType::UBit8  => 0x10,
+        NumericArrayDataType::UBit16 => 0x11,
+        NumericArrayDataType::UBit32 => 0x12,
+        NumericArrayDataType::UBit64 => 0x13,
+
+        NumericArrayDataType::Real32 => 0x22,
+        NumericArrayDataType::Real64 => 0x23,
+
+        NumericArrayDataType::ComplexReal32 => 0x33,
+        NumericArrayDataType::ComplexReal64 => 0x34,
+    }
+}
+
+fn array_type_from_byte(byte: u8) -> Option<NumericArrayDataType> {
+    ALL_DATA_TYPES
+        .iter()
+        .copied()
+        .find(|&data_type| array_type_byte(data_type) == byte)
+}
+
+fn numeric_array_data_type(name: &str) -> Option<NumericArrayDataType> {
+    ALL_DATA_TYPES
+        .iter()
+        .copied()
+        .find(|data_type| data_type.name() == name)
+}
+
+/// Size in bytes of a single array element of type `data_type`.
+fn element_size(data_type: NumericArrayDataType) -> usize {
+    match data_type {
+        NumericArrayDataType::Bit8 | NumericArrayDataType::UBit8 => 1,
+        NumericArrayDataType::Bit16 | NumericArrayDataType::UBit16 => 2,
+        NumericArrayDataType::Bit32
+        | NumericArrayDataType::UBit32
+        | NumericArrayDataType::Real32 => 4,
+        NumericArrayDataType::Bit64
+        | NumericArrayDataType::UBit64
+        | NumericArrayDataType::Real64
+        | NumericArrayDataType::ComplexReal32 => 8,
+        NumericArrayDataType::ComplexReal64 => 16,
+    }
+}
+
+const ALL_DATA_TYPES: [NumericArrayDataType; 12] = [
+    NumericArrayDataType::Bit8,
+    NumericArrayDataType::Bit16,
+    NumericArrayDataType::Bit32,
+    NumericArrayDataType::Bit64,
+    NumericArrayDataType::UBit8,
+    NumericArrayDataType::UBit16,
+    NumericArrayDataType::UBit32,
+    NumericArrayDataType::UBit64,
+    NumericArrayDataType::Real32,
+    NumericArrayDataType::Real64,
+    NumericArrayDataType::ComplexReal32,
+    NumericArrayDataType::ComplexReal64,
+];
+
+//======================================
+// Impls
+//======================================
+
+impl WxfError {
+    fn new(offset: usize, message: &'static str) -> Self {
+        WxfError { offset, message }
+    }
+
+    /// Offset in bytes from the start of the data at which the error occurred.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Description of the error.
+    pub fn message(&self) -> &str {
+        self.message
+    }
+}
+
+impl fmt::Display for WxfError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let WxfError { offset, message } = self;
+
+        write!(f, "invalid WXF data at byte {}: {}", offset, message)
+    }
+}
+
+impl std::error::Error for WxfError {}
+
+impl From<Expr> for Wxf {
+    fn from(expr: Expr) -> Wxf {
+        Wxf(expr)
+    }
+}
@@ -0,0 +1,342 @@
+//! Tests of WXF serialization and deserialization that do not need a Wolfram Kernel.
+
+use wolfram_library_link::{
+    expr::{Expr, Symbol},
+    wl,
+    wxf::{self, WxfError},
+};
+
+fn round_trip(expr: &Expr) -> Expr {
+    let bytes = wxf::serialize(expr).expect("serialization failed");
+
+    wxf::deserialize(&bytes).expect("deserialization failed")
+}
+
+fn numeric_array(data: Expr, type_name: &str) -> Expr {
+    Expr::normal(Symbol::new("System`NumericArray"), vec![
+        data,
+        Expr::string(type_name),
+    ])
+}
+
+fn complex(re: f64, im: f64) -> Expr {
+    Expr::normal(Symbol::new("System`Complex"), vec![
+        Expr::real(re),
+        Expr::real(im),
+    ])
+}
+
+/// `f[f[...f[x]...]]`, nested `depth` levels deep.
+fn nested(depth: usize) -> Expr {
+    (0..depth).fold(wl!(x), |expr, _| {
+        Expr::normal(Symbol::new("Global`f"), vec![expr])
+    })
+}
+
+fn assert_error(data: &[u8], message: &str) {
+    match wxf::deserialize(data) {
+        Err(err) => assert_eq!(err.message(), message, "data: {:?}", data),
+        Ok(expr) => panic!("expected error {:?}, got: {}", message, expr),
+    }
+}
+
+//======================================
+// Packed arrays
+//======================================
+
+#[test]
+fn test_packed_array_round_trip() {
+    let arrays = [
+        wl!({1, 2, 3}),
+        wl!({{1, 2}, {3, 4}, {5, 6}}),
+        // Elements that need 16, 32, and 64 bits.
+        Expr::list(vec![Expr::from(1), Expr::from(1000)]),
+        Expr::list(vec![Expr::from(-1), Expr::from(100_000)]),
+        Expr::list(vec![Expr::from(i64::MIN), Expr::from(i64::MAX)]),
+        Expr::list(vec![
+            Expr::list(vec![Expr::real(1.5), Expr::real(-2.0)]),
+            Expr::list(vec![Expr::real(0.0), Expr::real(1e300)]),
+        ]),
+    ];
+
+    for array in &arrays {
+        let bytes = wxf::serialize(array).unwrap();
+
+        assert_eq!(bytes[2], 0xC1, "not written as a packed array: {}", array);
+        assert_eq!(&round_trip(array), array);
+    }
+}
+
+#[test]
+fn test_non_rectangular_lists_are_not_packed() {
+    let lists = [
+        wl!({{1, 2}, {3}}),
+        wl!({1, x}),
+        Expr::list(vec![Expr::from(1), Expr::real(2.0)]),
+        wl!({}),
+    ];
+
+    for list in &lists {
+        let bytes = wxf::serialize(list).unwrap();
+
+        assert_eq!(bytes[2], b'f', "written as a packed array: {}", list);
+        assert_eq!(&round_trip(list), list);
+    }
+}
+
+#[test]
+fn test_complex_packed_array() {
+    // BinarySerialize[{1. + 2. I}]
+    let mut data = b"8:\xC1\x34\x01\x01".to_vec();
+    data.extend_from_slice(&1f64.to_le_bytes());
+    data.extend_from_slice(&2f64.to_le_bytes());
+
+    assert_eq!(
+        wxf::deserialize(&data),
+        Ok(Expr::list(vec![complex(1.0, 2.0)]))
+    );
+}
+
+//======================================
+// Numeric arrays
+//======================================
+
+#[test]
+fn test_numeric_array_round_trip() {
+    let integers = wl!({{1, 2}, {3, 4}});
+    let reals = Expr::list(vec![Expr::real(0.5), Expr::real(-1.25)]);
+    let complexes = Expr::list(vec![complex(1.0, -2.0), complex(0.5, 0.0)]);
+
+    let arrays = [
+        numeric_array(integers.clone(), "Integer8"),
+        numeric_array(integers.clone(), "Integer16"),
+        numeric_array(integers.clone(), "Integer32"),
+        numeric_array(integers.clone(), "Integer64"),
+        numeric_array(integers.clone(), "UnsignedInteger8"),
+        numeric_array(integers.clone(), "UnsignedInteger16"),
+        numeric_array(integers.clone(), "UnsignedInteger32"),
+        numeric_array(integers, "UnsignedInteger64"),
+        numeric_array(reals.clone(), "Real32"),
+        numeric_array(reals, "Real64"),
+        numeric_array(complexes.clone(), "ComplexReal32"),
+        numeric_array(complexes, "ComplexReal64"),
+    ];
+
+    for array in &arrays {
+        let bytes = wxf::serialize(array).unwrap();
+
+        assert_eq!(bytes[2], 0xC2, "not written as a numeric array: {}", array);
+        assert_eq!(&round_trip(array), array);
+    }
+}
+
+#[test]
+fn test_invalid_numeric_array_is_written_as_function() {
+    let arrays = [
+        // Out of range for the element type.
+        numeric_array(wl!({1, 300}), "UnsignedInteger8"),
+        numeric_array(wl!({ -1 }), "UnsignedInteger32"),
+        numeric_array(wl!({1, 2}), "NotAType"),
+        numeric_array(wl!({{1, 2}, {3}}), "Integer8"),
+    ];
+
+    for array in &arrays {
+        let bytes = wxf::serialize(array).unwrap();
+
+        assert_eq!(bytes[2], b'f', "written as a numeric array: {}", array);
+        assert_eq!(&round_trip(array), array);
+    }
+}
+
+#[test]
+fn test_unsigned_integer64_overflow() {
+    // NumericArray[{2^64 - 1}, "UnsignedInteger64"]
+    let mut data = b"8:\xC2\x13\x01\x01".to_vec();
+    data.extend_from_slice(&u64::MAX.to_le_bytes());
+
+    assert_error(
+        &data,
+        "UnsignedInteger64 array element does not fit in an i64",
+    );
+}
+
+//======================================
+// Associations
+//======================================
+
+#[test]
+fn test_association_round_trip() {
+    let association = Expr::normal(Symbol::new("System`Association"), vec![
+        Expr::rule(Expr::string("a"), Expr::from(1)),
+        Expr::rule_delayed(wl!(b), wl!({x, y})),
+        Expr::rule(
+            Expr::from(3),
+            Expr::normal(Symbol::new("System`Association"), vec![]),
+        ),
+    ]);
+
+    let bytes = wxf::serialize(&association).unwrap();
+
+    assert_eq!(bytes[2], b'A');
+    assert_eq!(round_trip(&association), association);
+}
+
+#[test]
+fn test_association_with_non_rule_elements() {
+    let association =
+        Expr::normal(Symbol::new("System`Association"), vec![wl!(x), wl!(y)]);
+
+    let bytes = wxf::serialize(&association).unwrap();
+
+    assert_eq!(bytes[2], b'f');
+    assert_eq!(round_trip(&association), association);
+}
+
+//======================================
+// Other expressions
+//======================================
+
+#[test]
+fn test_expr_round_trip() {
+    let exprs = [
+        wl!(f[x, "text", 5]),
+        wl!(Global::g[Developer::DataStore[1], Hold[1 + 2]]),
+        Expr::string("unicode: \u{3b1}\u{3b2}\u{3b3}"),
+        Expr::real(-0.125),
+        Expr::from(-129),
+        Expr::from(i64::MAX),
+        nested(100),
+    ];
+
+    for expr in &exprs {
+        assert_eq!(&round_trip(expr), expr);
+    }
+}
+
+#[test]
+fn test_big_numbers_are_not_supported() {
+    // BinarySerialize[2^64]
+    assert_error(
+        b"8:I\x1418446744073709551616",
+        "big integers are not supported",
+    );
+
+    // BinarySerialize[1.5`30]
+    assert_error(b"8:R\x061.5`30", "big reals are not supported");
+}
+
+//======================================
+// Malformed data
+//======================================
+
+#[test]
+fn test_malformed_data() {
+    assert_error(b"", "missing WXF header");
+    assert_error(b"not wxf", "missing WXF header");
+    assert_error(b"8C:x\x9c", "compressed WXF data is not supported");
+    assert_error(b"8:", "unexpected end of data");
+    assert_error(b"8:Z", "unknown token");
+    assert_error(b"8:C\x01C\x02", "unexpected data after expression");
+    assert_error(b"8:s\x02a`", "invalid symbol name");
+    assert_error(b"8:S\x02\xFF\xFE", "string is not valid UTF-8");
+    assert_error(
+        b"8:S\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\x01",
+        "varint is too large",
+    );
+    assert_error(b"8:A\x01C\x01C\x02", "expected association rule");
+    assert_error(b"8:\xC1\xFF\x01\x01\x00", "unknown array type");
+    assert_error(b"8:\xC1\x00\x00", "array rank must be positive");
+    assert_error(b"8:\xC1\x00\x01\x00", "array dimensions must be positive");
+    assert_error(b"8:\xC1\x10\x01\x01\x00", "invalid packed array type");
+    assert_error(
+        b"8:\xC1\x03\x02\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\x7F\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\x7F",
+        "array is too large",
+    );
+}
+
+#[test]
+fn test_truncated_data() {
+    let exprs = [
+        wl!(f[x, "text", {1, 2, 3}]),
+        numeric_array(wl!({{1, 2}, {3, 4}}), "Real32"),
+        Expr::normal(Symbol::new("System`Association"), vec![Expr::rule(
+            Expr::string("key"),
+            Expr::real(1.5),
+        )]),
+    ];
+
+    for expr in &exprs {
+        let bytes = wxf::serialize(expr).unwrap();
+
+        for len in 0..bytes.len() {
+            let result: Result<Expr, WxfError> = wxf::deserialize(&bytes[..len]);
+
+            assert!(
+                result.is_err(),
+                "truncated data was accepted: {:?}",
+                &bytes[..len]
+            );
+        }
+    }
+}
+
+//======================================
+// Nesting depth
+//======================================
+
+/// Run `test` on a thread with a large stack. The nesting limit is chosen to be safe for
+/// optimized code, but unoptimized builds use much larger stack frames.
+fn with_large_stack(test: fn()) {
+    std::thread::Builder::new()
+        .stack_size(16 * 1024 * 1024)
+        .spawn(test)
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+/// WXF data for `f[f[...f[x]...]]`, nested `depth` levels deep.
+fn nested_data(depth: usize) -> Vec<u8> {
+    let mut data = b"8:".to_vec();
+    for _ in 0..depth {
+        data.extend_from_slice(b"f\x01s\x08Global`f");
+    }
+    data.extend_from_slice(b"s\x08Global`x");
+    data
+}
+
+#[test]
+fn test_serialize_nesting_limit() {
+    with_large_stack(|| {
+        assert!(wxf::serialize(&nested(511)).is_ok());
+
+        let err = wxf::serialize(&nested(512)).unwrap_err();
+        assert_eq!(err.message(), "expression is nested too deeply");
+
+        // A list that is too deep to be written as a packed array.
+        let list = (0..1000).fold(Expr::from(1), |expr, _| Expr::list(vec![expr]));
+
+        let err = wxf::serialize(&list).unwrap_err();
+        assert_eq!(err.message(), "expression is nested too deeply");
+    })
+}
+
+#[test]
+fn test_deserialize_nesting_limit() {
+    with_large_stack(|| {
+        assert_eq!(wxf::deserialize(&nested_data(511)), Ok(nested(511)));
+
+        assert_error(&nested_data(512), "expression is nested too deeply");
+
+        // Data nested far beyond the limit.
+        assert_error(&nested_data(100_000), "expression is nested too deeply");
+
+        // A packed array whose rank is far beyond the limit.
+        let mut data = b"8:\xC1\x00".to_vec();
+        data.extend_from_slice(&[0xA0, 0x8D, 0x06]); // varint 100_000
+        data.extend(vec![1; 100_000]);
+        data.push(7);
+
+        assert_error(&data, "expression is nested too deeply");
+    })
+}
@@ -0,0 +1,519 @@
+use proc_macro2::TokenStream as TokenStream2;
+
+use quote::quote;
+use syn::{
+    spanned::Spanned, Attribute, Data, DataEnum, DeriveInput, Error, Fields, Ident,
+    Index, Lifetime, LifetimeDef, Lit, Member, Meta, NestedMeta, Type,
+};
+
+//======================================
+// #[derive(FromArg)]
+//======================================
+
+pub(crate) fn derive_from_arg(input: DeriveInput) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+
+    // The lifetime of the `MArgument` the value is read from.
+    let lifetime = Lifetime::new("'__wll_arg", name.span());
+
+    let mut generics = input.generics.clone();
+    generics
+        .params
+        .insert(0, LifetimeDef::new(lifetime.clone()).into());
+
+    let (try_from_arg, parameter_type) = match derive_kind(&input, "FromArg", &[])? {
+        DeriveKind::Newtype { member, ty } => {
+            if !input.generics.params.is_empty() {
+                generics
+                    .make_where_clause()
+                    .predicates
+                    .push(syn::parse_quote! {
+                        #ty: ::wolfram_library_link::FromArg<#lifetime>
+                    });
+            }
+
+            let try_from_arg = quote! {
+                ::std::result::Result::Ok(#name {
+                    #member: <#ty as ::wolfram_library_link::FromArg<#lifetime>>::try_from_arg(arg)?
+                })
+            };
+
+            let parameter_type = quote! {
+                <#ty as ::wolfram_library_link::FromArg<#lifetime>>::parameter_type()
+            };
+
+            (try_from_arg, parameter_type)
+        },
+        DeriveKind::Enum(variants) => {
+            let type_name = name.to_string();
+            let expected = variants.expected();
+
+            let (value_ty, arms) = match variants.repr {
+                EnumRepr::String => {
+                    let arms = variants.names.iter().map(|(ident, wl_name)| {
+                        quote! { #wl_name => ::std::result::Result::Ok(#name::#ident), }
+                    });
+
+                    (quote! { ::std::string::String }, quote! {
+                        match value.as_str() {
+                            #(#arms)*
+                            _ => ::std::result::Result::Err(
+                                ::wolfram_library_link::macro_utils::arg_type_error(
+                                    #type_name,
+                                    &value,
+                                    #expected,
+                                ),
+                            ),
+                        }
+                    })
+                },
+                EnumRepr::Integer => {
+                    let arms = variants.names.iter().map(|(ident, _)| {
+                        quote! {
+                            _ if value == #name::#ident as ::wolfram_library_link::sys::mint => {
+                                ::std::result::Result::Ok(#name::#ident)
+                            },
+                        }
+                    });
+
+                    (quote! { ::wolfram_library_link::sys::mint }, quote! {
+                        match value {
+                            #(#arms)*
+                            _ => ::std::result::Result::Err(
+                                ::wolfram_library_link::macro_utils::arg_type_error(
+                                    #type_name,
+                                    &value,
+                                    #expected,
+                                ),
+                            ),
+                        }
+                    })
+                },
+            };
+
+            let try_from_arg = quote! {
+                let value =
+                    <#value_ty as ::wolfram_library_link::FromArg<#lifetime>>::try_from_arg(arg)?;
+
+                #arms
+            };
+
+            let parameter_type = quote! {
+                <#value_ty as ::wolfram_library_link::FromArg<#lifetime>>::parameter_type()
+            };
+
+            (try_from_arg, parameter_type)
+        },
+        // `#[arg(data_store)]` is not an allowed `FromArg` attribute argument, so this is
+        // rejected by `derive_kind()`.
+        DeriveKind::DataStore => {
+            return Err(Error::new(
+                proc_macro2::Span::call_site(),
+                "`FromArg` cannot be derived for a `#[arg(data_store)]` struct",
+            ))
+        },
+    };
+
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+
+    let output = quote! {
+        impl #impl_generics ::wolfram_library_link::FromArg<#lifetime> for #name #ty_generics
+            #where_clause
+        {
+            unsafe fn from_arg(
+                arg: &#lifetime ::wolfram_library_link::sys::MArgument,
+            ) -> Self {
+                match <Self as ::wolfram_library_link::FromArg<#lifetime>>::try_from_arg(arg) {
+                    ::std::result::Result::Ok(value) => value,
+                    ::std::result::Result::Err(err) => panic!("{}", err),
+                }
+            }
+
+            unsafe fn try_from_arg(
+                arg: &#lifetime ::wolfram_library_link::sys::MArgument,
+            ) -> ::std::result::Result<Self, ::wolfram_library_link::macro_utils::ArgTypeError> {
+                #try_from_arg
+            }
+
+            fn parameter_type() -> ::wolfram_library_link::expr::Expr {
+                #parameter_type
+            }
+        }
+    };
+
+    Ok(output)
+}
+
+//======================================
+// #[derive(IntoArg)]
+//======================================
+
+pub(crate) fn derive_into_arg(input: DeriveInput) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+
+    let mut generics = input.generics.clone();
+
+    let kind = derive_kind(&input, "IntoArg", &["data_store"])?;
+
+    let is_data_store = matches!(kind, DeriveKind::DataStore);
+
+    let (into_arg, return_type) = match kind {
+        DeriveKind::Newtype { member, ty } => {
+            if !input.generics.params.is_empty() {
+                generics
+                    .make_where_clause()
+                    .predicates
+                    .push(syn::parse_quote! {
+                        #ty: ::wolfram_library_link::IntoArg
+                    });
+            }
+
+            let into_arg = quote! {
+                ::wolfram_library_link::IntoArg::into_arg(self.#member, arg)
+            };
+
+            let return_type = quote! {
+                <#ty as ::wolfram_library_link::IntoArg>::return_type()
+            };
+
+            (into_arg, return_type)
+        },
+        DeriveKind::Enum(variants) => match variants.repr {
+            EnumRepr::String => {
+                let arms = variants.names.iter().map(|(ident, wl_name)| {
+                    quote! { #name::#ident => #wl_name, }
+                });
+
+                let into_arg = quote! {
+                    let value: &'static str = match self {
+                        #(#arms)*
+                    };
+
+                    ::wolfram_library_link::IntoArg::into_arg(
+                        ::std::string::String::from(value),
+                        arg,
+                    )
+                };
+
+                let return_type = quote! {
+                    <::std::string::String as ::wolfram_library_link::IntoArg>::return_type()
+                };
+
+                (into_arg, return_type)
+            },
+            EnumRepr::Integer => {
+                let into_arg = quote! {
+                    ::wolfram_library_link::IntoArg::into_arg(
+                        self as ::wolfram_library_link::sys::mint,
+                        arg,
+                    )
+                };
+
+                let return_type = quote! {
+                    <::wolfram_library_link::sys::mint as ::wolfram_library_link::IntoArg>::return_type()
+                };
+
+                (into_arg, return_type)
+            },
+        },
+        DeriveKind::DataStore => {
+            let (_, ty_generics, _) = input.generics.split_for_impl();
+
+            if !input.generics.params.is_empty() {
+                generics
+                    .make_where_clause()
+                    .predicates
+                    .push(syn::parse_quote! {
+                        #name #ty_generics: ::wolfram_library_link::IntoDataStore
+                    });
+            }
+
+            let into_arg = quote! {
+                ::wolfram_library_link::IntoArg::into_arg(
+                    ::wolfram_library_link::IntoDataStore::into_data_store(self),
+                    arg,
+                )
+            };
+
+            let return_type = quote! {
+                <::wolfram_library_link::DataStore as ::wolfram_library_link::IntoArg>::return_type()
+            };
+
+            (into_arg, return_type)
+        },
+    };
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let mut output = quote! {
+        impl #impl_generics ::wolfram_library_link::IntoArg for #name #ty_generics
+            #where_clause
+        {
+            unsafe fn into_arg(self, arg: ::wolfram_library_link::sys::MArgument) {
+                #into_arg
+            }
+
+            fn return_type() -> ::wolfram_library_link::expr::Expr {
+                #return_type
+            }
+        }
+    };
+
+    // Allow this type to be returned from an `#[export(unpack = "...")]` function.
+    if is_data_store {
+        output.extend(quote! {
+            impl #impl_generics ::wolfram_library_link::macro_utils::ReturnsDataStore
+                for #name #ty_generics
+                #where_clause
+            {
+            }
+        });
+    }
+
+    Ok(output)
+}
+
+//======================================
+// Utilities
+//======================================
+
+enum DeriveKind<'a> {
+    /// A struct with exactly one field, which is passed as its inner type.
+    Newtype { member: Member, ty: &'a Type },
+    /// An enum whose variants have no fields.
+    Enum(EnumVariants),
+    /// A struct with the `#[arg(data_store)]` attribute, which is passed as the
+    /// `DataStore` constructed by its `IntoDataStore` implementation.
+    DataStore,
+}
+
+struct EnumVariants {
+    repr: EnumRepr,
+    /// The Rust name of each variant, and the Wolfram Language string used for it.
+    names: Vec<(Ident, String)>,
+}
+
+/// How a fieldless enum is passed: `#[arg(string)]` (the default) or `#[arg(integer)]`.
+enum EnumRepr {
+    String,
+    Integer,
+}
+
+impl EnumVariants {
+    /// Description of the valid values, used in the error for an unknown value.
+    fn expected(&self) -> String {
+        let values: Vec<String> = match self.repr {
+            EnumRepr::String => self
+                .names
+                .iter()
+                .map(|(_, wl_name)| format!("{:?}", wl_name))
+                .collect(),
+            EnumRepr::Integer => {
+                return format!(
+                    "an integer value of one of the {} variants",
+                    self.names.len()
+                )
+            },
+        };
+
+        format!("one of {}", values.join(", "))
+    }
+}
+
+/// `struct_attrs` are the `#[arg(...)]` arguments that are allowed on a struct.
+fn derive_kind<'a>(
+    input: &'a DeriveInput,
+    trait_name: &str,
+    struct_attrs: &[&str],
+) -> Result<DeriveKind<'a>, Error> {
+    match input.data {
+        Data::Struct(ref data) => {
+            // `data_store` is the only argument that can be allowed on a struct.
+            match parse_arg_attrs(&input.attrs, struct_attrs)?.as_slice() {
+                [] => (),
+                [(_, None)] => return Ok(DeriveKind::DataStore),
+                [(ident, _), ..] => {
+                    return Err(Error::new(ident.span(), "expected `#[arg(data_store)]`"))
+                },
+            }
+
+            let mut fields = data.fields.iter();
+
+            let field = match (fields.next(), fields.next()) {
+                (Some(field), None) => field,
+                _ => return Err(newtype_error(&data.fields, trait_name)),
+            };
+
+            let member = match field.ident {
+                Some(ref ident) => Member::Named(ident.clone()),
+                None => Member::Unnamed(Index {
+                    index: 0,
+                    span: field.span(),
+                }),
+            };
+
+            Ok(DeriveKind::Newtype {
+                member,
+                ty: &field.ty,
+            })
+        },
+        Data::Enum(ref data) => {
+            Ok(DeriveKind::Enum(enum_variants(input, data, trait_name)?))
+        },
+        Data::Union(ref data) => Err(Error::new(
+            data.union_token.span(),
+            format!(
+                "`{}` can only be derived for structs with one field and enums",
+                trait_name
+            ),
+        )),
+    }
+}
+
+fn newtype_error(fields: &Fields, trait_name: &str) -> Error {
+    let span = match fields {
+        Fields::Unit => proc_macro2::Span::call_site(),
+        _ => fields.span(),
+    };
+
+    Error::new(
+        span,
+        format!(
+            "`{}` can only be derived for structs with exactly one field",
+            trait_name
+        ),
+    )
+}
+
+fn enum_variants(
+    input: &DeriveInput,
+    data: &DataEnum,
+    trait_name: &str,
+) -> Result<EnumVariants, Error> {
+    let repr = match parse_arg_attrs(&input.attrs, &["string", "integer"])?.as_slice() {
+        [] => EnumRepr::String,
+        [(ident, None)] if ident == "string" => EnumRepr::String,
+        [(ident, None)] if ident == "integer" => EnumRepr::Integer,
+        [(ident, _), ..] => {
+            return Err(Error::new(
+                ident.span(),
+                "expected one of `#[arg(string)]` or `#[arg(integer)]`",
+            ))
+        },
+    };
+
+    if let Some(param) = input.generics.params.first() {
+        return Err(Error::new(
+            param.span(),
+            format!("`{}` cannot be derived for generic enums", trait_name),
+        ));
+    }
+
+    if data.variants.is_empty() {
+        return Err(Error::new(
+            data.enum_token.span(),
+            format!(
+                "`{}` cannot be derived for enums with no variants",
+                trait_name
+            ),
+        ));
+    }
+
+    let mut names: Vec<(Ident, String)> = Vec::new();
+
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(Error::new(
+                variant.fields.span(),
+                format!(
+                    "`{}` can only be derived for enums whose variants have no fields",
+                    trait_name
+                ),
+            ));
+        }
+
+        let mut wl_name = variant
+            .ident
+            .to_string()
+            .trim_start_matches("r#")
+            .to_owned();
+
+        for (ident, value) in parse_arg_attrs(&variant.attrs, &["name"])? {
+            match (value, &repr) {
+                (Some(value), EnumRepr::String) => wl_name = value,
+                (Some(_), EnumRepr::Integer) => {
+                    return Err(Error::new(
+                        ident.span(),
+                        "`#[arg(name = \"...\")]` cannot be used with `#[arg(integer)]`",
+                    ))
+                },
+                (None, _) => {
+                    return Err(Error::new(ident.span(), "expected `name = \"...\"`"))
+                },
+            }
+        }
+
+        if names.iter().any(|(_, other)| *other == wl_name) {
+            return Err(Error::new(
+                variant.span(),
+                format!("duplicate variant name {:?}", wl_name),
+            ));
+        }
+
+        names.push((variant.ident.clone(), wl_name));
+    }
+
+    Ok(EnumVariants { repr, names })
+}
+
+/// Parse the arguments of any `#[arg(...)]` attributes, which must be one of `allowed`.
+///
+/// Returns the name of each argument, and its value if it has the form
+/// `name = "value"`.
+fn parse_arg_attrs(
+    attrs: &[Attribute],
+    allowed: &[&str],
+) -> Result<Vec<(Ident, Option<String>)>, Error> {
+    let mut args = Vec::new();
+
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("arg")) {
+        let nested = match attr.parse_meta()? {
+            Meta::List(list) => list.nested,
+            meta => return Err(Error::new(meta.span(), "expected `#[arg(...)]`")),
+        };
+
+        for meta in nested {
+            let (path, value) = match meta {
+                NestedMeta::Meta(Meta::Path(ref path)) => (path.clone(), None),
+                NestedMeta::Meta(Meta::NameValue(syn::MetaNameValue {
+                    ref path,
+                    lit: Lit::Str(ref value),
+                    ..
+                })) => (path.clone(), Some(value.value())),
+                _ => {
+                    return Err(Error::new(
+                        meta.span(),
+                        "unrecognized `arg` attribute argument",
+                    ))
+                },
+            };
+
+            let ident = match path.get_ident() {
+                Some(ident) if allowed.iter().any(|allowed| ident == allowed) => {
+                    ident.clone()
+                },
+                _ => {
+                    return Err(Error::new(
+                        path.span(),
+                        "unrecognized `arg` attribute argument",
+                    ))
+                },
+            };
+
+            args.push((ident, value));
+        }
+    }
+
+    Ok(args)
+}
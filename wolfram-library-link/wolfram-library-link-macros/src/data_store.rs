@@ -0,0 +1,233 @@
+use proc_macro2::TokenStream as TokenStream2;
+
+use quote::quote;
+use syn::{spanned::Spanned, Data, DeriveInput, Error, Fields, Index, Member};
+
+//======================================
+// #[derive(IntoDataStore)]
+//======================================
+
+pub(crate) fn derive_into_data_store(input: DeriveInput) -> Result<TokenStream2, Error> {
+    let fields = struct_fields(&input, "IntoDataStore")?;
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    // Add each field to the data store, using the field name as the node name for
+    // structs with named fields.
+    let add_fields = fields.iter().map(|field| {
+        let member = &field.member;
+
+        let node_name = match field.node_name {
+            Some(ref node_name) => quote! { ::std::option::Option::Some(#node_name) },
+            None => quote! { ::std::option::Option::None },
+        };
+
+        quote! {
+            ::wolfram_library_link::IntoDataStoreNode::add_to_data_store(
+                self.#member,
+                &mut store,
+                #node_name,
+            );
+        }
+    });
+
+    let output = quote! {
+        impl #impl_generics ::wolfram_library_link::IntoDataStore for #name #ty_generics
+            #where_clause
+        {
+            fn into_data_store(self) -> ::wolfram_library_link::DataStore {
+                let mut store = ::wolfram_library_link::DataStore::new();
+
+                #(#add_fields)*
+
+                store
+            }
+        }
+
+        impl #impl_generics ::wolfram_library_link::IntoDataStoreNode for #name #ty_generics
+            #where_clause
+        {
+            fn add_to_data_store(
+                self,
+                store: &mut ::wolfram_library_link::DataStore,
+                name: ::std::option::Option<&str>,
+            ) {
+                ::wolfram_library_link::IntoDataStoreNode::add_to_data_store(
+                    ::wolfram_library_link::IntoDataStore::into_data_store(self),
+                    store,
+                    name,
+                )
+            }
+        }
+    };
+
+    Ok(output)
+}
+
+//======================================
+// #[derive(FromDataStore)]
+//======================================
+
+pub(crate) fn derive_from_data_store(input: DeriveInput) -> Result<TokenStream2, Error> {
+    let fields = struct_fields(&input, "FromDataStore")?;
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let body = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(_) => {
+                let field_values = fields.iter().map(|field| {
+                    let member = &field.member;
+                    let node_name = &field.node_name;
+
+                    quote! {
+                        #member: ::wolfram_library_link::macro_utils::data_store_named_node(
+                            &nodes,
+                            #node_name,
+                        )?
+                    }
+                });
+
+                // Look up every field in a single pass over the store. Structs without
+                // fields don't read the store at all.
+                let nodes = if fields.is_empty() {
+                    quote! {}
+                } else {
+                    quote! {
+                        let nodes =
+                            ::wolfram_library_link::macro_utils::data_store_named_nodes(store);
+                    }
+                };
+
+                quote! {
+                    #nodes
+
+                    ::std::result::Result::Ok(#name {
+                        #(#field_values),*
+                    })
+                }
+            },
+            Fields::Unnamed(_) => {
+                let count = fields.len();
+
+                let field_values = (0..count).map(|index| {
+                    quote! {
+                        ::wolfram_library_link::macro_utils::data_store_positional_node(
+                            #index,
+                            &nodes[#index],
+                        )?
+                    }
+                });
+
+                quote! {
+                    let nodes = ::wolfram_library_link::macro_utils::data_store_nodes_exact(
+                        store,
+                        #count,
+                    )?;
+
+                    ::std::result::Result::Ok(#name(#(#field_values),*))
+                }
+            },
+            Fields::Unit => quote! {
+                ::wolfram_library_link::macro_utils::data_store_nodes_exact(store, 0)?;
+
+                ::std::result::Result::Ok(#name)
+            },
+        },
+        Data::Enum(_) | Data::Union(_) => unreachable!(),
+    };
+
+    let output = quote! {
+        impl #impl_generics ::wolfram_library_link::FromDataStore for #name #ty_generics
+            #where_clause
+        {
+            fn from_data_store(
+                store: &::wolfram_library_link::DataStore,
+            ) -> ::std::result::Result<Self, ::wolfram_library_link::FromDataStoreError> {
+                #body
+            }
+        }
+
+        impl #impl_generics ::wolfram_library_link::FromDataStoreNode for #name #ty_generics
+            #where_clause
+        {
+            fn from_data_store_node(
+                node: &::wolfram_library_link::DataStoreNode,
+            ) -> ::std::result::Result<Self, ::wolfram_library_link::FromDataStoreError> {
+                let store =
+                    ::wolfram_library_link::macro_utils::data_store_node_as_data_store(node)?;
+
+                ::wolfram_library_link::FromDataStore::from_data_store(store)
+            }
+        }
+    };
+
+    Ok(output)
+}
+
+//======================================
+// Utilities
+//======================================
+
+struct StructField {
+    member: Member,
+    /// The name of the corresponding data store node, or `None` if this is a field of a
+    /// tuple struct.
+    node_name: Option<String>,
+}
+
+fn struct_fields(
+    input: &DeriveInput,
+    trait_name: &str,
+) -> Result<Vec<StructField>, Error> {
+    let data = match input.data {
+        Data::Struct(ref data) => data,
+        Data::Enum(ref data) => {
+            return Err(Error::new(
+                data.enum_token.span(),
+                format!("`{}` can only be derived for structs", trait_name),
+            ))
+        },
+        Data::Union(ref data) => {
+            return Err(Error::new(
+                data.union_token.span(),
+                format!("`{}` can only be derived for structs", trait_name),
+            ))
+        },
+    };
+
+    let fields = match data.fields {
+        Fields::Named(ref fields) => fields
+            .named
+            .iter()
+            .map(|field| {
+                let ident = field.ident.clone().unwrap();
+
+                // Strip the `r#` prefix from raw identifiers.
+                let node_name = ident.to_string().trim_start_matches("r#").to_owned();
+
+                StructField {
+                    member: Member::Named(ident),
+                    node_name: Some(node_name),
+                }
+            })
+            .collect(),
+        Fields::Unnamed(ref fields) => fields
+            .unnamed
+            .iter()
+            .enumerate()
+            .map(|(index, field)| StructField {
+                member: Member::Unnamed(Index {
+                    index: index as u32,
+                    span: field.span(),
+                }),
+                node_name: None,
+            })
+            .collect(),
+        Fields::Unit => Vec::new(),
+    };
+
+    Ok(fields)
+}
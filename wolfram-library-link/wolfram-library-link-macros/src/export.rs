@@ -1,7 +1,7 @@
 use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
 
-use quote::quote;
+use quote::{quote, quote_spanned};
 use syn::{spanned::Spanned, Error, Ident, Item, Meta, NestedMeta};
 
 //======================================
@@ -91,8 +91,10 @@ pub(crate) fn export(
 
     let ExportArgs {
         use_wstp,
+        use_async_task,
         exported_name,
         hidden,
+        unpack,
     } = parse_export_attribute_args(attrs)?;
 
     //--------------------------------------------------------------------
@@ -115,12 +117,44 @@ pub(crate) fn export(
     // Validate the user `func`
     //-------------------------
 
-    // No `async`
+    // `async` is only supported for native functions.
     if let Some(async_) = func.sig.asyncness {
-        return Err(Error::new(
-            async_.span(),
-            "exported function cannot be `async`",
-        ));
+        if use_wstp {
+            return Err(Error::new(
+                async_.span(),
+                "`#[export(wstp)]` function cannot be `async`",
+            ));
+        }
+
+        if use_async_task {
+            return Err(Error::new(
+                async_.span(),
+                "`#[export(async)]` function cannot be `async`",
+            ));
+        }
+
+        if !cfg!(feature = "futures") {
+            return Err(Error::new(
+                async_.span(),
+                "exporting an `async fn` requires the `futures` feature of \
+                wolfram-library-link",
+            ));
+        }
+
+        // The returned future is run on another thread, so it must be `'static`, which
+        // rules out any parameter that borrows from the function arguments.
+        for param in &func.sig.inputs {
+            if let syn::FnArg::Typed(syn::PatType { ty, .. }) = param {
+                if let Some(span) = find_borrow(ty) {
+                    return Err(Error::new(
+                        span,
+                        "exported `async fn` parameters must be owned types, like \
+                        `String` instead of `&str`, because the returned future must be \
+                        `'static`",
+                    ));
+                }
+            }
+        }
     }
 
     // No generics
@@ -141,9 +175,32 @@ pub(crate) fn export(
     let params = func.sig.inputs.clone();
 
     let wrapper = if use_wstp {
-        export_wstp_function(&name, &exported_name, params, hidden)
+        let returns_iterator = returns_impl_iterator(&func.sig.output)?;
+
+        export_wstp_function(&name, &exported_name, params, returns_iterator, hidden)
+    } else if use_async_task {
+        // The first parameter is the `AsyncTaskObject`.
+        if params.is_empty() {
+            return Err(Error::new(
+                func.sig.paren_token.span,
+                "`#[export(async)]` function must take an `AsyncTaskObject` as its first \
+                parameter",
+            ));
+        }
+
+        export_async_function(&name, &exported_name, params.len() - 1, hidden)
     } else {
-        export_native_function(&name, &exported_name, params.len(), hidden)
+        let is_async = func.sig.asyncness.is_some();
+
+        export_native_function(
+            &name,
+            &exported_name,
+            params.len(),
+            is_async,
+            hidden,
+            unpack.as_ref(),
+            &func.sig.output,
+        )
     };
 
     let output = quote! {
@@ -164,10 +221,21 @@ fn export_native_function(
     name: &Ident,
     exported_name: &Ident,
     parameter_count: usize,
+    is_async: bool,
     hidden: bool,
+    unpack: Option<&Ident>,
+    output: &syn::ReturnType,
 ) -> TokenStream2 {
     let params = vec![quote! { _ }; parameter_count];
 
+    // For an `async fn`, the returned future is run to completion by the `BlockOn`
+    // wrapper.
+    let wrapped_func = if is_async {
+        quote! { ::wolfram_library_link::macro_utils::BlockOn(func) }
+    } else {
+        quote! { func }
+    };
+
     let mut tokens = quote! {
         mod #name {
             #[no_mangle]
@@ -186,14 +254,38 @@ fn export_native_function(
                     args,
                     argc,
                     res,
-                    func
+                    #wrapped_func
                 )
             }
         }
 
     };
 
+    // Only a `DataStore` result can be unpacked by the loader, so check the return type
+    // at compile time.
+    if unpack.is_some() {
+        let return_ty = match output {
+            syn::ReturnType::Default => quote! { () },
+            syn::ReturnType::Type(_, ty) => quote! { #ty },
+        };
+
+        tokens.extend(quote_spanned! { output.span()=>
+            const _: fn() = || {
+                ::wolfram_library_link::macro_utils::assert_data_store_return::<#return_ty>();
+            };
+        });
+    }
+
     if !hidden && cfg!(feature = "automate-function-loading-boilerplate") {
+        let unpack = match unpack {
+            Some(unpack) => quote! {
+                ::std::option::Option::Some(
+                    ::wolfram_library_link::macro_utils::Unpack::#unpack
+                )
+            },
+            None => quote! { ::std::option::Option::None },
+        };
+
         tokens.extend(quote! {
             // Register this exported function.
             ::wolfram_library_link::inventory::submit! {
@@ -201,10 +293,12 @@ fn export_native_function(
                     name: stringify!(#exported_name),
                     signature: || {
                         let func: fn(#(#params),*) -> _ = #name;
-                        let func: &dyn ::wolfram_library_link::NativeFunction<'_> = &func;
+                        let func: &dyn ::wolfram_library_link::NativeFunction<'_> =
+                            &#wrapped_func;
 
                         func.signature()
-                    }
+                    },
+                    unpack: #unpack,
                 }
             }
         });
@@ -213,6 +307,40 @@ fn export_native_function(
     tokens
 }
 
+/// Returns the span of the first reference type or lifetime that appears in `ty`, if
+/// any.
+fn find_borrow(ty: &syn::Type) -> Option<proc_macro2::Span> {
+    match ty {
+        syn::Type::Reference(reference) => Some(reference.span()),
+        syn::Type::Array(array) => find_borrow(&array.elem),
+        syn::Type::Slice(slice) => find_borrow(&slice.elem),
+        syn::Type::Group(group) => find_borrow(&group.elem),
+        syn::Type::Paren(paren) => find_borrow(&paren.elem),
+        syn::Type::Tuple(tuple) => tuple.elems.iter().find_map(find_borrow),
+        syn::Type::Path(path) => {
+            let args =
+                path.path
+                    .segments
+                    .iter()
+                    .filter_map(|segment| match segment.arguments {
+                        syn::PathArguments::AngleBracketed(ref args) => Some(&args.args),
+                        _ => None,
+                    });
+
+            args.flatten().find_map(|arg| match arg {
+                syn::GenericArgument::Lifetime(lifetime)
+                    if lifetime.ident != "static" =>
+                {
+                    Some(lifetime.span())
+                },
+                syn::GenericArgument::Type(ty) => find_borrow(ty),
+                _ => None,
+            })
+        },
+        _ => None,
+    }
+}
+
 //--------------------------------------
 // #[export(wstp): export WstpFunction
 //--------------------------------------
@@ -221,14 +349,30 @@ fn export_wstp_function(
     name: &Ident,
     exported_name: &Ident,
     parameter_tys: syn::punctuated::Punctuated<syn::FnArg, syn::token::Comma>,
+    returns_iterator: bool,
     hidden: bool,
 ) -> TokenStream2 {
     // let params = vec![quote! { _ }; parameter_count];
 
+    // Only the parameter types can be used in the `fn(..)` type, not patterns like
+    // `mut args`.
+    let parameter_tys = parameter_tys.iter().map(|param| match param {
+        syn::FnArg::Typed(syn::PatType { ty, .. }) => quote! { #ty },
+        syn::FnArg::Receiver(receiver) => quote! { #receiver },
+    });
+
+    // The elements of a returned iterator are written to the link one at a time by the
+    // `StreamList` wrapper.
+    let wrapped_func = if returns_iterator {
+        quote! { ::wolfram_library_link::macro_utils::StreamList(func) }
+    } else {
+        quote! { func }
+    };
+
     let mut tokens = quote! {
         mod #name {
             // Ensure that types imported into the enclosing parent module can be used in
-            // the expansion of $argc. Always `Link` or `Vec<Expr>` at the moment.
+            // the expansion of $argc. Always `Link`, `Vec<Expr>`, or `Args` at the moment.
             use super::*;
 
             #[no_mangle]
@@ -240,7 +384,7 @@ fn export_wstp_function(
                 // generic `fn(...)` type.
                 // The number of arguments is required for type inference of the variadic
                 // `fn(..) -> _` type to work. See constraint 2a.
-                let func: fn(#parameter_tys) -> _ = super::#name;
+                let func: fn(#(#parameter_tys),*) -> _ = super::#name;
 
                 // TODO: Why does this code work:
                 //   let func: fn(&mut _) = super::$name;
@@ -250,7 +394,7 @@ fn export_wstp_function(
                 ::wolfram_library_link::macro_utils::call_wstp_wolfram_library_function(
                     lib,
                     raw_link,
-                    func
+                    #wrapped_func
                 )
             }
 
@@ -269,6 +413,114 @@ fn export_wstp_function(
     tokens
 }
 
+/// Returns `true` if `output` is an `impl Trait` type with an `ExactSizeIterator` bound,
+/// e.g. `impl ExactSizeIterator<Item = T>`.
+///
+/// Returns an error if `output` is an `impl Trait` type with some other `..Iterator`
+/// bound, like `impl Iterator<Item = T>`, because the length of the returned `List` must
+/// be known before its elements are written.
+fn returns_impl_iterator(output: &syn::ReturnType) -> Result<bool, Error> {
+    let ty = match output {
+        syn::ReturnType::Type(_, ty) => ty,
+        syn::ReturnType::Default => return Ok(false),
+    };
+
+    let bounds = match **ty {
+        syn::Type::ImplTrait(ref impl_trait) => &impl_trait.bounds,
+        _ => return Ok(false),
+    };
+
+    let iterator_bounds = bounds.iter().filter_map(|bound| match bound {
+        syn::TypeParamBound::Trait(bound) => bound.path.segments.last(),
+        _ => None,
+    });
+
+    let mut other_iterator = None;
+
+    for segment in iterator_bounds {
+        if segment.ident == "ExactSizeIterator" {
+            return Ok(true);
+        }
+
+        if segment.ident.to_string().ends_with("Iterator") {
+            other_iterator = Some(segment);
+        }
+    }
+
+    match other_iterator {
+        Some(segment) => Err(Error::new(
+            segment.ident.span(),
+            format!(
+                "`#[export(wstp)]` function cannot return `impl {}`; return \
+                `impl ExactSizeIterator<Item = T>` so that the length of the returned \
+                List is known before it is written",
+                segment.ident
+            ),
+        )),
+        None => Ok(false),
+    }
+}
+
+//--------------------------------------
+// #[export(async)]: export AsyncFunction
+//--------------------------------------
+
+fn export_async_function(
+    name: &Ident,
+    exported_name: &Ident,
+    parameter_count: usize,
+    hidden: bool,
+) -> TokenStream2 {
+    // The task arguments, not including the leading `AsyncTaskObject` parameter.
+    let params = vec![quote! { _ }; parameter_count];
+
+    let mut tokens = quote! {
+        mod #name {
+            #[no_mangle]
+            pub unsafe extern "C" fn #exported_name(
+                lib: ::wolfram_library_link::sys::WolframLibraryData,
+                argc: ::wolfram_library_link::sys::mint,
+                args: *mut ::wolfram_library_link::sys::MArgument,
+                res: ::wolfram_library_link::sys::MArgument,
+            ) -> std::os::raw::c_int {
+                // Cast away the unique `fn(...) {some_name}` function type to get the
+                // generic `fn(...)` type.
+                let func: fn(::wolfram_library_link::AsyncTaskObject #(, #params)*) =
+                    super::#name;
+
+                ::wolfram_library_link::macro_utils::call_async_wolfram_library_function(
+                    lib,
+                    args,
+                    argc,
+                    res,
+                    func
+                )
+            }
+        }
+
+    };
+
+    if !hidden && cfg!(feature = "automate-function-loading-boilerplate") {
+        tokens.extend(quote! {
+            // Register this exported function.
+            ::wolfram_library_link::inventory::submit! {
+                ::wolfram_library_link::macro_utils::LibraryLinkFunction::Async {
+                    name: stringify!(#exported_name),
+                    signature: || {
+                        let func: fn(::wolfram_library_link::AsyncTaskObject #(, #params)*) =
+                            #name;
+                        let func: &dyn ::wolfram_library_link::AsyncFunction = &func;
+
+                        func.signature()
+                    }
+                }
+            }
+        });
+    }
+
+    tokens
+}
+
 //======================================
 // Parse `#[export(<attrs>)]` arguments
 //======================================
@@ -277,6 +529,8 @@ fn export_wstp_function(
 struct ExportArgs {
     /// `#[export(wstp)]`
     use_wstp: bool,
+    /// `#[export(async)]`, or its alias `#[export(async_task)]`
+    use_async_task: bool,
     /// `#[export(name = "...")]`
     exported_name: Option<Ident>,
     /// `#[export(hidden)]`
@@ -284,12 +538,19 @@ struct ExportArgs {
     /// If set, this exported function will not have an automatic loader entry generated
     /// for it.
     hidden: bool,
+    /// `#[export(unpack = "List" | "Association")]`
+    ///
+    /// The name of the `macro_utils::Unpack` variant used by the automatic loader to
+    /// convert the `DataStore` returned by this function.
+    unpack: Option<Ident>,
 }
 
 fn parse_export_attribute_args(attrs: syn::AttributeArgs) -> Result<ExportArgs, Error> {
     let mut use_wstp = false;
+    let mut use_async_task = false;
     let mut hidden = false;
     let mut exported_name: Option<Ident> = None;
+    let mut unpack: Option<Ident> = None;
 
     for attr in attrs {
         match attr {
@@ -304,6 +565,18 @@ fn parse_export_attribute_args(attrs: syn::AttributeArgs) -> Result<ExportArgs,
 
                     use_wstp = true;
                 },
+                Meta::Path(path)
+                    if path.is_ident("async") || path.is_ident("async_task") =>
+                {
+                    if use_async_task {
+                        return Err(Error::new(
+                            attr.span(),
+                            "duplicate export `async` attribute argument",
+                        ));
+                    }
+
+                    use_async_task = true;
+                },
                 Meta::Path(path) if path.is_ident("hidden") => {
                     if hidden {
                         return Err(Error::new(
@@ -349,6 +622,28 @@ fn parse_export_attribute_args(attrs: syn::AttributeArgs) -> Result<ExportArgs,
                                 // Use the correct span for this error.
                                 .map_err(|err| Error::new(lit_str.span(), err))?,
                         );
+                    } else if path.is_ident("unpack") {
+                        if unpack.is_some() {
+                            return Err(Error::new(
+                                attr.span(),
+                                "duplicate definition for `unpack`",
+                            ));
+                        }
+
+                        unpack = match lit {
+                            syn::Lit::Str(str)
+                                if str.value() == "List"
+                                    || str.value() == "Association" =>
+                            {
+                                Some(Ident::new(&str.value(), str.span()))
+                            },
+                            _ => {
+                                return Err(Error::new(
+                                    lit.span(),
+                                    "expected `unpack = \"List\"` or `unpack = \"Association\"`",
+                                ))
+                            },
+                        };
                     } else {
                         return Err(Error::new(
                             path.span(),
@@ -366,9 +661,27 @@ fn parse_export_attribute_args(attrs: syn::AttributeArgs) -> Result<ExportArgs,
         }
     }
 
+    if use_wstp && use_async_task {
+        return Err(Error::new(
+            proc_macro2::Span::call_site(),
+            "export `wstp` and `async` attribute arguments cannot be used together",
+        ));
+    }
+
+    if let Some(ref unpack) = unpack {
+        if use_wstp || use_async_task {
+            return Err(Error::new(
+                unpack.span(),
+                "export `unpack` attribute argument can only be used with native functions",
+            ));
+        }
+    }
+
     Ok(ExportArgs {
         use_wstp,
+        use_async_task,
         exported_name,
         hidden,
+        unpack,
     })
 }
@@ -0,0 +1,578 @@
+//! Implementation of the `expr_match!` macro, which matches an `Expr` against Wolfram
+//! Language patterns and binds the matched parts to Rust variables.
+
+use std::collections::HashSet;
+
+use proc_macro2::{Ident, Span, TokenStream as TokenStream2, TokenTree};
+
+use quote::{format_ident, quote};
+use syn::{
+    braced,
+    parse::{Parse, ParseStream},
+    spanned::Spanned,
+    Error, Token,
+};
+
+use crate::wl::{self, Node};
+
+pub(crate) fn expr_match(input: ExprMatchInput) -> Result<TokenStream2, Error> {
+    let ExprMatchInput { scrutinee, arms } = input;
+
+    if arms.is_empty() {
+        return Err(Error::new(
+            Span::call_site(),
+            "expected at least one `pattern => expression` arm",
+        ));
+    }
+
+    let expr_var = Ident::new("__expr", Span::mixed_site());
+
+    let mut patterns = Vec::new();
+    let mut compiled_arms = Vec::new();
+
+    for arm in arms {
+        let pattern = wl::parse(arm.pattern, arm.arrow_span)?;
+
+        let mut compiler = PatternCompiler::new();
+        let statements = compiler.compile(&pattern, &expr_var)?;
+
+        patterns.push(display(&pattern));
+        compiled_arms.push((compiler.bindings, statements, arm.body));
+    }
+
+    // Build the arms from last to first, so that each arm is only tried if all of the
+    // previous arms failed to match.
+    let mut output = quote! {
+        ::std::result::Result::Err(
+            ::wolfram_library_link::macro_utils::expr_match_error(
+                #expr_var,
+                &[#(#patterns),*],
+            )
+        )
+    };
+
+    let label = syn::Lifetime::new("'__wll_expr_match", Span::mixed_site());
+    let arm_var = Ident::new("__arm", Span::mixed_site());
+    let value_var = Ident::new("__value", Span::mixed_site());
+
+    for (bindings, statements, body) in compiled_arms.into_iter().rev() {
+        output = quote! {
+            #[allow(unused_labels)]
+            let #arm_var = #label: {
+                #statements
+
+                ::std::option::Option::Some((#(#bindings,)*))
+            };
+
+            match #arm_var {
+                ::std::option::Option::Some((#(#bindings,)*)) => {
+                    let #value_var = #body;
+
+                    // Don't warn about the `Ok(..)` wrapper if `body` diverges, e.g.
+                    // because it uses `return`.
+                    #[allow(unreachable_code)]
+                    let #value_var = ::std::result::Result::Ok(#value_var);
+
+                    #value_var
+                },
+                ::std::option::Option::None => {
+                    #output
+                },
+            }
+        };
+    }
+
+    Ok(quote! {
+        {
+            let #expr_var: &::wolfram_library_link::expr::Expr = #scrutinee;
+
+            #output
+        }
+    })
+}
+
+//======================================
+// Input
+//======================================
+
+pub(crate) struct ExprMatchInput {
+    scrutinee: syn::Expr,
+    arms: Vec<MatchArm>,
+}
+
+struct MatchArm {
+    pattern: TokenStream2,
+    arrow_span: Span,
+    body: syn::Expr,
+}
+
+impl Parse for ExprMatchInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let scrutinee: syn::Expr = input.parse()?;
+        let _: Token![,] = input.parse()?;
+
+        let content;
+        let _ = braced!(content in input);
+
+        let mut arms = Vec::new();
+
+        while !content.is_empty() {
+            let mut pattern = TokenStream2::new();
+
+            while !content.peek(Token![=>]) {
+                if content.is_empty() {
+                    return Err(content.error("expected `=>`"));
+                }
+
+                pattern.extend(Some(content.parse::<TokenTree>()?));
+            }
+
+            let arrow: Token![=>] = content.parse()?;
+            let body: syn::Expr = content.parse()?;
+
+            // Like `match` arms, a comma is only required after bodies that are not
+            // blocks.
+            let is_block_like = matches!(
+                body,
+                syn::Expr::Block(_)
+                    | syn::Expr::If(_)
+                    | syn::Expr::Match(_)
+                    | syn::Expr::Loop(_)
+                    | syn::Expr::While(_)
+                    | syn::Expr::ForLoop(_)
+                    | syn::Expr::Unsafe(_)
+            );
+
+            arms.push(MatchArm {
+                pattern,
+                arrow_span: arrow.span(),
+                body,
+            });
+
+            if content.is_empty() {
+                break;
+            }
+
+            if is_block_like {
+                let _: Option<Token![,]> = content.parse()?;
+            } else {
+                let _: Token![,] = content.parse()?;
+            }
+        }
+
+        let _: Option<Token![,]> = input.parse()?;
+
+        Ok(ExprMatchInput { scrutinee, arms })
+    }
+}
+
+//======================================
+// Pattern compilation
+//======================================
+
+/// Compiles a pattern into statements that bind the pattern variables, or
+/// `break '__wll_expr_match None` if the expression does not match.
+struct PatternCompiler {
+    bindings: Vec<Ident>,
+    binding_names: HashSet<String>,
+    /// Counter used to generate unique names for temporary variables.
+    next_var: usize,
+}
+
+/// A sequence pattern, like `x__` or `___Rule`, that can match any number of
+/// consecutive elements.
+struct SequencePattern<'a> {
+    name: Option<(String, Span)>,
+    min_length: usize,
+    head: Option<&'a Node>,
+}
+
+impl PatternCompiler {
+    fn new() -> Self {
+        PatternCompiler {
+            bindings: Vec::new(),
+            binding_names: HashSet::new(),
+            next_var: 0,
+        }
+    }
+
+    fn var(&mut self) -> Ident {
+        let var = format_ident!("__e{}", self.next_var, span = Span::mixed_site());
+        self.next_var += 1;
+        var
+    }
+
+    fn bind(&mut self, name: &str, span: Span) -> Result<Ident, Error> {
+        if !self.binding_names.insert(name.to_owned()) {
+            return Err(Error::new(
+                span,
+                format!("pattern name `{}` is used more than once", name),
+            ));
+        }
+
+        let ident = Ident::new(name, span);
+        self.bindings.push(ident.clone());
+        Ok(ident)
+    }
+
+    /// Compile `pattern` into statements that match against `target`, a variable of type
+    /// `&Expr`.
+    fn compile(&mut self, pattern: &Node, target: &Ident) -> Result<TokenStream2, Error> {
+        let mismatch = mismatch();
+
+        if let Some(seq) = sequence_pattern(pattern) {
+            let span = seq.name.map_or_else(Span::call_site, |(_, span)| span);
+
+            return Err(Error::new(
+                span,
+                "sequence patterns can only be used as arguments or list elements",
+            ));
+        }
+
+        if let Some((name, head)) = blank_pattern(pattern, "Blank") {
+            let value = extract(head, target);
+
+            return Ok(match name {
+                Some((name, span)) => {
+                    let ident = self.bind(&name, span)?;
+                    quote! { let #ident = #value; }
+                },
+                None => quote! { let _ = #value; },
+            });
+        }
+
+        let tokens = match pattern {
+            Node::Symbol(name, _) => quote! {
+                match #target.kind() {
+                    ::wolfram_library_link::expr::ExprKind::Symbol(sym)
+                        if sym.as_str() == #name => (),
+                    _ => #mismatch,
+                }
+            },
+            Node::Integer(value) => quote! {
+                match *#target.kind() {
+                    ::wolfram_library_link::expr::ExprKind::Integer(value)
+                        if value == #value => (),
+                    _ => #mismatch,
+                }
+            },
+            Node::Real(value) => quote! {
+                match *#target.kind() {
+                    ::wolfram_library_link::expr::ExprKind::Real(value)
+                        if *value == #value => (),
+                    _ => #mismatch,
+                }
+            },
+            Node::String(value) => quote! {
+                match #target.kind() {
+                    ::wolfram_library_link::expr::ExprKind::String(value)
+                        if value == #value => (),
+                    _ => #mismatch,
+                }
+            },
+            Node::Interpolated(_) => {
+                let value = pattern.to_tokens();
+
+                quote! {
+                    if *#target != #value {
+                        #mismatch
+                    }
+                }
+            },
+            Node::Normal(head, elements) => {
+                let normal = self.var();
+                let head_var = self.var();
+                let elements_var = self.var();
+
+                let head = self.compile(head, &head_var)?;
+                let elements = self.compile_elements(elements, &elements_var)?;
+
+                quote! {
+                    let #normal = match #target.kind() {
+                        ::wolfram_library_link::expr::ExprKind::Normal(normal) => normal,
+                        _ => #mismatch,
+                    };
+
+                    let #head_var: &::wolfram_library_link::expr::Expr = #normal.head();
+                    #head
+
+                    let #elements_var: &[::wolfram_library_link::expr::Expr] =
+                        #normal.elements();
+                    #elements
+                }
+            },
+        };
+
+        Ok(tokens)
+    }
+
+    /// Compile the element patterns of a normal expression, which may contain at most
+    /// one sequence pattern.
+    fn compile_elements(
+        &mut self,
+        patterns: &[Node],
+        target: &Ident,
+    ) -> Result<TokenStream2, Error> {
+        let mismatch = mismatch();
+        let count = patterns.len();
+
+        let sequences: Vec<(usize, SequencePattern)> = patterns
+            .iter()
+            .enumerate()
+            .filter_map(|(index, pattern)| Some((index, sequence_pattern(pattern)?)))
+            .collect();
+
+        let mut output = TokenStream2::new();
+
+        let (seq_index, seq) = match sequences.len() {
+            0 => {
+                output.extend(quote! {
+                    if #target.len() != #count {
+                        #mismatch
+                    }
+                });
+
+                for (index, pattern) in patterns.iter().enumerate() {
+                    let var = self.var();
+                    let matcher = self.compile(pattern, &var)?;
+
+                    output.extend(quote! {
+                        let #var: &::wolfram_library_link::expr::Expr = &#target[#index];
+                        #matcher
+                    });
+                }
+
+                return Ok(output);
+            },
+            1 => sequences.into_iter().next().unwrap(),
+            _ => {
+                let span = sequences[1]
+                    .1
+                    .name
+                    .as_ref()
+                    .map_or_else(Span::call_site, |(_, span)| *span);
+
+                return Err(Error::new(
+                    span,
+                    "only one sequence pattern can be used in each list of elements",
+                ));
+            },
+        };
+
+        // Number of elements after the sequence pattern.
+        let suffix_count = count - seq_index - 1;
+        let min_length = count - 1 + seq.min_length;
+
+        output.extend(quote! {
+            if #target.len() < #min_length {
+                #mismatch
+            }
+        });
+
+        for (index, pattern) in patterns.iter().enumerate() {
+            if index == seq_index {
+                continue;
+            }
+
+            let position = if index < seq_index {
+                quote! { #index }
+            } else {
+                let offset = count - index;
+                quote! { #target.len() - #offset }
+            };
+
+            let var = self.var();
+            let matcher = self.compile(pattern, &var)?;
+
+            output.extend(quote! {
+                let #var: &::wolfram_library_link::expr::Expr = &#target[#position];
+                #matcher
+            });
+        }
+
+        let element = self.var();
+        let value = extract(seq.head, &element);
+
+        let values = quote! {
+            {
+                let mut values = ::std::vec::Vec::new();
+
+                for #element in &#target[#seq_index..#target.len() - #suffix_count] {
+                    values.push(#value);
+                }
+
+                values
+            }
+        };
+
+        output.extend(match seq.name {
+            Some((name, span)) => {
+                let ident = self.bind(&name, span)?;
+                quote! { let #ident = #values; }
+            },
+            None => quote! { let _ = #values; },
+        });
+
+        Ok(output)
+    }
+}
+
+fn mismatch() -> TokenStream2 {
+    let label = syn::Lifetime::new("'__wll_expr_match", Span::mixed_site());
+
+    quote! { break #label ::std::option::Option::None }
+}
+
+/// Returns an expression that evaluates to the value bound by a blank with the
+/// specified `head`, or breaks out of the match if `target` does not have that head.
+///
+/// `_Integer`, `_Real`, `_String`, and `_Symbol` bind `i64`, `f64`, `&str`, and
+/// `&Symbol` values respectively. Other blanks bind the `&Expr` itself.
+fn extract(head: Option<&Node>, target: &Ident) -> TokenStream2 {
+    let mismatch = mismatch();
+
+    let head = match head {
+        Some(Node::Symbol(head, _)) => head.as_str(),
+        Some(_) => unreachable!("blank head is always a symbol"),
+        None => return quote! { #target },
+    };
+
+    match head {
+        "System`Integer" => quote! {
+            match *#target.kind() {
+                ::wolfram_library_link::expr::ExprKind::Integer(value) => value,
+                _ => #mismatch,
+            }
+        },
+        "System`Real" => quote! {
+            match *#target.kind() {
+                ::wolfram_library_link::expr::ExprKind::Real(value) => *value,
+                _ => #mismatch,
+            }
+        },
+        "System`String" => quote! {
+            match #target.kind() {
+                ::wolfram_library_link::expr::ExprKind::String(value) => value.as_str(),
+                _ => #mismatch,
+            }
+        },
+        "System`Symbol" => quote! {
+            match #target.kind() {
+                ::wolfram_library_link::expr::ExprKind::Symbol(value) => value,
+                _ => #mismatch,
+            }
+        },
+        _ => quote! {
+            {
+                if !#target.has_normal_head(
+                    &::wolfram_library_link::expr::Symbol::new(#head)
+                ) {
+                    #mismatch
+                }
+
+                #target
+            }
+        },
+    }
+}
+
+//======================================
+// Pattern utilities
+//======================================
+
+/// If `pattern` is `kind[]`, `kind[head]`, `Pattern[name, kind[]]`, or
+/// `Pattern[name, kind[head]]`, returns the pattern name and blank head.
+#[allow(clippy::type_complexity)]
+fn blank_pattern<'a>(
+    pattern: &'a Node,
+    kind: &str,
+) -> Option<(Option<(String, Span)>, Option<&'a Node>)> {
+    let (name, blank) = match pattern {
+        Node::Normal(head, elements) if head.is_system("Pattern") => {
+            match &elements[..] {
+                [Node::Symbol(name, span), blank] => {
+                    // Pattern names are always written without a context, so the
+                    // variable name is the symbol name.
+                    let name = name.rsplit('`').next().unwrap().to_owned();
+                    (Some((name, *span)), blank)
+                },
+                _ => return None,
+            }
+        },
+        _ => (None, pattern),
+    };
+
+    match blank {
+        Node::Normal(head, elements) if head.is_system(kind) => match &elements[..] {
+            [] => Some((name, None)),
+            [head] => Some((name, Some(head))),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn sequence_pattern(pattern: &Node) -> Option<SequencePattern<'_>> {
+    if let Some((name, head)) = blank_pattern(pattern, "BlankSequence") {
+        return Some(SequencePattern {
+            name,
+            min_length: 1,
+            head,
+        });
+    }
+
+    if let Some((name, head)) = blank_pattern(pattern, "BlankNullSequence") {
+        return Some(SequencePattern {
+            name,
+            min_length: 0,
+            head,
+        });
+    }
+
+    None
+}
+
+/// Format `pattern` using Wolfram Language syntax, for use in error messages.
+fn display(pattern: &Node) -> String {
+    fn symbol_name(name: &str) -> &str {
+        name.strip_prefix("System`")
+            .or_else(|| name.strip_prefix("Global`"))
+            .unwrap_or(name)
+    }
+
+    fn join(elements: &[Node]) -> String {
+        elements.iter().map(display).collect::<Vec<_>>().join(", ")
+    }
+
+    for (kind, underscores) in [
+        ("Blank", "_"),
+        ("BlankSequence", "__"),
+        ("BlankNullSequence", "___"),
+    ] {
+        if let Some((name, head)) = blank_pattern(pattern, kind) {
+            let name = name.map(|(name, _)| name).unwrap_or_default();
+            let head = head.map(display).unwrap_or_default();
+
+            return format!("{}{}{}", name, underscores, head);
+        }
+    }
+
+    match pattern {
+        Node::Symbol(name, _) => symbol_name(name).to_owned(),
+        Node::Integer(value) => value.to_string(),
+        Node::Real(value) => format!("{:?}", value),
+        Node::String(value) => format!("{:?}", value),
+        Node::Interpolated(tokens) => format!("#({})", tokens),
+        Node::Normal(head, elements) => {
+            if head.is_system("List") {
+                format!("{{{}}}", join(elements))
+            } else if head.is_system("Rule") && elements.len() == 2 {
+                format!("{} -> {}", display(&elements[0]), display(&elements[1]))
+            } else if head.is_system("RuleDelayed") && elements.len() == 2 {
+                format!("{} :> {}", display(&elements[0]), display(&elements[1]))
+            } else {
+                format!("{}[{}]", display(head), join(elements))
+            }
+        },
+    }
+}
@@ -1,4 +1,9 @@
+mod arg;
+mod data_store;
 mod export;
+mod expr_match;
+mod system_symbols;
+mod wl;
 
 
 use proc_macro::TokenStream;
@@ -111,3 +116,77 @@ pub fn export(
         Err(err) => err.into_compile_error().into(),
     }
 }
+
+//======================================
+// wolfram_library_link::wl!
+//======================================
+
+#[proc_macro]
+pub fn wl(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    match self::wl::wl(input.into()) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
+//======================================
+// wolfram_library_link::expr_match!
+//======================================
+
+#[proc_macro]
+pub fn expr_match(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as self::expr_match::ExprMatchInput);
+
+    match self::expr_match::expr_match(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
+//======================================
+// #[derive(IntoDataStore, FromDataStore)]
+//======================================
+
+#[proc_macro_derive(IntoDataStore)]
+pub fn derive_into_data_store(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input: syn::DeriveInput = syn::parse_macro_input!(input);
+
+    match self::data_store::derive_into_data_store(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
+#[proc_macro_derive(FromDataStore)]
+pub fn derive_from_data_store(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input: syn::DeriveInput = syn::parse_macro_input!(input);
+
+    match self::data_store::derive_from_data_store(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
+//======================================
+// #[derive(FromArg, IntoArg)]
+//======================================
+
+#[proc_macro_derive(FromArg, attributes(arg))]
+pub fn derive_from_arg(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input: syn::DeriveInput = syn::parse_macro_input!(input);
+
+    match self::arg::derive_from_arg(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
+#[proc_macro_derive(IntoArg, attributes(arg))]
+pub fn derive_into_arg(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input: syn::DeriveInput = syn::parse_macro_input!(input);
+
+    match self::arg::derive_into_arg(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
@@ -0,0 +1,67 @@
+//! Names of common symbols in the ``System` `` context.
+//!
+//! `wl!` resolves an unqualified symbol name that begins with an uppercase letter or `$`
+//! to the ``System` `` context only if it is listed here, so that a misspelled or
+//! user-defined name is never silently created in ``System` ``. Other ``System` ``
+//! symbols can be written using an explicit context, e.g. `System::ImageResize`.
+
+/// Sorted list of symbol names, searched using [`slice::binary_search()`].
+#[rustfmt::skip]
+pub(crate) const SYSTEM_SYMBOLS: &[&str] = &[
+    "$Aborted", "$Context", "$ContextPath", "$Failed", "$FrontEnd", "$Input", "$Line",
+    "$MachineEpsilon", "$MachinePrecision", "$MaxMachineNumber", "$MinMachineNumber",
+    "$OperatingSystem", "$Packages", "$ProcessorCount", "$SystemID", "$TimeZone",
+    "$Version", "$VersionNumber", "Abort", "AbortProtect", "Abs", "Accumulate", "AddTo",
+    "All", "AllTrue", "And", "Annotation", "Answer", "Apart", "Append", "AppendTo",
+    "Apply", "ArcCos", "ArcSin", "ArcTan", "Arg", "Array", "ArrayDepth", "ArrayQ",
+    "Assert", "Association", "AssociationQ", "AssociationThread", "Attributes",
+    "Automatic", "BinaryDeserialize", "BinarySerialize", "Blank", "BlankNullSequence",
+    "BlankSequence", "Block", "Boole", "BooleanQ", "Break", "ByteArray", "Cases", "Catch",
+    "Ceiling", "Characters", "Check", "Chop", "Circle", "Clear", "ClearAll", "Clip",
+    "Close", "Complement", "Complex", "ComplexInfinity", "CompoundExpression",
+    "Condition", "ConstantArray", "Continue", "Cos", "Count", "CreateDataStructure",
+    "Cross", "Cuboid", "DateObject", "DateString", "Decrement", "Delete", "DeleteCases",
+    "DeleteDuplicates", "Depth", "Derivative", "Det", "DiagonalMatrix", "Dimensions",
+    "DirectedInfinity", "Disk", "Divide", "Do", "Dot", "Drop", "E", "Echo",
+    "EchoFunction", "Element", "EndOfFile", "Entity", "Equal", "Evaluate",
+    "EvaluatePacket", "Except", "Exp", "Expand", "ExpandAll", "Export", "Extract",
+    "Factor", "Factorial", "Failure", "False", "FileNames", "Find", "First",
+    "FirstPosition", "Flatten", "Floor", "Fold", "FoldList", "For", "FreeQ",
+    "FromCharacterCode", "FullForm", "Function", "Gather", "GatherBy", "Graphics",
+    "Graphics3D", "Greater", "GreaterEqual", "Head", "Hold", "HoldAll", "HoldComplete",
+    "HoldFirst", "HoldForm", "HoldPattern", "HoldRest", "I", "IdentityMatrix", "If", "Im",
+    "Image", "Image3D", "ImageData", "ImageDimensions", "Import", "In", "Increment",
+    "Indeterminate", "Infinity", "Inner", "Insert", "Integer", "IntegerDigits",
+    "IntegerQ", "Integrate", "Interpolation", "Intersection", "Inverse", "Join", "Key",
+    "KeyDrop", "KeyExistsQ", "KeySelect", "KeySort", "KeyTake", "KeyValueMap", "Keys",
+    "Last", "Length", "Less", "LessEqual", "LibraryDataType", "LibraryFunction",
+    "LibraryFunctionError", "LibraryFunctionLoad", "LibraryFunctionUnload", "Line",
+    "LinkObject", "List", "ListQ", "Listable", "Log", "Lookup", "MachinePrecision", "Map",
+    "MapAt", "MapIndexed", "MapThread", "MatchQ", "MatrixQ", "Max", "Mean", "Median",
+    "Message", "MessageName", "Min", "Minus", "Missing", "Mod", "Module", "Most", "N",
+    "Nest", "NestList", "NestWhile", "None", "Norm", "Normal", "Not", "Null", "NumberQ",
+    "NumericArray", "NumericArrayQ", "NumericQ", "OptionValue", "Optional", "Options",
+    "OptionsPattern", "Or", "Order", "Ordering", "Out", "Outer", "Part", "Partition",
+    "Pattern", "PatternTest", "Permutations", "Pi", "Placeholder", "Plot", "Plot3D",
+    "Plus", "Point", "Polygon", "Position", "Power", "Prepend", "PrependTo", "Print",
+    "Product", "Protect", "Quantity", "Quiet", "Quotient", "Random", "RandomChoice",
+    "RandomInteger", "RandomReal", "RandomSample", "Range", "Rational", "Re", "Real",
+    "Rectangle", "ReleaseHold", "Repeated", "RepeatedNull", "Replace", "ReplaceAll",
+    "ReplacePart", "ReplaceRepeated", "Rest", "Return", "Reverse", "Round", "Rule",
+    "RuleDelayed", "SameQ", "Scan", "Select", "Sequence", "Set", "SetAttributes",
+    "SetDelayed", "Short", "Sign", "Simplify", "Sin", "Slot", "SlotSequence", "Sort",
+    "SortBy", "Span", "SparseArray", "Sphere", "Split", "Sqrt", "String",
+    "StringContainsQ", "StringDrop", "StringJoin", "StringLength", "StringMatchQ",
+    "StringPosition", "StringQ", "StringReplace", "StringReverse", "StringRiffle",
+    "StringSplit", "StringStartsQ", "StringTake", "StringTemplate", "Subtract",
+    "SubtractFrom", "Sum", "Switch", "Symbol", "SymbolName", "Table", "Take", "Tally",
+    "Tan", "Throw", "Timing", "ToCharacterCode", "ToExpression", "ToString", "Total",
+    "Transpose", "True", "Tuples", "Unequal", "Unevaluated", "Union", "Unique",
+    "Unprotect", "UnsameQ", "ValueQ", "Values", "Variance", "VectorQ", "Which", "While",
+    "With",
+];
+
+/// Returns `true` if `name` is a known ``System` `` symbol name.
+pub(crate) fn is_system_symbol(name: &str) -> bool {
+    SYSTEM_SYMBOLS.binary_search(&name).is_ok()
+}
@@ -0,0 +1,992 @@
+//! Implementation of the `wl!` macro, which parses Wolfram Language syntax into code
+//! that constructs an `Expr`.
+//!
+//! The macro input is tokenized by the Rust lexer, so the supported syntax is the subset
+//! of Wolfram Language syntax that is also made up of valid Rust tokens. Notably, the
+//! backtick context separator cannot be used, so `Context::name` is used instead.
+
+use std::iter::Peekable;
+
+use proc_macro2::{
+    Delimiter, Literal, Punct, Spacing, Span, TokenStream as TokenStream2, TokenTree,
+};
+
+use quote::quote;
+use syn::Error;
+
+use crate::system_symbols::is_system_symbol;
+
+pub(crate) fn wl(input: TokenStream2) -> Result<TokenStream2, Error> {
+    let expr = parse(input, Span::call_site())?;
+
+    Ok(expr.to_tokens())
+}
+
+/// Parse `input` as a single Wolfram Language expression.
+///
+/// `span` is used for errors that occur at the end of the input.
+pub(crate) fn parse(input: TokenStream2, span: Span) -> Result<Node, Error> {
+    if input.is_empty() {
+        return Err(Error::new(span, "expected Wolfram Language expression"));
+    }
+
+    let mut parser = Parser::new(input, span);
+
+    let expr = parser.parse_expr(0)?;
+    parser.expect_end()?;
+
+    Ok(expr)
+}
+
+//======================================
+// AST
+//======================================
+
+pub(crate) enum Node {
+    /// An absolute symbol name, e.g. ``System`List``, and the span it was parsed from.
+    Symbol(String, Span),
+    Integer(i64),
+    Real(f64),
+    String(String),
+    Normal(Box<Node>, Vec<Node>),
+    /// A `#var` or `#(expr)` Rust value.
+    Interpolated(TokenStream2),
+}
+
+impl Node {
+    fn system(name: &str) -> Node {
+        Node::Symbol(format!("System`{}", name), Span::call_site())
+    }
+
+    fn normal(head: &str, elements: Vec<Node>) -> Node {
+        Node::Normal(Box::new(Node::system(head)), elements)
+    }
+
+    /// Construct `head[lhs, rhs]`, flattening `lhs` if it already has head `head`.
+    fn flat(head: &str, lhs: Node, rhs: Node) -> Node {
+        let mut elements = match lhs {
+            Node::Normal(lhs_head, elements) if lhs_head.is_system(head) => elements,
+            lhs => vec![lhs],
+        };
+
+        elements.push(rhs);
+
+        Node::normal(head, elements)
+    }
+
+    pub(crate) fn is_system(&self, name: &str) -> bool {
+        match self {
+            Node::Symbol(sym, _) => sym.strip_prefix("System`") == Some(name),
+            _ => false,
+        }
+    }
+
+    fn negate(self) -> Node {
+        match self {
+            Node::Integer(value) => Node::Integer(-value),
+            Node::Real(value) => Node::Real(-value),
+            other => Node::normal("Times", vec![Node::Integer(-1), other]),
+        }
+    }
+
+    pub(crate) fn to_tokens(&self) -> TokenStream2 {
+        let expr = quote! { ::wolfram_library_link::expr::Expr };
+
+        match self {
+            Node::Symbol(name, _) => quote! {
+                #expr::symbol(::wolfram_library_link::expr::Symbol::new(#name))
+            },
+            Node::Integer(value) => {
+                let value = Literal::i64_suffixed(*value);
+                quote! { #expr::from(#value) }
+            },
+            Node::Real(value) => {
+                let value = Literal::f64_suffixed(*value);
+                quote! { #expr::real(#value) }
+            },
+            Node::String(value) => quote! { #expr::string(#value) },
+            Node::Normal(head, elements) => {
+                let head = head.to_tokens();
+                let elements = elements.iter().map(Node::to_tokens);
+
+                quote! { #expr::normal(#head, ::std::vec![#(#elements),*]) }
+            },
+            Node::Interpolated(tokens) => quote! { #expr::from(#tokens) },
+        }
+    }
+}
+
+//======================================
+// Tokens
+//======================================
+
+/// Multi-character operators, longest first. Rust tokenizes these as a sequence of
+/// single-character `Punct`s, which are merged back together by [`tokenize()`].
+const OPERATORS: &[&str] = &[
+    "===", "=!=", "->", ":>", ":=", "::", "==", "!=", "<=", ">=", "&&", "||", "<|", "|>",
+    "<>", "/@", "@@", "##",
+];
+
+enum Token {
+    Ident(proc_macro2::Ident),
+    Literal(Literal),
+    Op(String, Span),
+    Group(Delimiter, TokenStream2, Span),
+}
+
+impl Token {
+    fn span(&self) -> Span {
+        match self {
+            Token::Ident(ident) => ident.span(),
+            Token::Literal(lit) => lit.span(),
+            Token::Op(_, span) => *span,
+            Token::Group(_, _, span) => *span,
+        }
+    }
+
+    fn is_op(&self, op: &str) -> bool {
+        matches!(self, Token::Op(this, _) if this == op)
+    }
+}
+
+fn tokenize(input: TokenStream2) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut puncts: Vec<Punct> = Vec::new();
+
+    for tree in input {
+        let punct = match tree {
+            TokenTree::Punct(punct) => punct,
+            other => {
+                tokens.push(match other {
+                    TokenTree::Ident(ident) => Token::Ident(ident),
+                    TokenTree::Literal(lit) => Token::Literal(lit),
+                    TokenTree::Group(group) => {
+                        Token::Group(group.delimiter(), group.stream(), group.span())
+                    },
+                    TokenTree::Punct(_) => unreachable!(),
+                });
+                continue;
+            },
+        };
+
+        let is_joint = punct.spacing() == Spacing::Joint;
+        puncts.push(punct);
+
+        if !is_joint {
+            merge_operators(&mut tokens, std::mem::take(&mut puncts));
+        }
+    }
+
+    merge_operators(&mut tokens, puncts);
+
+    tokens
+}
+
+/// Split a run of adjacent punctuation characters into operators, preferring the
+/// longest operator at each position.
+fn merge_operators(tokens: &mut Vec<Token>, puncts: Vec<Punct>) {
+    let chars: String = puncts.iter().map(Punct::as_char).collect();
+
+    let mut index = 0;
+
+    while index < puncts.len() {
+        let rest = &chars[index..];
+
+        let len = OPERATORS
+            .iter()
+            .find(|op| rest.starts_with(*op))
+            .map(|op| op.len())
+            .unwrap_or(1);
+
+        tokens.push(Token::Op(rest[..len].to_owned(), puncts[index].span()));
+
+        index += len;
+    }
+}
+
+//======================================
+// Parser
+//======================================
+
+/// Binding powers of the supported operators, following the relative precedence of the
+/// corresponding Wolfram Language operators.
+mod bp {
+    pub const COMPOUND_EXPRESSION: u32 = 10;
+    pub const SET: u32 = 40;
+    pub const FUNCTION: u32 = 90;
+    pub const RULE: u32 = 120;
+    pub const ALTERNATIVES: u32 = 160;
+    pub const OR: u32 = 200;
+    pub const AND: u32 = 210;
+    pub const NOT: u32 = 230;
+    pub const COMPARISON: u32 = 290;
+    pub const PLUS: u32 = 310;
+    pub const TIMES: u32 = 400;
+    pub const MINUS: u32 = 480;
+    pub const POWER: u32 = 590;
+    pub const STRING_JOIN: u32 = 600;
+    pub const MAP: u32 = 620;
+    pub const PREFIX: u32 = 640;
+    pub const CALL: u32 = 1000;
+}
+
+struct Parser {
+    tokens: Peekable<std::vec::IntoIter<Token>>,
+    /// Span used for errors that occur at the end of the input.
+    end: Span,
+}
+
+impl Parser {
+    fn new(input: TokenStream2, end: Span) -> Self {
+        Parser {
+            tokens: tokenize(input).into_iter().peekable(),
+            end,
+        }
+    }
+
+    fn peek(&mut self) -> Option<&Token> {
+        self.tokens.peek()
+    }
+
+    fn peek_op(&mut self, op: &str) -> bool {
+        self.peek().is_some_and(|token| token.is_op(op))
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        self.tokens.next()
+    }
+
+    /// Span of the next token, or the end span if there are no more tokens.
+    fn span(&mut self) -> Span {
+        let end = self.end;
+        self.peek().map_or(end, Token::span)
+    }
+
+    fn expect_end(&mut self) -> Result<(), Error> {
+        match self.peek() {
+            None => Ok(()),
+            Some(token) => Err(Error::new(token.span(), "unexpected token")),
+        }
+    }
+
+    fn expect_op(&mut self, op: &str) -> Result<(), Error> {
+        if self.peek_op(op) {
+            self.next();
+            Ok(())
+        } else {
+            Err(Error::new(self.span(), format!("expected `{}`", op)))
+        }
+    }
+
+    /// Returns true if the next token cannot begin an expression.
+    fn at_expression_end(&mut self) -> bool {
+        match self.peek() {
+            None => true,
+            Some(Token::Op(op, _)) => {
+                !matches!(op.as_str(), "-" | "!" | "#" | "##" | "$" | "<|")
+            },
+            Some(_) => false,
+        }
+    }
+
+    //----------------------------------
+    // Expressions
+    //----------------------------------
+
+    fn parse_expr(&mut self, min_bp: u32) -> Result<Node, Error> {
+        // Operands written in parentheses are not flattened, so `(a + b) + c` is
+        // `Plus[Plus[a, b], c]`, as in the Wolfram Language.
+        let mut parenthesized = matches!(
+            self.peek(),
+            Some(Token::Group(Delimiter::Parenthesis, _, _))
+        );
+
+        let mut lhs = self.parse_prefix()?;
+
+        while let Some(token) = self.peek() {
+            let op: &str = match token {
+                Token::Group(Delimiter::Bracket, _, _) => {
+                    if bp::CALL < min_bp {
+                        break;
+                    }
+
+                    lhs = self.parse_call(lhs)?;
+                    parenthesized = false;
+                    continue;
+                },
+                Token::Op(op, _) => op,
+                _ => break,
+            };
+
+            let (l_bp, r_bp) = match infix_binding_power(op) {
+                Some(bps) => bps,
+                None => break,
+            };
+
+            if l_bp < min_bp {
+                break;
+            }
+
+            let op_span = token.span();
+            let op = op.to_owned();
+            self.next();
+
+            let lhs_parenthesized = std::mem::replace(&mut parenthesized, false);
+
+            let flat = |head: &str, lhs: Node, rhs: Node| {
+                if lhs_parenthesized {
+                    Node::normal(head, vec![lhs, rhs])
+                } else {
+                    Node::flat(head, lhs, rhs)
+                }
+            };
+
+            lhs = match op.as_str() {
+                ";" => {
+                    let rhs = if self.at_expression_end() {
+                        Node::system("Null")
+                    } else {
+                        self.parse_expr(r_bp)?
+                    };
+
+                    flat("CompoundExpression", lhs, rhs)
+                },
+                "&" => Node::normal("Function", vec![lhs]),
+                "=" => Node::normal("Set", vec![lhs, self.parse_expr(r_bp)?]),
+                ":=" => Node::normal("SetDelayed", vec![lhs, self.parse_expr(r_bp)?]),
+                "->" => Node::normal("Rule", vec![lhs, self.parse_expr(r_bp)?]),
+                ":>" => Node::normal("RuleDelayed", vec![lhs, self.parse_expr(r_bp)?]),
+                "|" => flat("Alternatives", lhs, self.parse_expr(r_bp)?),
+                "||" => flat("Or", lhs, self.parse_expr(r_bp)?),
+                "&&" => flat("And", lhs, self.parse_expr(r_bp)?),
+                "+" => flat("Plus", lhs, self.parse_expr(r_bp)?),
+                "-" => flat("Plus", lhs, self.parse_expr(r_bp)?.negate()),
+                "*" => flat("Times", lhs, self.parse_expr(r_bp)?),
+                "/" => {
+                    let rhs = self.parse_expr(r_bp)?;
+                    flat(
+                        "Times",
+                        lhs,
+                        Node::normal("Power", vec![rhs, Node::Integer(-1)]),
+                    )
+                },
+                "^" => Node::normal("Power", vec![lhs, self.parse_expr(r_bp)?]),
+                "<>" => flat("StringJoin", lhs, self.parse_expr(r_bp)?),
+                "/@" => Node::normal("Map", vec![lhs, self.parse_expr(r_bp)?]),
+                "@@" => Node::normal("Apply", vec![lhs, self.parse_expr(r_bp)?]),
+                "@" => Node::Normal(Box::new(lhs), vec![self.parse_expr(r_bp)?]),
+                _ => {
+                    let head = comparison_head(&op).unwrap();
+                    let rhs = self.parse_expr(r_bp)?;
+
+                    match lhs {
+                        lhs if lhs_parenthesized => Node::normal(head, vec![lhs, rhs]),
+                        Node::Normal(ref lhs_head, _) if lhs_head.is_system(head) => {
+                            Node::flat(head, lhs, rhs)
+                        },
+                        Node::Normal(ref lhs_head, _)
+                            if COMPARISONS
+                                .iter()
+                                .any(|(_, other)| lhs_head.is_system(other)) =>
+                        {
+                            return Err(Error::new(
+                                op_span,
+                                "mixing different comparison operators is not supported",
+                            ));
+                        },
+                        lhs => Node::normal(head, vec![lhs, rhs]),
+                    }
+                },
+            };
+        }
+
+        Ok(lhs)
+    }
+
+    fn parse_prefix(&mut self) -> Result<Node, Error> {
+        let span = self.span();
+
+        let token = match self.next() {
+            Some(token) => token,
+            None => return Err(Error::new(span, "expected expression")),
+        };
+
+        let node = match token {
+            Token::Ident(ident) => {
+                self.parse_symbol_or_pattern(ident.to_string(), span)?
+            },
+            Token::Literal(lit) => parse_literal(lit)?,
+            Token::Group(Delimiter::Brace, stream, span) => {
+                Node::normal("List", parse_sequence(stream, span)?)
+            },
+            Token::Group(Delimiter::Parenthesis, stream, span) => {
+                if stream.is_empty() {
+                    return Err(Error::new(span, "expected expression"));
+                }
+
+                let mut parser = Parser::new(stream, span);
+                let expr = parser.parse_expr(0)?;
+                parser.expect_end()?;
+                expr
+            },
+            Token::Op(op, span) => match op.as_str() {
+                "-" => self.parse_expr(bp::MINUS)?.negate(),
+                "!" => Node::normal("Not", vec![self.parse_expr(bp::NOT)?]),
+                "$" => match self.next() {
+                    Some(Token::Ident(ident)) => {
+                        self.parse_symbol_or_pattern(format!("${}", ident), span)?
+                    },
+                    _ => return Err(Error::new(span, "expected symbol name after `$`")),
+                },
+                "#" => self.parse_slot_or_interpolation(span)?,
+                "##" => {
+                    let index = self.parse_slot_index()?;
+                    Node::normal("SlotSequence", vec![Node::Integer(index)])
+                },
+                "<|" => {
+                    let mut elements = Vec::new();
+
+                    while !self.peek_op("|>") {
+                        elements.push(self.parse_expr(bp::COMPOUND_EXPRESSION + 1)?);
+
+                        if !self.peek_op("|>") {
+                            self.expect_op(",")?;
+                        }
+                    }
+
+                    self.expect_op("|>")?;
+
+                    Node::normal("Association", elements)
+                },
+                _ => {
+                    return Err(Error::new(span, format!("unexpected operator `{}`", op)))
+                },
+            },
+            Token::Group(_, _, span) => {
+                return Err(Error::new(span, "unexpected token"));
+            },
+        };
+
+        Ok(node)
+    }
+
+    /// Parse `f[args...]` or `expr[[parts...]]`.
+    fn parse_call(&mut self, head: Node) -> Result<Node, Error> {
+        let (stream, span) = match self.next() {
+            Some(Token::Group(Delimiter::Bracket, stream, span)) => (stream, span),
+            _ => unreachable!(),
+        };
+
+        let mut trees = stream.clone().into_iter();
+
+        // `expr[[..]]` is tokenized as a bracket group containing a single bracket group.
+        if let (Some(TokenTree::Group(inner)), None) = (trees.next(), trees.next()) {
+            if inner.delimiter() == Delimiter::Bracket {
+                let mut elements = vec![head];
+                elements.extend(parse_sequence(inner.stream(), inner.span())?);
+
+                return Ok(Node::normal("Part", elements));
+            }
+        }
+
+        Ok(Node::Normal(Box::new(head), parse_sequence(stream, span)?))
+    }
+
+    /// Parse a symbol, optionally with a `Context::` prefix, or a pattern like `x_`,
+    /// `x__Integer`, or `_`.
+    fn parse_symbol_or_pattern(
+        &mut self,
+        first: String,
+        span: Span,
+    ) -> Result<Node, Error> {
+        let mut segments = vec![first];
+
+        while self.peek_op("::") {
+            self.next();
+
+            let segment = match self.next() {
+                Some(Token::Ident(ident)) => ident.to_string(),
+                Some(Token::Op(op, span)) if op == "$" => match self.next() {
+                    Some(Token::Ident(ident)) => format!("${}", ident),
+                    _ => return Err(Error::new(span, "expected symbol name after `$`")),
+                },
+                _ => {
+                    return Err(Error::new(
+                        self.span(),
+                        "expected symbol name after `::`",
+                    ))
+                },
+            };
+
+            segments.push(segment);
+        }
+
+        let segments: Vec<String> = segments
+            .into_iter()
+            .map(|segment| segment.trim_start_matches("r#").to_owned())
+            .collect();
+
+        // Wolfram Language symbol names cannot contain underscores, so an identifier
+        // containing one is a pattern. In `x_Context::Head`, the context applies to the
+        // blank head.
+        let (first, rest) = segments.split_first().unwrap();
+
+        let (name, blank) = match first.find('_') {
+            Some(index) => (&first[..index], &first[index..]),
+            None => return resolve_symbol(&segments, span),
+        };
+
+        let underscores = blank.len() - blank.trim_start_matches('_').len();
+
+        let blank_kind = match underscores {
+            1 => "Blank",
+            2 => "BlankSequence",
+            3 => "BlankNullSequence",
+            _ => return Err(Error::new(span, "invalid pattern")),
+        };
+
+        let mut head_segments = vec![blank[underscores..].to_owned()];
+        head_segments.extend(rest.iter().cloned());
+
+        let blank = if head_segments == [""] {
+            Node::normal(blank_kind, vec![])
+        } else {
+            Node::normal(blank_kind, vec![resolve_symbol(&head_segments, span)?])
+        };
+
+        if name.is_empty() {
+            return Ok(blank);
+        }
+
+        Ok(Node::normal("Pattern", vec![
+            resolve_symbol(&[name.to_owned()], span)?,
+            blank,
+        ]))
+    }
+
+    /// Parse the remainder of `#`, `#n`, `#var`, or `#(expr)`.
+    fn parse_slot_or_interpolation(&mut self, span: Span) -> Result<Node, Error> {
+        match self.peek() {
+            Some(Token::Ident(_)) => match self.next() {
+                Some(Token::Ident(ident)) => Ok(Node::Interpolated(quote! { #ident })),
+                _ => unreachable!(),
+            },
+            Some(Token::Group(Delimiter::Parenthesis, _, _)) => match self.next() {
+                Some(Token::Group(_, stream, group_span)) => {
+                    if stream.is_empty() {
+                        return Err(Error::new(group_span, "expected Rust expression"));
+                    }
+
+                    Ok(Node::Interpolated(quote! { (#stream) }))
+                },
+                _ => unreachable!(),
+            },
+            _ => {
+                let index = self.parse_slot_index().map_err(|_| {
+                    Error::new(span, "expected slot number, identifier, or `(..)`")
+                })?;
+
+                Ok(Node::normal("Slot", vec![Node::Integer(index)]))
+            },
+        }
+    }
+
+    /// Parse the optional positive integer following `#` or `##`, defaulting to 1.
+    fn parse_slot_index(&mut self) -> Result<i64, Error> {
+        let lit = match self.peek() {
+            Some(Token::Literal(lit)) => lit.clone(),
+            _ => return Ok(1),
+        };
+
+        self.next();
+
+        match syn::Lit::new(lit) {
+            syn::Lit::Int(int) if int.suffix().is_empty() => int.base10_parse(),
+            other => Err(Error::new(other.span(), "expected slot number")),
+        }
+    }
+}
+
+/// Resolve the `::`-separated `segments` of a symbol into an absolute symbol.
+///
+/// Symbols without an explicit context are in ``System` `` if their name is a known
+/// ``System` `` symbol, and in ``Global` `` if their name begins with a lowercase letter.
+/// Any other name must be written with an explicit context, so that it is not created
+/// in ``System` `` by mistake.
+fn resolve_symbol(segments: &[String], span: Span) -> Result<Node, Error> {
+    if segments
+        .iter()
+        .any(|segment| segment.is_empty() || segment.contains('_'))
+    {
+        return Err(Error::new(span, "invalid symbol name"));
+    }
+
+    let (name, context) = segments.split_last().unwrap();
+
+    let node = if !context.is_empty() {
+        Node::Symbol(format!("{}`{}", context.join("`"), name), span)
+    } else if is_system_symbol(name) {
+        Node::Symbol(format!("System`{}", name), span)
+    } else if name.starts_with(|c: char| c.is_ascii_uppercase() || c == '$') {
+        return Err(Error::new(
+            span,
+            format!(
+                "`{}` is not a known System` symbol; write its context explicitly, \
+                 e.g. `Global::{}` or `System::{}`",
+                name, name, name
+            ),
+        ));
+    } else {
+        Node::Symbol(format!("Global`{}", name), span)
+    };
+
+    Ok(node)
+}
+
+/// Parse a comma-separated sequence of expressions, e.g. function arguments or list
+/// elements.
+fn parse_sequence(stream: TokenStream2, span: Span) -> Result<Vec<Node>, Error> {
+    let mut parser = Parser::new(stream, span);
+    let mut elements = Vec::new();
+
+    if parser.peek().is_none() {
+        return Ok(elements);
+    }
+
+    loop {
+        elements.push(parser.parse_expr(bp::COMPOUND_EXPRESSION + 1)?);
+
+        if parser.peek().is_none() {
+            break;
+        }
+
+        parser.expect_op(",")?;
+    }
+
+    Ok(elements)
+}
+
+fn parse_literal(lit: Literal) -> Result<Node, Error> {
+    match syn::Lit::new(lit) {
+        syn::Lit::Str(string) => Ok(Node::String(string.value())),
+        syn::Lit::Int(int) if int.suffix().is_empty() => {
+            Ok(Node::Integer(int.base10_parse()?))
+        },
+        syn::Lit::Float(float) if float.suffix().is_empty() => {
+            let value: f64 = float.base10_parse()?;
+
+            if !value.is_finite() {
+                return Err(Error::new(float.span(), "real literal is out of range"));
+            }
+
+            Ok(Node::Real(value))
+        },
+        syn::Lit::Int(int) => Err(Error::new(
+            int.span(),
+            "integer literals cannot have a type suffix",
+        )),
+        syn::Lit::Float(float) => Err(Error::new(
+            float.span(),
+            "real literals cannot have a type suffix",
+        )),
+        other => Err(Error::new(other.span(), "unsupported literal")),
+    }
+}
+
+//======================================
+// Operators
+//======================================
+
+const COMPARISONS: &[(&str, &str)] = &[
+    ("===", "SameQ"),
+    ("=!=", "UnsameQ"),
+    ("==", "Equal"),
+    ("!=", "Unequal"),
+    ("<", "Less"),
+    (">", "Greater"),
+    ("<=", "LessEqual"),
+    (">=", "GreaterEqual"),
+];
+
+fn comparison_head(op: &str) -> Option<&'static str> {
+    COMPARISONS
+        .iter()
+        .find(|(this, _)| *this == op)
+        .map(|(_, head)| *head)
+}
+
+/// Returns the left and right binding power of the infix or postfix operator `op`.
+///
+/// Left-associative operators have a right binding power one greater than their left
+/// binding power.
+fn infix_binding_power(op: &str) -> Option<(u32, u32)> {
+    let bps = match op {
+        ";" => (bp::COMPOUND_EXPRESSION, bp::COMPOUND_EXPRESSION + 1),
+        "=" | ":=" => (bp::SET, bp::SET),
+        "&" => (bp::FUNCTION, bp::FUNCTION + 1),
+        "->" | ":>" => (bp::RULE, bp::RULE),
+        "|" => (bp::ALTERNATIVES, bp::ALTERNATIVES + 1),
+        "||" => (bp::OR, bp::OR + 1),
+        "&&" => (bp::AND, bp::AND + 1),
+        "+" | "-" => (bp::PLUS, bp::PLUS + 1),
+        "*" | "/" => (bp::TIMES, bp::TIMES + 1),
+        "^" => (bp::POWER, bp::POWER),
+        "<>" => (bp::STRING_JOIN, bp::STRING_JOIN + 1),
+        "/@" | "@@" => (bp::MAP, bp::MAP),
+        "@" => (bp::PREFIX, bp::PREFIX),
+        _ if comparison_head(op).is_some() => (bp::COMPARISON, bp::COMPARISON + 1),
+        _ => return None,
+    };
+
+    Some(bps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::system_symbols::SYSTEM_SYMBOLS;
+
+    /// Parse `input` and format the result using `FullForm`-like syntax.
+    fn parse_str(input: &str) -> Result<String, String> {
+        let input: TokenStream2 = input.parse().unwrap();
+
+        match parse(input, Span::call_site()) {
+            Ok(node) => Ok(full_form(&node)),
+            Err(err) => Err(err.to_string()),
+        }
+    }
+
+    fn full_form(node: &Node) -> String {
+        match node {
+            Node::Symbol(name, _) => name.clone(),
+            Node::Integer(value) => value.to_string(),
+            Node::Real(value) => format!("{:?}", value),
+            Node::String(value) => format!("{:?}", value),
+            Node::Normal(head, elements) => {
+                let elements: Vec<String> = elements.iter().map(full_form).collect();
+
+                format!("{}[{}]", full_form(head), elements.join(", "))
+            },
+            Node::Interpolated(tokens) => format!("#({})", tokens),
+        }
+    }
+
+    fn assert_parses(input: &str, expected: &str) {
+        assert_eq!(
+            parse_str(input).as_deref(),
+            Ok(expected),
+            "input: {}",
+            input
+        );
+    }
+
+    fn assert_error(input: &str, expected: &str) {
+        assert_eq!(
+            parse_str(input),
+            Err(expected.to_owned()),
+            "input: {}",
+            input
+        );
+    }
+
+    #[test]
+    fn test_operator_precedence() {
+        assert_parses("1 + 2 * 3", "System`Plus[1, System`Times[2, 3]]");
+        assert_parses("(1 + 2) * 3", "System`Times[System`Plus[1, 2], 3]");
+        assert_parses("1 + 2 + 3", "System`Plus[1, 2, 3]");
+        assert_parses(
+            "a * (b * c)",
+            "System`Times[Global`a, System`Times[Global`b, Global`c]]",
+        );
+        assert_parses(
+            "a / b",
+            "System`Times[Global`a, System`Power[Global`b, -1]]",
+        );
+
+        // `^` is right associative.
+        assert_parses(
+            "a ^ b ^ c",
+            "System`Power[Global`a, System`Power[Global`b, Global`c]]",
+        );
+
+        assert_parses(
+            "a -> b + c",
+            "System`Rule[Global`a, System`Plus[Global`b, Global`c]]",
+        );
+        assert_parses(
+            "a == b && c || d",
+            "System`Or[System`And[System`Equal[Global`a, Global`b], Global`c], Global`d]",
+        );
+        assert_parses(
+            "x = y; z",
+            "System`CompoundExpression[System`Set[Global`x, Global`y], Global`z]",
+        );
+        assert_parses("f /@ {1, 2}", "System`Map[Global`f, System`List[1, 2]]");
+        assert_parses("f[x][[1]]", "System`Part[Global`f[Global`x], 1]");
+        assert_parses(
+            "<|\"a\" -> 1|>",
+            "System`Association[System`Rule[\"a\", 1]]",
+        );
+    }
+
+    #[test]
+    fn test_parenthesized_operands() {
+        // Parenthesized operands are not flattened into the enclosing expression.
+        assert_parses(
+            "(a + b) + c",
+            "System`Plus[System`Plus[Global`a, Global`b], Global`c]",
+        );
+        assert_parses(
+            "(a; b); c",
+            "System`CompoundExpression[\
+                System`CompoundExpression[Global`a, Global`b], \
+                Global`c\
+            ]",
+        );
+        assert_parses(
+            "(a == b) == c",
+            "System`Equal[System`Equal[Global`a, Global`b], Global`c]",
+        );
+        assert_parses(
+            "(a < b) == c",
+            "System`Equal[System`Less[Global`a, Global`b], Global`c]",
+        );
+
+        // Only the parenthesized operand itself is exempt.
+        assert_parses(
+            "(a + b) + c + d",
+            "System`Plus[System`Plus[Global`a, Global`b], Global`c, Global`d]",
+        );
+        assert_parses(
+            "(f)[x] + y + z",
+            "System`Plus[Global`f[Global`x], Global`y, Global`z]",
+        );
+    }
+
+    #[test]
+    fn test_unary_minus() {
+        assert_parses("-2", "-2");
+        assert_parses("-2.5", "-2.5");
+        assert_parses("-a", "System`Times[-1, Global`a]");
+
+        // Unary minus binds less tightly than `^`.
+        assert_parses("-a ^ 2", "System`Times[-1, System`Power[Global`a, 2]]");
+
+        // Subtraction is the addition of a negated value.
+        assert_parses("a - 2", "System`Plus[Global`a, -2]");
+        assert_parses("a - b", "System`Plus[Global`a, System`Times[-1, Global`b]]");
+        assert_parses(
+            "a - -b",
+            "System`Plus[Global`a, System`Times[-1, System`Times[-1, Global`b]]]",
+        );
+        assert_parses(
+            "a - b - 1",
+            "System`Plus[Global`a, System`Times[-1, Global`b], -1]",
+        );
+    }
+
+    #[test]
+    fn test_comparisons() {
+        assert_parses("a == b == c", "System`Equal[Global`a, Global`b, Global`c]");
+        assert_parses("a < b", "System`Less[Global`a, Global`b]");
+        assert_parses("a === b", "System`SameQ[Global`a, Global`b]");
+
+        assert_error(
+            "a < b <= c",
+            "mixing different comparison operators is not supported",
+        );
+        assert_error(
+            "a == b != c",
+            "mixing different comparison operators is not supported",
+        );
+    }
+
+    #[test]
+    fn test_patterns() {
+        assert_parses("x_", "System`Pattern[Global`x, System`Blank[]]");
+        assert_parses("_", "System`Blank[]");
+        assert_parses("_Integer", "System`Blank[System`Integer]");
+        assert_parses(
+            "x_Integer",
+            "System`Pattern[Global`x, System`Blank[System`Integer]]",
+        );
+        assert_parses("xs__", "System`Pattern[Global`xs, System`BlankSequence[]]");
+        assert_parses(
+            "xs__String",
+            "System`Pattern[Global`xs, System`BlankSequence[System`String]]",
+        );
+        assert_parses(
+            "xs___",
+            "System`Pattern[Global`xs, System`BlankNullSequence[]]",
+        );
+        assert_parses(
+            "x_Developer::DataStore",
+            "System`Pattern[Global`x, System`Blank[Developer`DataStore]]",
+        );
+        assert_parses(
+            "f[x_, y_] := x + y",
+            "System`SetDelayed[\
+                Global`f[\
+                    System`Pattern[Global`x, System`Blank[]], \
+                    System`Pattern[Global`y, System`Blank[]]\
+                ], \
+                System`Plus[Global`x, Global`y]\
+            ]",
+        );
+
+        assert_error("x____", "invalid pattern");
+    }
+
+    #[test]
+    fn test_interpolation() {
+        assert_parses("#value", "#(value)");
+        assert_parses("#(a + 1)", "#((a + 1))");
+        assert_parses(
+            "f[#value, #(items.len())]",
+            "Global`f[#(value), #((items . len ()))]",
+        );
+
+        // `#` followed by a number or nothing is a `Slot`.
+        assert_parses(
+            "# + #2 &",
+            "System`Function[System`Plus[System`Slot[1], System`Slot[2]]]",
+        );
+        assert_parses("##", "System`SlotSequence[1]");
+
+        assert_error("#()", "expected Rust expression");
+    }
+
+    #[test]
+    fn test_context_resolution() {
+        // Symbols without a context are in System` if they are known System` symbols,
+        // and Global` if they start with a lowercase letter.
+        assert_parses("Plus", "System`Plus");
+        assert_parses("$Version", "System`$Version");
+        assert_parses("myVariable", "Global`myVariable");
+
+        // Other names are not created in System` by mistake.
+        assert_error(
+            "MyFunction[x]",
+            "`MyFunction` is not a known System` symbol; write its context explicitly, \
+             e.g. `Global::MyFunction` or `System::MyFunction`",
+        );
+        assert_error(
+            "x_MyHead",
+            "`MyHead` is not a known System` symbol; write its context explicitly, \
+             e.g. `Global::MyHead` or `System::MyHead`",
+        );
+        assert_parses("Global::MyFunction[x]", "Global`MyFunction[Global`x]");
+        assert_parses("System::ImageResize", "System`ImageResize");
+
+        // `is_system_symbol()` uses a binary search.
+        assert!(SYSTEM_SYMBOLS.windows(2).all(|pair| pair[0] < pair[1]));
+
+        // `::` separates the contexts of a symbol, like `` ` `` in the Wolfram Language.
+        assert_parses("Developer::DataStore", "Developer`DataStore");
+        assert_parses("MyPackage::Private::helper", "MyPackage`Private`helper");
+        assert_parses("Global::x", "Global`x");
+
+        assert_error("Developer::", "expected symbol name after `::`");
+    }
+}